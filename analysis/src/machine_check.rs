@@ -310,7 +310,7 @@ impl TypeChecker {
 
         for m in module.statements {
             match m {
-                ModuleStatement::SymbolDefinition(SymbolDefinition { name, value }) => {
+                ModuleStatement::SymbolDefinition(SymbolDefinition { name, value, .. }) => {
                     match value {
                         asm::SymbolValue::Machine(m) => {
                             match self.check_machine_type(m, &ctx.with_part(&name)) {
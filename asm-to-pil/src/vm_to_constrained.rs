@@ -775,6 +775,9 @@ impl<T: FieldElement> VMConverter<T> {
         match value {
             Expression::PublicReference(_, _) => panic!(),
             Expression::IndexAccess(_, _) => panic!(),
+            Expression::FieldAccess(_, _) => {
+                panic!("Field accesses are not supported in assignment values: {value}")
+            }
             Expression::FunctionCall(_, _) => panic!(),
             Expression::Reference(_, reference) => {
                 // TODO check it actually is a register
@@ -565,12 +565,39 @@ pub fn type_from_definition(
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SolvedTraitImpls {
     impls: BTreeMap<String, HashMap<Vec<Type>, ImplData>>,
+    /// All implementations of each trait function, including generic ones.
+    /// They are used to resolve trait function references whose type arguments
+    /// are only known at evaluation time.
+    candidates: BTreeMap<String, Vec<TraitImplCandidate>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct ImplData {
     index: usize,
     function: Arc<Expression>,
+    type_vars: HashMap<String, Type>,
+}
+
+/// An implementation of a trait function that can be selected for type arguments
+/// matching its type scheme.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TraitImplCandidate {
+    /// Index into the list of trait implementations provided by `Analyzed`.
+    pub index: usize,
+    /// The type scheme of the trait implementation, the type is a tuple of
+    /// the type arguments of the trait.
+    pub type_scheme: TypeScheme,
+    pub function: Arc<Expression>,
+}
+
+/// A trait implementation function resolved for concrete type arguments.
+pub struct ResolvedTraitFunction<'a> {
+    /// Index into the list of trait implementations provided by `Analyzed`.
+    pub index: usize,
+    pub function: &'a Expression,
+    /// The values of the type variables of the trait implementation.
+    /// This is empty unless the implementation is generic.
+    pub type_vars: HashMap<String, Type>,
 }
 
 impl SolvedTraitImpls {
@@ -587,22 +614,15 @@ impl SolvedTraitImpls {
         &self,
         trait_function_name: &str,
         type_args: &[Type],
-    ) -> Option<&Expression> {
+    ) -> Option<ResolvedTraitFunction<'_>> {
         self.impls
             .get(trait_function_name)
             .and_then(|map| map.get(type_args))
-            .map(|impl_data| impl_data.function.as_ref())
-    }
-
-    /// Returns the correct trait impl function for a given trait function name and type arguments.
-    /// It returns just the function of the trait impl.
-    pub fn resolve_trait_function(
-        &self,
-        trait_function_name: &str,
-        type_args: &[Type],
-    ) -> &Expression {
-        self.try_resolve_trait_function(trait_function_name, type_args)
-            .unwrap()
+            .map(|impl_data| ResolvedTraitFunction {
+                index: impl_data.index,
+                function: impl_data.function.as_ref(),
+                type_vars: impl_data.type_vars.clone(),
+            })
     }
 
     pub fn insert(
@@ -611,18 +631,37 @@ impl SolvedTraitImpls {
         type_args: Vec<Type>,
         index: usize,
         function: Arc<Expression>,
+        type_vars: HashMap<String, Type>,
     ) {
-        let existing = self
-            .impls
-            .entry(trait_function_name)
-            .or_default()
-            .insert(type_args, ImplData { index, function });
+        let existing = self.impls.entry(trait_function_name).or_default().insert(
+            type_args,
+            ImplData {
+                index,
+                function,
+                type_vars,
+            },
+        );
         assert!(
             existing.is_none(),
             "Duplicate trait impl for the same type arguments."
         );
     }
 
+    /// Returns all implementations of the given trait function, in source order.
+    pub fn candidates(&self, trait_function_name: &str) -> &[TraitImplCandidate] {
+        self.candidates
+            .get(trait_function_name)
+            .map(|c| c.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn add_candidate(&mut self, trait_function_name: String, candidate: TraitImplCandidate) {
+        self.candidates
+            .entry(trait_function_name)
+            .or_default()
+            .push(candidate);
+    }
+
     /// Update the data structure after a certain set of trait impls have been removed.
     /// This just updates the `index` fields.
     /// Assumes that `to_remove` is sorted.
@@ -642,6 +681,17 @@ impl SolvedTraitImpls {
                 })
                 .collect();
         }
+        for candidates in self.candidates.values_mut() {
+            candidates.retain_mut(
+                |candidate| match to_remove.binary_search(&candidate.index) {
+                    Ok(_) => false,
+                    Err(index) => {
+                        candidate.index -= index;
+                        true
+                    }
+                },
+            );
+        }
     }
 }
 
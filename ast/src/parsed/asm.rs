@@ -48,10 +48,20 @@ impl ModuleStatement {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SymbolDefinition {
+    pub source: SourceRef,
     pub name: String,
     pub value: SymbolValue,
 }
 
+impl SourceReference for SymbolDefinition {
+    fn source_reference(&self) -> &SourceRef {
+        &self.source
+    }
+    fn source_reference_mut(&mut self) -> &mut SourceRef {
+        &mut self.source
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, From)]
 pub enum SymbolValue {
     /// A machine definition
@@ -395,6 +405,7 @@ impl Display for Part {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Machine {
+    pub source: SourceRef,
     pub params: MachineParams,
     pub properties: MachineProperties,
     pub statements: Vec<MachineStatement>,
@@ -425,6 +436,16 @@ impl Machine {
     }
 }
 
+impl SourceReference for Machine {
+    fn source_reference(&self) -> &SourceRef {
+        &self.source
+    }
+
+    fn source_reference_mut(&mut self) -> &mut SourceRef {
+        &mut self.source
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Default, Clone)]
 pub struct MachineParams(pub Vec<Param>);
 
@@ -747,11 +768,35 @@ impl Children<Expression> for FunctionStatement {
     }
 }
 
+impl SourceReference for FunctionStatement {
+    fn source_reference(&self) -> &SourceRef {
+        match self {
+            FunctionStatement::Assignment(source, ..)
+            | FunctionStatement::Instruction(source, ..)
+            | FunctionStatement::Label(source, ..)
+            | FunctionStatement::DebugDirective(source, ..)
+            | FunctionStatement::Return(source, ..) => source,
+        }
+    }
+
+    fn source_reference_mut(&mut self) -> &mut SourceRef {
+        match self {
+            FunctionStatement::Assignment(source, ..)
+            | FunctionStatement::Instruction(source, ..)
+            | FunctionStatement::Label(source, ..)
+            | FunctionStatement::DebugDirective(source, ..)
+            | FunctionStatement::Return(source, ..) => source,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum DebugDirective {
     File(usize, String, String),
     Loc(usize, usize, usize),
     OriginalInstruction(String),
+    /// The address of the original instruction, which the subsequent statements implement.
+    InstructionAddress(u32),
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
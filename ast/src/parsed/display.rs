@@ -47,11 +47,14 @@ impl Display for ModuleStatement {
 
 impl Display for SymbolDefinition {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let SymbolDefinition { name, value } = self;
+        let SymbolDefinition { name, value, .. } = self;
         match value {
             SymbolValue::Machine(m) => {
                 write!(f, "machine {name}{m}")
             }
+            SymbolValue::Import(i) if i.path.try_last_part() == Some(name) => {
+                write!(f, "{i};")
+            }
             SymbolValue::Import(i) => {
                 write!(f, "{i} as {name};")
             }
@@ -313,6 +316,9 @@ impl Display for DebugDirective {
             DebugDirective::OriginalInstruction(insn) => {
                 write!(f, ".debug insn \"{insn}\";")
             }
+            DebugDirective::InstructionAddress(address) => {
+                write!(f, ".debug insn 0x{address:08x};")
+            }
         }
     }
 }
@@ -355,6 +361,16 @@ impl<E: Display> Display for IndexAccess<Expression<E>> {
     }
 }
 
+impl<E: Display> Display for FieldAccess<Expression<E>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.object.precedence().is_none() {
+            write!(f, "{}.{}", self.object, self.field)
+        } else {
+            write!(f, "({}).{}", self.object, self.field)
+        }
+    }
+}
+
 impl<E: Display> Display for FunctionCall<Expression<E>> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         if self.function.precedence().is_none() {
@@ -730,6 +746,7 @@ impl<Ref: Display> Display for Expression<Ref> {
                 write!(f, "{unaryop}")
             }
             Expression::IndexAccess(_, index_access) => write!(f, "{index_access}"),
+            Expression::FieldAccess(_, field_access) => write!(f, "{field_access}"),
             Expression::FunctionCall(_, fun_call) => write!(f, "{fun_call}"),
             Expression::FreeInput(_, input) => write!(f, "${{ {input} }}"),
             Expression::MatchExpression(_, match_expr) => {
@@ -24,7 +24,7 @@ use powdr_parser_util::SourceRef;
 use crate::analyzed::Reference;
 
 use self::{
-    asm::{Part, SymbolPath},
+    asm::{MachineStatement, Part, SymbolPath},
     types::{FunctionType, Type, TypeBounds, TypeScheme},
     visitor::{Children, ExpressionVisitable},
 };
@@ -64,7 +64,7 @@ impl SymbolCategory {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct PILFile(pub Vec<PilStatement>);
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -306,6 +306,20 @@ pub struct StructDeclaration<E = u64> {
 }
 
 impl<E: Clone> StructDeclaration<E> {
+    /// Returns the type of values of this struct as a type scheme
+    /// over the type variables of the struct.
+    pub fn type_scheme(&self) -> TypeScheme<E> {
+        let name = SymbolPath::from_str(&self.name).unwrap();
+        let vars = self.type_vars.clone();
+        let generic_args =
+            (!vars.is_empty()).then(|| vars.vars().cloned().map(Type::TypeVar).collect::<Vec<_>>());
+
+        TypeScheme {
+            vars,
+            ty: Type::NamedType(name, generic_args),
+        }
+    }
+
     pub fn type_of_field(&self, name: &str) -> Option<TypeScheme<E>> {
         self.fields
             .iter()
@@ -584,6 +598,7 @@ pub enum Expression<Ref = NamespacedPolynomialReference> {
     UnaryOperation(SourceRef, UnaryOperation<Self>),
     BinaryOperation(SourceRef, BinaryOperation<Self>),
     IndexAccess(SourceRef, IndexAccess<Self>),
+    FieldAccess(SourceRef, FieldAccess<Self>),
     FunctionCall(SourceRef, FunctionCall<Self>),
     FreeInput(SourceRef, Box<Self>),
     MatchExpression(SourceRef, MatchExpression<Self>),
@@ -639,6 +654,7 @@ impl_source_reference!(
     BinaryOperation,
     UnaryOperation,
     IndexAccess,
+    FieldAccess,
     FunctionCall,
     FreeInput,
     MatchExpression,
@@ -663,6 +679,17 @@ impl_source_reference!(
     Expression
 );
 
+impl_source_reference!(
+    MachineStatement,
+    Pil,
+    Submachine,
+    RegisterDeclaration,
+    InstructionDeclaration,
+    LinkDeclaration,
+    FunctionDeclaration,
+    OperationDeclaration
+);
+
 #[derive(
     Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, JsonSchema, Hash,
 )]
@@ -893,6 +920,7 @@ impl<R> Expression<R> {
             Expression::BinaryOperation(_, binary_op) => binary_op.children(),
             Expression::UnaryOperation(_, unary_op) => unary_op.children(),
             Expression::IndexAccess(_, index_access) => index_access.children(),
+            Expression::FieldAccess(_, field_access) => field_access.children(),
             Expression::FunctionCall(_, function_call) => function_call.children(),
             Expression::FreeInput(_, e) => once(e.as_ref()),
             Expression::MatchExpression(_, match_expr) => match_expr.children(),
@@ -919,6 +947,7 @@ impl<R> Expression<R> {
             Expression::BinaryOperation(_, binary_op) => binary_op.children_mut(),
             Expression::UnaryOperation(_, unary_op) => unary_op.children_mut(),
             Expression::IndexAccess(_, index_access) => index_access.children_mut(),
+            Expression::FieldAccess(_, field_access) => field_access.children_mut(),
             Expression::FunctionCall(_, function_call) => function_call.children_mut(),
             Expression::FreeInput(_, e) => once(e.as_mut()),
             Expression::MatchExpression(_, match_expr) => match_expr.children_mut(),
@@ -1217,6 +1246,31 @@ impl<E> Children<E> for IndexAccess<E> {
     }
 }
 
+/// Access to a named field of a struct value, i.e. `object.field`.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, JsonSchema, Hash,
+)]
+pub struct FieldAccess<E = Expression<NamespacedPolynomialReference>> {
+    pub object: Box<E>,
+    pub field: String,
+}
+
+impl<Ref> From<FieldAccess<Expression<Ref>>> for Expression<Ref> {
+    fn from(fa: FieldAccess<Expression<Ref>>) -> Self {
+        Expression::FieldAccess(SourceRef::unknown(), fa)
+    }
+}
+
+impl<E> Children<E> for FieldAccess<E> {
+    fn children(&self) -> Box<dyn Iterator<Item = &E> + '_> {
+        Box::new(once(self.object.as_ref()))
+    }
+
+    fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut E> + '_> {
+        Box::new(once(self.object.as_mut()))
+    }
+}
+
 #[derive(
     Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, JsonSchema, Hash,
 )]
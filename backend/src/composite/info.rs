@@ -0,0 +1,229 @@
+//! Inspection of composite proofs, e.g. to track the proof size of each machine.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use itertools::Itertools;
+use powdr_ast::analyzed::Analyzed;
+use powdr_number::{DegreeType, FieldElement};
+use serde::Serialize;
+
+use crate::Error;
+
+use super::{CompositeProof, CompositeVerificationKey};
+
+/// The contents of a composite proof.
+#[derive(Serialize)]
+pub struct CompositeProofInfo {
+    /// The size of the serialized composite proof in bytes.
+    pub total_bytes: usize,
+    /// The proven machines, sorted by name. Machines without rows are not proven.
+    pub machines: Vec<MachineProofInfo>,
+}
+
+#[derive(Serialize)]
+pub struct MachineProofInfo {
+    pub name: String,
+    /// The (dynamic) size of the machine.
+    pub size: DegreeType,
+    /// The size of the serialized machine proof in bytes.
+    pub proof_bytes: usize,
+    pub stage_count: usize,
+    /// The public values by name.
+    pub publics: Vec<(String, String)>,
+    /// The verification key entry the machine proof is verified against, if a
+    /// verification key is given.
+    pub verification_key: Option<VerificationKeyEntry>,
+}
+
+#[derive(Serialize)]
+pub struct VerificationKeyEntry {
+    /// The index of the machine in the composite verification key.
+    pub index: usize,
+    /// The size of the entry in bytes, None if the backend of the machine has no
+    /// verification key or the key has no entry for the size of the machine.
+    pub bytes: Option<usize>,
+}
+
+/// Decodes a composite proof of the given PIL and optionally the composite verification key.
+/// `publics` are the public values the proof is verified against, in the order of
+/// `Analyzed::get_publics`.
+pub fn composite_proof_info<F: FieldElement>(
+    pil: &Analyzed<F>,
+    proof: &[u8],
+    verification_key: Option<&[u8]>,
+    publics: &[F],
+) -> Result<CompositeProofInfo, Error> {
+    let composite_proof: CompositeProof = bincode::deserialize(proof)
+        .map_err(|e| Error::BackendError(format!("Failed to deserialize proof: {e}")))?;
+    let verification_keys = verification_key
+        .map(|verification_key| {
+            bincode::deserialize::<CompositeVerificationKey>(verification_key)
+                .map(|vk| vk.verification_keys)
+                .map_err(|e| {
+                    Error::BackendError(format!("Failed to deserialize verification key: {e}"))
+                })
+        })
+        .transpose()?;
+
+    let public_names = pil.get_publics().into_iter().map(|(name, ..)| name);
+    if public_names.len() != publics.len() {
+        return Err(Error::BackendError(format!(
+            "The PIL has {} public values, but {} were given",
+            public_names.len(),
+            publics.len()
+        )));
+    }
+    let public_values = public_names
+        .zip(publics.iter().map(|value| value.to_string()))
+        .collect::<BTreeMap<_, _>>();
+
+    // The verification key contains an entry for each machine, sorted by name.
+    let pils = powdr_backend_utils::split_pil(pil);
+    if let Some(verification_keys) = &verification_keys {
+        if verification_keys.len() != pils.len() {
+            return Err(Error::BackendError(format!(
+                "The verification key has {} entries, but the PIL has {} machines",
+                verification_keys.len(),
+                pils.len()
+            )));
+        }
+    }
+
+    let machines = composite_proof
+        .proofs
+        .into_iter()
+        .map(|(name, machine_proof)| {
+            let (index, machine_pil) = pils
+                .iter()
+                .find_position(|(machine_name, _)| **machine_name == name)
+                .map(|(index, (_, pil))| (index, pil))
+                .ok_or_else(|| Error::BackendError(format!("Unknown machine in proof: {name}")))?;
+            let publics = machine_pil
+                .get_publics()
+                .into_iter()
+                .map(|(public_name, ..)| {
+                    let value = public_values.get(&public_name).cloned().ok_or_else(|| {
+                        Error::BackendError(format!("Unknown public value: {public_name}"))
+                    })?;
+                    Ok((public_name, value))
+                })
+                .collect::<Result<_, Error>>()?;
+            Ok(MachineProofInfo {
+                size: machine_proof.size,
+                proof_bytes: machine_proof.proof.len(),
+                stage_count: machine_pil.stage_count(),
+                publics,
+                verification_key: verification_keys.as_ref().map(|keys| VerificationKeyEntry {
+                    index,
+                    bytes: keys[index]
+                        .as_ref()
+                        .and_then(|keys| keys.get(&machine_proof.size))
+                        .map(|key| key.len()),
+                }),
+                name,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(CompositeProofInfo {
+        total_bytes: proof.len(),
+        machines,
+    })
+}
+
+impl Display for CompositeProofInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Composite proof of {} machines, {} bytes:",
+            self.machines.len(),
+            self.total_bytes
+        )?;
+        for machine in &self.machines {
+            writeln!(f, "* {}:", machine.name)?;
+            writeln!(f, "  * Size: {}", machine.size)?;
+            writeln!(f, "  * Proof size: {} bytes", machine.proof_bytes)?;
+            writeln!(f, "  * Stages: {}", machine.stage_count)?;
+            if !machine.publics.is_empty() {
+                writeln!(f, "  * Public values:")?;
+                for (name, value) in &machine.publics {
+                    writeln!(f, "    * {name}: {value}")?;
+                }
+            }
+            if let Some(entry) = &machine.verification_key {
+                match entry.bytes {
+                    Some(bytes) => writeln!(
+                        f,
+                        "  * Verification key: entry {}, size {}, {bytes} bytes",
+                        entry.index, machine.size
+                    )?,
+                    None => writeln!(
+                        f,
+                        "  * Verification key: entry {} has no key for size {}",
+                        entry.index, machine.size
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use powdr_number::GoldilocksField;
+    use powdr_pil_analyzer::analyze_string;
+
+    use super::*;
+    use crate::composite::MachineProof;
+
+    #[test]
+    fn decode() {
+        let pil = analyze_string::<GoldilocksField>(
+            "namespace A(8); col witness x; x = 1; public p = x(2);
+            namespace B(8); col witness y; y = 2;",
+        )
+        .unwrap();
+        let proof = bincode::serialize(&CompositeProof {
+            proofs: BTreeMap::from([(
+                "A".to_string(),
+                MachineProof {
+                    size: 8,
+                    proof: vec![0; 100],
+                },
+            )]),
+        })
+        .unwrap();
+        let verification_key = bincode::serialize(&CompositeVerificationKey {
+            verification_keys: vec![Some(BTreeMap::from([(8, vec![0; 10])])), None],
+        })
+        .unwrap();
+
+        let publics = [GoldilocksField::from(1)];
+        let info = composite_proof_info(&pil, &proof, Some(&verification_key), &publics).unwrap();
+        assert_eq!(info.total_bytes, proof.len());
+        let [machine] = &info.machines[..] else {
+            panic!("Expected a single machine");
+        };
+        assert_eq!(machine.name, "A");
+        assert_eq!(machine.size, 8);
+        assert_eq!(machine.proof_bytes, 100);
+        assert_eq!(machine.stage_count, 1);
+        assert_eq!(machine.publics, vec![("A::p".to_string(), "1".to_string())]);
+        let entry = machine.verification_key.as_ref().unwrap();
+        assert_eq!((entry.index, entry.bytes), (0, Some(10)));
+
+        let text = info.to_string();
+        assert!(text.contains("* A:"));
+        assert!(text.contains("Verification key: entry 0, size 8, 10 bytes"));
+
+        let Some(Error::BackendError(error)) = composite_proof_info(&pil, &proof, None, &[]).err()
+        else {
+            panic!("Expected an error");
+        };
+        assert_eq!(error, "The PIL has 1 public values, but 0 were given");
+    }
+}
@@ -17,6 +17,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{Backend, BackendFactory, BackendOptions, Error, Proof};
 
+use self::scheduler::{CompositeOptions, Job, Scheduler};
 use self::sub_prover::RunStatus;
 
 /// Maps each size to the corresponding verification key.
@@ -78,6 +79,8 @@ impl<F: FieldElement, B: BackendFactory<F>> BackendFactory<F> for CompositeBacke
             unimplemented!();
         }
 
+        let (options, backend_options) = CompositeOptions::split(backend_options)?;
+
         let pils = powdr_backend_utils::split_pil(&pil);
 
         // Read the setup once (if any) to pass to all backends.
@@ -160,7 +163,10 @@ impl<F: FieldElement, B: BackendFactory<F>> BackendFactory<F> for CompositeBacke
             })
             .collect::<Result<BTreeMap<_, _>, _>>()?;
 
-        Ok(Box::new(CompositeBackend { machine_data }))
+        Ok(Box::new(CompositeBackend {
+            machine_data,
+            memory_budget: options.memory_budget,
+        }))
     }
 
     fn generate_setup(&self, size: DegreeType, output: &mut dyn io::Write) -> Result<(), Error> {
@@ -216,6 +222,8 @@ pub(crate) struct CompositeBackend<F> {
     /// Note that it is essential that we use BTreeMap here to ensure that the machines are
     /// deterministically ordered.
     machine_data: BTreeMap<String, BTreeMap<DegreeType, MachineData<F>>>,
+    /// The estimated memory in bytes that machine proofs running concurrently may use.
+    memory_budget: usize,
 }
 
 /// Makes sure that all columns in the machine PIL have the provided degree, cloning
@@ -248,6 +256,8 @@ fn set_size<F: Clone>(pil: Arc<Analyzed<F>>, degree: DegreeType) -> Arc<Analyzed
     Arc::new(Analyzed { definitions, ..pil })
 }
 
+pub mod info;
+pub(crate) mod scheduler;
 mod sub_prover;
 
 fn accumulate_challenges<F: FieldElement>(into: &mut BTreeMap<u64, F>, from: BTreeMap<u64, F>) {
@@ -324,7 +334,7 @@ impl<F: FieldElement> Backend<F> for CompositeBackend<F> {
 
         // We use scoped threads to be able to share non-'static references.
         thread::scope(|scope| {
-            let mut proofs_status = self
+            let (machines, jobs): (Vec<_>, Vec<Job<_>>) = self
                 .machine_data
                 .iter()
                 .filter_map(|machine_entry| {
@@ -338,12 +348,26 @@ impl<F: FieldElement> Backend<F> for CompositeBackend<F> {
                         .get(&size)
                         .expect("Machine does not support the given size");
 
-                    let status = time_stage(machine, size, 0, || {
-                        sub_prover::run(scope, &inner_machine_data.backend, witness)
-                    });
+                    let memory = scheduler::estimate_memory(&inner_machine_data.pil, size);
+                    let job: Box<dyn FnOnce() -> RunStatus<'_, F> + Send + '_> =
+                        Box::new(move || {
+                            time_stage(machine, size, 0, || {
+                                sub_prover::run(scope, &inner_machine_data.backend, witness)
+                            })
+                        });
+                    Some(((machine_entry, size), (memory, job)))
+                })
+                .unzip();
 
-                    Some((status, machine_entry, size))
+            // Run the first stage of all machines concurrently, within the memory budget.
+            let mut job_scheduler = Scheduler::new(self.memory_budget);
+            let mut proofs_status = job_scheduler
+                .start(scope, jobs, |status| {
+                    matches!(status, RunStatus::Completed(_))
                 })
+                .into_iter()
+                .zip_eq(machines)
+                .map(|(status, (machine_entry, size))| (status, machine_entry, size))
                 .collect::<Vec<_>>();
 
             let mut proof_results = BTreeMap::new();
@@ -388,18 +412,32 @@ impl<F: FieldElement> Backend<F> for CompositeBackend<F> {
                     .collect();
 
                 // Resume the waiting provers with the new witness
-                proofs_status = waiting_provers
+                let (machines, jobs): (Vec<_>, Vec<Job<_>>) = waiting_provers
                     .into_iter()
                     .map(|(prover, machine_entry)| {
-                        let (machine_name, _) = machine_entry;
+                        let (machine_name, machine_data) = machine_entry;
                         let (witness, size) =
                             witness_by_machine.get(machine_name).cloned().unwrap();
 
-                        let status =
-                            time_stage(machine_name, size, stage, move || prover.resume(witness));
+                        let memory = scheduler::estimate_memory(&machine_data[&size].pil, size);
+                        let job: Box<dyn FnOnce() -> RunStatus<'_, F> + Send + '_> =
+                            Box::new(move || {
+                                time_stage(machine_name, size, stage, move || {
+                                    prover.resume(witness)
+                                })
+                            });
+                        ((machine_entry, size), (memory, job))
+                    })
+                    .unzip();
 
-                        (status, machine_entry, size)
+                // The provers still hold the memory reserved in the first stage.
+                proofs_status = job_scheduler
+                    .resume(scope, jobs, |status| {
+                        matches!(status, RunStatus::Completed(_))
                     })
+                    .into_iter()
+                    .zip_eq(machines)
+                    .map(|(status, (machine_entry, size))| (status, machine_entry, size))
                     .collect();
             }
 
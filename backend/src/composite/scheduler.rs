@@ -0,0 +1,298 @@
+//! Runs the machine proofs of a stage concurrently, within a memory budget.
+
+use std::{
+    cmp::Reverse,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread::Scope,
+};
+
+use itertools::Itertools;
+use powdr_ast::analyzed::Analyzed;
+use powdr_number::DegreeType;
+
+use crate::{BackendOptions, Error};
+
+/// The ratio between the memory needed to prove a machine and the size of its trace.
+/// This accounts for the low-degree extension of the columns, the commitments to
+/// them and the quotient polynomial.
+const MEMORY_OVERHEAD_FACTOR: usize = 8;
+
+/// Options of the composite backend, which are taken from the backend options
+/// before they are passed on to the backend of each machine.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CompositeOptions {
+    /// The memory in bytes the machine proofs of a stage that run concurrently
+    /// are estimated to need at most.
+    /// At least one machine is proven at a time, so with a budget of 0, the
+    /// machines are proven sequentially.
+    pub memory_budget: usize,
+}
+
+impl CompositeOptions {
+    /// Splits off the options of the composite backend from the comma-separated
+    /// backend options, e.g. `memory_budget=16G` in `conservative,memory_budget=16G`.
+    /// Returns the remaining options.
+    pub fn split(options: BackendOptions) -> Result<(Self, BackendOptions), Error> {
+        let mut composite_options = Self::default();
+        let mut remaining = vec![];
+        for option in options.split(',') {
+            match option.strip_prefix("memory_budget=") {
+                Some(budget) => {
+                    composite_options.memory_budget = parse_size(budget).ok_or_else(|| {
+                        Error::BackendError(format!("Invalid memory budget: {budget}"))
+                    })?
+                }
+                None => remaining.push(option),
+            }
+        }
+        Ok((composite_options, remaining.join(",")))
+    }
+}
+
+/// Parses a number of bytes with an optional binary unit suffix, e.g. `512M` or `64G`.
+fn parse_size(size: &str) -> Option<usize> {
+    let (number, unit) = match size.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((index, _)) => size.split_at(index),
+        None => (size, ""),
+    };
+    let shift = match unit {
+        "" | "B" => 0,
+        "K" | "KB" => 10,
+        "M" | "MB" => 20,
+        "G" | "GB" => 30,
+        "T" | "TB" => 40,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+/// A rough estimate of the memory in bytes needed to prove a machine of the given size.
+pub fn estimate_memory<F>(pil: &Analyzed<F>, size: DegreeType) -> usize {
+    (pil.commitment_count() + pil.constant_count())
+        * size as usize
+        * std::mem::size_of::<F>()
+        * MEMORY_OVERHEAD_FACTOR
+}
+
+/// A job of a stage, with its estimated memory usage.
+pub type Job<'s, T> = (usize, Box<dyn FnOnce() -> T + Send + 's>);
+
+/// Runs the jobs of the stages on threads of a scope, within a memory budget.
+/// The memory estimate of a job is reserved from the time it is started until it
+/// has completed all stages, because a job that waits for the challenges of the
+/// next stage keeps its data in memory.
+pub struct Scheduler {
+    memory_budget: usize,
+    memory_in_use: usize,
+}
+
+impl Scheduler {
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            memory_budget,
+            memory_in_use: 0,
+        }
+    }
+
+    /// Starts new jobs and returns their results, in the order of the jobs.
+    /// Jobs with larger memory estimates are started first. Further jobs are only
+    /// started while the estimates of all jobs that have not completed fit into the
+    /// budget, except that at least one job is always running.
+    /// The reservation of a job is kept unless `is_completed` holds for its result.
+    pub fn start<'s, T: Send + 's>(
+        &mut self,
+        scope: &'s Scope<'s, '_>,
+        jobs: Vec<Job<'s, T>>,
+        is_completed: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        self.run(scope, jobs, false, is_completed)
+    }
+
+    /// Resumes jobs that have been started before and still hold their reservation,
+    /// and returns their results, in the order of the jobs.
+    pub fn resume<'s, T: Send + 's>(
+        &mut self,
+        scope: &'s Scope<'s, '_>,
+        jobs: Vec<Job<'s, T>>,
+        is_completed: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        self.run(scope, jobs, true, is_completed)
+    }
+
+    fn run<'s, T: Send + 's>(
+        &mut self,
+        scope: &'s Scope<'s, '_>,
+        jobs: Vec<Job<'s, T>>,
+        reserved: bool,
+        is_completed: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        let memory = jobs.iter().map(|(memory, _)| *memory).collect::<Vec<_>>();
+        let mut pending = jobs
+            .into_iter()
+            .enumerate()
+            .sorted_by_key(|(_, (memory, _))| Reverse(*memory))
+            .map(|(index, (_, job))| (index, job))
+            .collect::<Vec<_>>();
+        let mut results = (0..memory.len()).map(|_| None).collect::<Vec<_>>();
+
+        let (sender, receiver) = mpsc::channel();
+        let mut running = 0;
+        while !pending.is_empty() || running > 0 {
+            // Start the largest jobs that fit into the budget.
+            let mut i = 0;
+            while i < pending.len() {
+                let index = pending[i].0;
+                if !reserved
+                    && running > 0
+                    && self.memory_in_use + memory[index] > self.memory_budget
+                {
+                    i += 1;
+                    continue;
+                }
+                let (index, job) = pending.remove(i);
+                if !reserved {
+                    self.memory_in_use += memory[index];
+                }
+                running += 1;
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    // Forward panics, so that we do not wait for the result forever.
+                    let result = panic::catch_unwind(AssertUnwindSafe(job));
+                    sender.send((index, result)).unwrap();
+                });
+            }
+
+            let (index, result) = receiver.recv().unwrap();
+            running -= 1;
+            match result {
+                Ok(result) => {
+                    if is_completed(&result) {
+                        self.memory_in_use -= memory[index];
+                    }
+                    results[index] = Some(result)
+                }
+                Err(err) => panic::resume_unwind(err),
+            }
+        }
+
+        results.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn split_options() {
+        assert_eq!(
+            CompositeOptions::split("".to_string()).unwrap(),
+            (CompositeOptions::default(), "".to_string())
+        );
+        assert_eq!(
+            CompositeOptions::split("conservative,memory_budget=16G,pow_bits=20".to_string())
+                .unwrap(),
+            (
+                CompositeOptions {
+                    memory_budget: 16 << 30
+                },
+                "conservative,pow_bits=20".to_string()
+            )
+        );
+        assert_eq!(
+            CompositeOptions::split("memory_budget=1000".to_string()).unwrap(),
+            (
+                CompositeOptions {
+                    memory_budget: 1000
+                },
+                "".to_string()
+            )
+        );
+        assert!(CompositeOptions::split("memory_budget=16X".to_string()).is_err());
+    }
+
+    /// Runs jobs with the given memory estimates and returns the order in which they started
+    /// and the maximum memory in use at a time.
+    fn schedule(memory_budget: usize, memory: &[usize]) -> (Vec<usize>, usize) {
+        let started = Arc::new(Mutex::new(vec![]));
+        let in_use = Arc::new(Mutex::new((0, 0)));
+        let results = thread::scope(|scope| {
+            let jobs = memory
+                .iter()
+                .enumerate()
+                .map(|(index, &memory)| {
+                    let started = started.clone();
+                    let in_use = in_use.clone();
+                    let job = move || {
+                        started.lock().unwrap().push(index);
+                        {
+                            let (current, max) = &mut *in_use.lock().unwrap();
+                            *current += memory;
+                            *max = (*max).max(*current);
+                        }
+                        thread::sleep(std::time::Duration::from_millis(10));
+                        in_use.lock().unwrap().0 -= memory;
+                        index
+                    };
+                    (memory, Box::new(job) as Box<dyn FnOnce() -> usize + Send>)
+                })
+                .collect();
+            Scheduler::new(memory_budget).start(scope, jobs, |_| true)
+        });
+        assert_eq!(results, (0..memory.len()).collect::<Vec<_>>());
+        let started = started.lock().unwrap().clone();
+        let max_in_use = in_use.lock().unwrap().1;
+        (started, max_in_use)
+    }
+
+    #[test]
+    fn sequential_without_budget() {
+        let (started, max_in_use) = schedule(0, &[1, 3, 2]);
+        assert_eq!(started, vec![1, 2, 0]);
+        assert_eq!(max_in_use, 3);
+    }
+
+    #[test]
+    fn within_budget() {
+        let (started, max_in_use) = schedule(5, &[1, 4, 2, 3, 1]);
+        assert_eq!(started[0], 1);
+        assert!(max_in_use <= 5);
+    }
+
+    #[test]
+    fn large_job_exceeding_budget() {
+        let (_, max_in_use) = schedule(2, &[1, 10, 1]);
+        assert_eq!(max_in_use, 10);
+    }
+
+    #[test]
+    #[should_panic = "job failed"]
+    fn panicking_job() {
+        thread::scope(|scope| {
+            let jobs: Vec<Job<()>> =
+                vec![(1, Box::new(|| ())), (2, Box::new(|| panic!("job failed")))];
+            Scheduler::new(10).start(scope, jobs, |_| true);
+        });
+    }
+
+    #[test]
+    fn reserved_until_completed() {
+        let mut scheduler = Scheduler::new(10);
+        thread::scope(|scope| {
+            // The first job waits for the next stage, the second one completes.
+            let jobs: Vec<Job<bool>> = vec![(3, Box::new(|| false)), (2, Box::new(|| true))];
+            scheduler.start(scope, jobs, |completed| *completed);
+            assert_eq!(scheduler.memory_in_use, 3);
+
+            let jobs: Vec<Job<bool>> = vec![(3, Box::new(|| true))];
+            scheduler.resume(scope, jobs, |completed| *completed);
+            assert_eq!(scheduler.memory_in_use, 0);
+        });
+    }
+}
@@ -50,10 +50,15 @@ impl ProofType {
     }
 }
 
+/// The log2 of the blowup factor of the FRI low degree extension.
+pub(crate) const FRI_LOG_BLOWUP: usize = 1;
+/// The number of FRI queries.
+pub(crate) const FRI_NUM_QUERIES: usize = 2;
+
 fn create_stark_struct(degree: DegreeType, hash_type: &str) -> StarkStruct {
     assert!(degree > 1);
     let n_bits = (DegreeType::BITS - (degree - 1).leading_zeros()) as usize;
-    let n_bits_ext = n_bits + 1;
+    let n_bits_ext = n_bits + FRI_LOG_BLOWUP;
 
     let steps = (2..=n_bits_ext)
         .rev()
@@ -64,7 +69,7 @@ fn create_stark_struct(degree: DegreeType, hash_type: &str) -> StarkStruct {
     StarkStruct {
         nBits: n_bits,
         nBitsExt: n_bits_ext,
-        nQueries: 2,
+        nQueries: FRI_NUM_QUERIES,
         verificationHashType: hash_type.to_string(),
         steps,
     }
@@ -10,7 +10,16 @@ mod stwo;
 mod composite;
 mod field_filter;
 mod mock;
+pub mod security;
 
+#[cfg(feature = "plonky3")]
+pub use plonky3::verifier_input as plonky3_verifier_input;
+#[cfg(feature = "plonky3")]
+pub use powdr_plonky3::VerifierInput as Plonky3VerifierInput;
+
+pub use composite::info::{
+    composite_proof_info, CompositeProofInfo, MachineProofInfo, VerificationKeyEntry,
+};
 use powdr_ast::analyzed::Analyzed;
 use powdr_executor::{constant_evaluator::VariablySizedColumn, witgen::WitgenCallback};
 use powdr_number::{DegreeType, FieldElement};
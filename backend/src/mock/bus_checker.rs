@@ -1,15 +1,24 @@
-use std::{cmp::Ordering, collections::BTreeMap, fmt};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 use itertools::Itertools;
 use powdr_ast::{
-    analyzed::{Analyzed, Identity, PhantomBusInteractionIdentity},
+    analyzed::{AlgebraicExpression, Analyzed, Identity, PhantomBusInteractionIdentity},
     parsed::visitor::Children,
 };
 use powdr_executor_utils::expression_evaluator::ExpressionEvaluator;
 use powdr_number::FieldElement;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use super::{localize, machine::Machine, unique_referenced_namespaces};
+use super::{
+    localize,
+    machine::Machine,
+    report::{BusInteractionCount, BusInteractionFailure},
+    unique_referenced_namespaces,
+};
 
 pub struct BusChecker<'a, F> {
     interactions: &'a [BusInteraction<F>],
@@ -44,6 +53,30 @@ impl<F: fmt::Display> fmt::Display for Error<F> {
     }
 }
 
+impl<F: fmt::Display> Error<F> {
+    pub fn to_report(&self) -> BusInteractionFailure {
+        let counts = |interactions: &BTreeMap<BusInteraction<F>, usize>| {
+            interactions
+                .iter()
+                .map(
+                    |(BusInteraction { machine, identity }, count)| BusInteractionCount {
+                        machine: machine.clone(),
+                        identity_id: identity.id,
+                        identity: identity.to_string(),
+                        count: *count,
+                    },
+                )
+                .collect()
+        };
+        BusInteractionFailure {
+            bus_id: self.tuple[0].to_string(),
+            payload: self.tuple.iter().skip(1).map(|v| v.to_string()).collect(),
+            sends: counts(&self.sends),
+            receives: counts(&self.receives),
+        }
+    }
+}
+
 #[derive(PartialEq, PartialOrd, Eq, Ord, Clone)]
 pub struct BusInteraction<F> {
     pub machine: String,
@@ -75,6 +108,35 @@ impl<F: FieldElement> BusInteraction<F> {
             })
             .collect()
     }
+
+    /// Returns the interactions that are on the same bus as an interaction of the given
+    /// machine, i.e. the ones that are affected by changes to the machine's witness.
+    /// An interaction with a non-constant bus ID can be on any bus.
+    pub fn get_related(interactions: &[Self], machine: &str) -> Vec<Self> {
+        let constant_bus_id = |interaction: &Self| match interaction.identity.bus_id {
+            AlgebraicExpression::Number(bus_id) => Some(bus_id),
+            _ => None,
+        };
+        // None if some bus ID of the machine is not constant.
+        let bus_ids = interactions
+            .iter()
+            .filter(|interaction| interaction.machine == machine)
+            .map(constant_bus_id)
+            .collect::<Option<BTreeSet<_>>>();
+        if bus_ids.as_ref().is_some_and(|bus_ids| bus_ids.is_empty()) {
+            return vec![];
+        }
+        interactions
+            .iter()
+            .filter(
+                |interaction| match (&bus_ids, constant_bus_id(*interaction)) {
+                    (Some(bus_ids), Some(bus_id)) => bus_ids.contains(&bus_id),
+                    _ => true,
+                },
+            )
+            .cloned()
+            .collect()
+    }
 }
 
 impl<'a, F: FieldElement> BusChecker<'a, F> {
@@ -114,6 +176,11 @@ impl<'a, F: FieldElement> BusChecker<'a, F> {
         let bus_state: BusState<F> = self
             .machines
             .into_par_iter()
+            .filter(|(name, _)| {
+                self.interactions
+                    .iter()
+                    .any(|interaction| interaction.machine == **name)
+            })
             .flat_map(|(name, machine)| {
                 (0..machine.size).into_par_iter().map(|row_id| {
                     // create an evaluator for this row
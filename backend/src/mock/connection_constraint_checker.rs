@@ -1,5 +1,4 @@
 use std::collections::BTreeMap;
-use std::collections::BTreeSet;
 use std::fmt;
 
 use std::iter::once;
@@ -17,15 +16,21 @@ use powdr_number::FieldElement;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 
-use super::{localize, machine::Machine, unique_referenced_namespaces};
+use super::{
+    localize,
+    machine::Machine,
+    report::{self, ConnectionFailure, TupleWithMultiplicity},
+    unique_referenced_namespaces,
+};
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum ConnectionKind {
     Lookup,
     Permutation,
 }
 
 /// A connection between two machines.
+#[derive(Clone)]
 pub struct Connection<F> {
     identity: Identity<F>,
     pub left: SelectedExpressions<F>,
@@ -123,6 +128,16 @@ impl<F: FieldElement> Connection<F> {
     pub fn callee(&self) -> Option<String> {
         unique_referenced_namespaces(&self.right)
     }
+
+    /// Returns true if the given machine is the caller or the callee.
+    pub fn involves(&self, machine: &str) -> bool {
+        self.caller().as_deref() == Some(machine) || self.callee().as_deref() == Some(machine)
+    }
+
+    /// The ID of the identity this connection was created from.
+    pub fn id(&self) -> u64 {
+        self.identity.id()
+    }
 }
 
 pub struct ConnectionConstraintChecker<'a, F> {
@@ -179,16 +194,11 @@ impl<'a, F: FieldElement> ConnectionConstraintChecker<'a, F> {
         match connection.kind {
             ConnectionKind::Lookup => {
                 // Check if $caller \subseteq callee$.
-                let caller_set = caller_multi_set.keys().collect::<BTreeSet<_>>();
-                let callee_set = callee_multi_set.keys().collect::<BTreeSet<_>>();
-                let not_in_callee = caller_set
-                    .difference(&callee_set)
-                    .cloned()
-                    .collect::<Vec<_>>();
+                let not_in_callee = not_in(&caller_multi_set, &callee_multi_set);
                 if !not_in_callee.is_empty() {
                     Err(vec![FailingConnectionConstraint {
                         connection,
-                        not_in_callee: not_in_callee.into_iter().cloned().collect(),
+                        not_in_callee,
                         not_in_caller: Default::default(),
                     }
                     .into()])
@@ -223,16 +233,11 @@ impl<'a, F: FieldElement> ConnectionConstraintChecker<'a, F> {
                 // Find the tuples that are in one set, but not in the other.
                 // Note that both `not_in_caller` and `not_in_callee` might actually be empty,
                 // if `caller_set` and `callee_set` are equal as sets but not as multi-sets.
-                let caller_set = caller_multi_set.keys().collect::<BTreeSet<_>>();
-                let callee_set = callee_multi_set.keys().collect::<BTreeSet<_>>();
-                let not_in_caller = callee_set.difference(&caller_set).collect::<Vec<_>>();
-                let not_in_callee = caller_set.difference(&callee_set).collect::<Vec<_>>();
-
                 if !is_equal {
                     Err(vec![FailingConnectionConstraint {
                         connection,
-                        not_in_caller: not_in_caller.into_iter().cloned().cloned().collect(),
-                        not_in_callee: not_in_callee.into_iter().cloned().cloned().collect(),
+                        not_in_caller: not_in(&callee_multi_set, &caller_multi_set),
+                        not_in_callee: not_in(&caller_multi_set, &callee_multi_set),
                     }
                     .into()])
                 } else {
@@ -349,6 +354,17 @@ impl<'a, F: FieldElement> ConnectionConstraintChecker<'a, F> {
     }
 }
 
+/// Returns the tuples (with their multiplicities) that are in `a`, but not in `b`.
+fn not_in<F: Ord + Clone>(
+    a: &BTreeMap<Tuple<F>, usize>,
+    b: &BTreeMap<Tuple<F>, usize>,
+) -> Vec<(Tuple<F>, usize)> {
+    a.iter()
+        .filter(|(tuple, _)| !b.contains_key(tuple))
+        .map(|(tuple, multiplicity)| (tuple.clone(), *multiplicity))
+        .collect()
+}
+
 struct EmptyVariables;
 
 impl<T: FieldElement> TerminalAccess<T> for EmptyVariables {}
@@ -412,12 +428,12 @@ pub struct FailingConnectionConstraint<'a, F> {
     /// The connection that failed.
     connection: &'a Connection<F>,
 
-    /// Tuples that are in the callee, but not in the caller.
+    /// Tuples that are in the callee, but not in the caller, with their multiplicities.
     /// For [ConnectionKind::Lookup], this is irrelevant and we'll store an empty vector here.
-    not_in_caller: Vec<Tuple<F>>,
+    not_in_caller: Vec<(Tuple<F>, usize)>,
 
-    /// Tuples that are in the caller, but not in the callee.
-    not_in_callee: Vec<Tuple<F>>,
+    /// Tuples that are in the caller, but not in the callee, with their multiplicities.
+    not_in_callee: Vec<(Tuple<F>, usize)>,
 }
 
 const MAX_TUPLES: usize = 5;
@@ -427,13 +443,13 @@ fn fmt_subset_error<F: fmt::Display>(
     f: &mut fmt::Formatter<'_>,
     machine1: &str,
     machine2: &str,
-    not_in_machine2: &[Tuple<F>],
+    not_in_machine2: &[(Tuple<F>, usize)],
 ) -> fmt::Result {
     writeln!(
         f,
         "  The following tuples appear in {machine1}, but not in {machine2}:"
     )?;
-    for tuple in not_in_machine2.iter().take(MAX_TUPLES) {
+    for (tuple, _) in not_in_machine2.iter().take(MAX_TUPLES) {
         writeln!(f, "    {tuple}")?;
     }
     if not_in_machine2.len() > MAX_TUPLES {
@@ -497,3 +513,59 @@ impl<F: FieldElement> fmt::Display for Errors<'_, F> {
         Ok(())
     }
 }
+
+impl<F: FieldElement> Errors<'_, F> {
+    /// Converts all errors into report entries, one per failing connection.
+    pub fn to_report(&self) -> Vec<ConnectionFailure> {
+        let mut failures: BTreeMap<u64, ConnectionFailure> = BTreeMap::new();
+        for error in &self.errors {
+            let connection = match error {
+                Error::MultiplicityMismatch(e) => e.connection,
+                Error::FailingConnectionConstraint(e) => e.connection,
+            };
+            let failure =
+                failures
+                    .entry(connection.identity.id())
+                    .or_insert_with(|| ConnectionFailure {
+                        identity_id: connection.identity.id(),
+                        identity: connection.identity.to_string(),
+                        caller: connection.caller(),
+                        callee: connection.callee(),
+                        not_in_callee: vec![],
+                        not_in_caller: vec![],
+                        multiplicity_mismatches: vec![],
+                    });
+            match error {
+                Error::MultiplicityMismatch(e) => {
+                    failure
+                        .multiplicity_mismatches
+                        .push(report::MultiplicityMismatch {
+                            row: e.tuple.row,
+                            values: e.tuple.values.iter().map(|v| v.to_string()).collect(),
+                            caller_multiplicity: e.caller_multiplicity,
+                            callee_multiplicity: e.callee_multiplicity,
+                        })
+                }
+                Error::FailingConnectionConstraint(e) => {
+                    failure
+                        .not_in_callee
+                        .extend(e.not_in_callee.iter().map(tuple_to_report));
+                    failure
+                        .not_in_caller
+                        .extend(e.not_in_caller.iter().map(tuple_to_report));
+                }
+            }
+        }
+        failures.into_values().collect()
+    }
+}
+
+fn tuple_to_report<F: fmt::Display>(
+    (tuple, multiplicity): &(Tuple<F>, usize),
+) -> TupleWithMultiplicity {
+    TupleWithMultiplicity {
+        row: tuple.row,
+        values: tuple.values.iter().map(|v| v.to_string()).collect(),
+        multiplicity: *multiplicity,
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use itertools::Itertools;
+use powdr_number::FieldElement;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Serialize;
+
+/// The name of the file accepted mutations are written to, relative to the output directory.
+pub const FUZZ_REPORT_FILE_NAME: &str = "mock_fuzz.json";
+
+const DEFAULT_ITERATIONS: usize = 1000;
+
+/// Options of the fuzzer, parsed from `fuzz`, `fuzz=<iterations>` or
+/// `fuzz=<iterations>,<seed>`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FuzzOptions {
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+impl FuzzOptions {
+    /// Parses the options, returns None if `options` is not a fuzz option.
+    pub fn parse(options: &str) -> Option<Result<Self, String>> {
+        let args = match options.strip_prefix("fuzz")? {
+            "" => return Some(Ok(Self::default())),
+            args => args.strip_prefix('=')?,
+        };
+        let parse = |s: &str| {
+            s.parse::<u64>()
+                .map_err(|_| format!("Invalid fuzz option: {options}"))
+        };
+        Some(match args.split_once(',') {
+            None => parse(args).map(|iterations| Self {
+                iterations: iterations as usize,
+                ..Self::default()
+            }),
+            Some((iterations, seed)) => parse(iterations).and_then(|iterations| {
+                Ok(Self {
+                    iterations: iterations as usize,
+                    seed: parse(seed)?,
+                })
+            }),
+        })
+    }
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            seed: 0,
+        }
+    }
+}
+
+/// A change of a single witness cell.
+#[derive(Serialize, Clone)]
+pub struct CellMutation {
+    pub column: String,
+    pub row: usize,
+    pub original: String,
+    pub value: String,
+}
+
+/// A mutation of the witness that still satisfies all constraints.
+#[derive(Serialize)]
+pub struct AcceptedMutation {
+    pub machine: String,
+    pub cells: Vec<CellMutation>,
+}
+
+/// Generates random mutations of the witness columns of machines.
+pub struct Mutator {
+    rng: StdRng,
+}
+
+impl Mutator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Picks one of the elements uniformly at random.
+    pub fn choose<'a, T>(&mut self, elements: &'a [T]) -> &'a T {
+        elements.choose(&mut self.rng).unwrap()
+    }
+
+    /// Mutates either a single cell or several cells in the same row of the given columns,
+    /// which must all have the same length.
+    /// Returns the mutated cells.
+    pub fn mutate<F: FieldElement>(
+        &mut self,
+        columns: &mut [(String, Vec<F>)],
+    ) -> Vec<CellMutation> {
+        let size = columns[0].1.len();
+        let row = self.rng.gen_range(0..size);
+        let column_indices = if columns.len() > 1 && self.rng.gen_bool(0.5) {
+            // Mutate a random subset of at least two cells in the row,
+            // to find columns that are only constrained relative to each other.
+            let count = self.rng.gen_range(2..=columns.len());
+            rand::seq::index::sample(&mut self.rng, columns.len(), count).into_vec()
+        } else {
+            vec![self.rng.gen_range(0..columns.len())]
+        };
+        column_indices
+            .into_iter()
+            .sorted()
+            .filter_map(|i| {
+                let (name, values) = &mut columns[i];
+                let original = values[row];
+                let value = self.mutate_value(original);
+                values[row] = value;
+                (value != original).then(|| CellMutation {
+                    column: name.clone(),
+                    row,
+                    original: original.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a new value. Besides random values, this also tries small changes and
+    /// boolean values, which are likely to satisfy range constraints.
+    fn mutate_value<F: FieldElement>(&mut self, value: F) -> F {
+        match self.rng.gen_range(0..5) {
+            0 => value + F::one(),
+            1 => value - F::one(),
+            2 => F::zero(),
+            3 => F::one(),
+            _ => F::from(self.rng.gen::<u64>()),
+        }
+    }
+}
+
+pub fn write_accepted_mutations(
+    accepted: &[AcceptedMutation],
+    path: &Path,
+) -> Result<(), std::io::Error> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, accepted)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use powdr_number::GoldilocksField;
+
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        assert_eq!(FuzzOptions::parse(""), None);
+        assert_eq!(FuzzOptions::parse("shrink"), None);
+        assert_eq!(FuzzOptions::parse("fuzz"), Some(Ok(FuzzOptions::default())));
+        assert_eq!(
+            FuzzOptions::parse("fuzz=10"),
+            Some(Ok(FuzzOptions {
+                iterations: 10,
+                seed: 0
+            }))
+        );
+        assert_eq!(
+            FuzzOptions::parse("fuzz=10,42"),
+            Some(Ok(FuzzOptions {
+                iterations: 10,
+                seed: 42
+            }))
+        );
+        assert!(FuzzOptions::parse("fuzz=ten").unwrap().is_err());
+    }
+
+    #[test]
+    fn mutations_change_the_witness() {
+        let mut mutator = Mutator::new(0);
+        let original = vec![
+            ("a".to_string(), vec![GoldilocksField::from(1); 4]),
+            ("b".to_string(), vec![GoldilocksField::from(2); 4]),
+        ];
+        for _ in 0..100 {
+            let mut columns = original.clone();
+            let cells = mutator.mutate(&mut columns);
+            let changed = original
+                .iter()
+                .zip(&columns)
+                .flat_map(|((name, a), (_, b))| {
+                    a.iter()
+                        .zip(b)
+                        .enumerate()
+                        .filter(|(_, (a, b))| a != b)
+                        .map(move |(row, _)| (name.clone(), row))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                changed,
+                cells
+                    .iter()
+                    .map(|cell| (cell.column.clone(), cell.row))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -20,7 +20,7 @@ impl<'a, F: FieldElement> Machine<'a, F> {
     /// Creates a new machine from a witness, fixed columns, and a PIL - if it is not empty.
     pub fn try_new(
         machine_name: String,
-        witness: &'a [(String, Vec<F>)],
+        witness: &[(String, Vec<F>)],
         fixed: &'a [(String, VariablySizedColumn<F>)],
         pil: &'a Analyzed<F>,
         witgen_callback: &WitgenCallback<F>,
@@ -2,12 +2,14 @@ use std::{
     collections::BTreeMap,
     hash::{DefaultHasher, Hash, Hasher},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
 use bus_checker::{BusChecker, BusInteraction};
 use connection_constraint_checker::{Connection, ConnectionConstraintChecker};
+use fuzz::{AcceptedMutation, FuzzOptions, Mutator, FUZZ_REPORT_FILE_NAME};
+use itertools::Itertools;
 use machine::Machine;
 use polynomial_constraint_checker::PolynomialConstraintChecker;
 use powdr_ast::{
@@ -17,30 +19,64 @@ use powdr_ast::{
 use powdr_executor::{constant_evaluator::VariablySizedColumn, witgen::WitgenCallback};
 use powdr_number::{DegreeType, FieldElement};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use report::{Report, REPORT_FILE_NAME};
+use shrink::Reproducer;
 
 use crate::{Backend, BackendFactory, BackendOptions, Error, Proof};
 
 mod bus_checker;
 mod connection_constraint_checker;
+mod fuzz;
 mod machine;
 mod polynomial_constraint_checker;
+mod report;
+mod shrink;
 mod utils;
 
 use utils::*;
 
 pub(crate) struct MockBackendFactory;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Only check the constraints.
+    Check,
+    /// On failure, additionally shrink the witness to a minimal standalone reproducer
+    /// and write it to the output directory.
+    Shrink,
+    /// If the witness is valid, additionally check randomly mutated witnesses.
+    /// Any mutation that is accepted indicates an underconstrained column.
+    Fuzz(FuzzOptions),
+}
+
+impl TryFrom<BackendOptions> for Mode {
+    type Error = Error;
+
+    fn try_from(options: BackendOptions) -> Result<Self, Error> {
+        match options.as_str() {
+            "" => Ok(Self::Check),
+            "shrink" => Ok(Self::Shrink),
+            other => match FuzzOptions::parse(other) {
+                Some(options) => options.map(Self::Fuzz).map_err(Error::BackendError),
+                None => Err(Error::BackendError(format!(
+                    "Unsupported mock backend option: {options}"
+                ))),
+            },
+        }
+    }
+}
+
 impl<F: FieldElement> BackendFactory<F> for MockBackendFactory {
     fn create(
         &self,
         pil: Arc<Analyzed<F>>,
         fixed: Arc<Vec<(String, VariablySizedColumn<F>)>>,
-        _output_dir: Option<PathBuf>,
+        output_dir: Option<PathBuf>,
         _setup: Option<&mut dyn std::io::Read>,
         proving_key: Option<&mut dyn std::io::Read>,
         _verification_key: Option<&mut dyn std::io::Read>,
         verification_app_key: Option<&mut dyn std::io::Read>,
-        _backend_options: BackendOptions,
+        backend_options: BackendOptions,
     ) -> Result<Box<dyn Backend<F>>, Error> {
         if proving_key.is_some() {
             unimplemented!();
@@ -48,6 +84,12 @@ impl<F: FieldElement> BackendFactory<F> for MockBackendFactory {
         if verification_app_key.is_some() {
             unimplemented!();
         }
+        let mode = Mode::try_from(backend_options)?;
+        if mode == Mode::Shrink && output_dir.is_none() {
+            return Err(Error::BackendError(
+                "The shrink mode of the mock backend requires an output directory".to_string(),
+            ));
+        }
         let machine_to_pil = powdr_backend_utils::split_pil(&pil);
         let connections = Connection::get_all(&pil, &machine_to_pil);
         let bus_connections = BusInteraction::get_all(&pil, &machine_to_pil);
@@ -57,6 +99,8 @@ impl<F: FieldElement> BackendFactory<F> for MockBackendFactory {
             fixed,
             connections,
             bus_connections,
+            output_dir,
+            mode,
         }))
     }
 
@@ -70,6 +114,169 @@ pub(crate) struct MockBackend<F> {
     fixed: Arc<Vec<(String, VariablySizedColumn<F>)>>,
     connections: Vec<Connection<F>>,
     bus_connections: Vec<BusInteraction<F>>,
+    /// If set, a JSON report of all constraint violations is written to this directory.
+    output_dir: Option<PathBuf>,
+    mode: Mode,
+}
+
+impl<F: FieldElement> MockBackend<F> {
+    /// Runs all checks on the machines.
+    fn check(&self, machines: &BTreeMap<String, Machine<F>>) -> Report {
+        check_constraints(machines, None, &self.connections, &self.bus_connections)
+    }
+
+    /// Checks randomly mutated versions of a valid witness and returns the mutations
+    /// that are accepted. Each mutation changes one or several cells in a single row
+    /// of a single machine.
+    fn fuzz<'a>(
+        &'a self,
+        options: FuzzOptions,
+        witness: &[(String, Vec<F>)],
+        mut machines: BTreeMap<String, Machine<'a, F>>,
+        witgen_callback: &WitgenCallback<F>,
+        challenges: &BTreeMap<u64, F>,
+    ) -> Vec<AcceptedMutation> {
+        let mut mutator = Mutator::new(options.seed);
+        let machine_names = machines.keys().cloned().collect::<Vec<_>>();
+        // A mutation only affects the constraints of the mutated machine, its connections
+        // and the bus interactions on the same buses, so only those are checked.
+        let related_constraints = machine_names
+            .iter()
+            .map(|name| {
+                let connections = self
+                    .connections
+                    .iter()
+                    .filter(|connection| connection.involves(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let bus_interactions = BusInteraction::get_related(&self.bus_connections, name);
+                (name, (connections, bus_interactions))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut accepted = vec![];
+        for _ in 0..options.iterations {
+            let machine_name = mutator.choose(&machine_names);
+            let pil = &self.machine_to_pil[machine_name];
+            let mut columns =
+                powdr_backend_utils::machine_witness_columns(witness, pil, machine_name);
+            let cells = mutator.mutate(&mut columns);
+            if cells.is_empty() {
+                continue;
+            }
+
+            let mutated = Machine::try_new(
+                machine_name.clone(),
+                &columns,
+                &self.fixed,
+                pil,
+                witgen_callback,
+                challenges,
+            )
+            .unwrap();
+            let original = machines.insert(machine_name.clone(), mutated).unwrap();
+            let (connections, bus_interactions) = &related_constraints[machine_name];
+            let report = check_constraints(
+                &machines,
+                Some(machine_name.as_str()),
+                connections,
+                bus_interactions,
+            );
+            machines.insert(machine_name.clone(), original);
+
+            if report.is_ok() {
+                for cell in &cells {
+                    log::warn!(
+                        "Accepted mutation of {} in row {}: {} (originally {})",
+                        cell.column,
+                        cell.row,
+                        cell.value,
+                        cell.original
+                    );
+                }
+                accepted.push(AcceptedMutation {
+                    machine: machine_name.clone(),
+                    cells,
+                });
+            }
+        }
+        log::info!(
+            "{} of {} witness mutations were accepted",
+            accepted.len(),
+            options.iterations
+        );
+        accepted
+    }
+
+    /// Builds a minimal reproducer for the first failure in the report, checks that it
+    /// indeed fails in isolation and writes it to the output directory.
+    fn shrink(
+        &self,
+        report: &Report,
+        machines: &BTreeMap<String, Machine<F>>,
+        output_dir: &Path,
+        witgen_callback: WitgenCallback<F>,
+    ) -> Result<(), Error> {
+        let reproducer = Reproducer::try_new(report, machines, &self.connections)?;
+        let pil = powdr_pil_analyzer::analyze_string(&reproducer.pil).map_err(|errors| {
+            let errors = errors.iter().map(|e| e.to_string()).join("\n");
+            format!("Reproducer PIL does not analyze:\n{errors}")
+        })?;
+        let reproduces = MockBackendFactory
+            .create(
+                Arc::new(pil),
+                Default::default(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Default::default(),
+            )?
+            .prove(&reproducer.witness, None, witgen_callback)
+            .is_err();
+        if !reproduces {
+            log::warn!("The reproducer does not reproduce the failure in isolation.");
+        }
+
+        reproducer.write_to_dir(output_dir)?;
+        log::info!(
+            "Wrote reproducer to {} and {}",
+            output_dir.join(shrink::REPRODUCER_PIL_FILE_NAME).display(),
+            output_dir
+                .join(shrink::REPRODUCER_WITNESS_FILE_NAME)
+                .display()
+        );
+        Ok(())
+    }
+}
+
+/// Checks the given connections and bus interactions and the polynomial constraints
+/// of `machine`, or of all machines if it is None.
+fn check_constraints<F: FieldElement>(
+    machines: &BTreeMap<String, Machine<F>>,
+    machine: Option<&str>,
+    connections: &[Connection<F>],
+    bus_interactions: &[BusInteraction<F>],
+) -> Report {
+    let mut report = Report {
+        polynomial_constraints: machines
+            .iter()
+            .filter(|(name, _)| machine.is_none_or(|machine| machine == name.as_str()))
+            .flat_map(|(_, machine)| {
+                PolynomialConstraintChecker::new(machine)
+                    .check()
+                    .to_report(machine)
+            })
+            .collect(),
+        ..Default::default()
+    };
+    if let Err(errors) = ConnectionConstraintChecker::new(connections, machines).check() {
+        report.connections = errors.to_report();
+    }
+    if let Err(errors) = BusChecker::new(bus_interactions, machines).check() {
+        report.bus_interactions = errors.iter().map(|error| error.to_report()).collect();
+    }
+    report
 }
 
 impl<F: FieldElement> Backend<F> for MockBackend<F> {
@@ -125,21 +332,50 @@ impl<F: FieldElement> Backend<F> for MockBackend<F> {
             );
         }
 
-        let is_ok = machines.values().all(|machine| {
-            !PolynomialConstraintChecker::new(machine)
-                .check()
-                .has_errors()
-        }) && ConnectionConstraintChecker::new(&self.connections, &machines)
-            .check()
-            .is_ok()
-            && BusChecker::new(&self.bus_connections, &machines)
-                .check()
-                .is_ok();
-
-        match is_ok {
-            true => Ok(Vec::new()),
-            false => Err(Error::BackendError("Constraint check failed".to_string())),
+        let report = self.check(&machines);
+        if let Some(output_dir) = &self.output_dir {
+            let path = output_dir.join(REPORT_FILE_NAME);
+            report.write_to_file(&path)?;
+            log::info!("Wrote mock backend report to {}", path.display());
+
+            if self.mode == Mode::Shrink && !report.is_ok() {
+                // The constraint violation is the actual result, so a failure
+                // to shrink the witness is only reported alongside it.
+                if let Err(e) = self.shrink(&report, &machines, output_dir, witgen_callback) {
+                    let e = match e {
+                        Error::BackendError(e) => e,
+                        e => e.to_string(),
+                    };
+                    log::warn!("Shrinking the failing witness failed: {e}");
+                    return Err(Error::BackendError(format!(
+                        "Constraint check failed\nNote: shrinking the failing witness failed: {e}"
+                    )));
+                }
+                return Err(Error::BackendError("Constraint check failed".to_string()));
+            }
         }
+
+        if !report.is_ok() {
+            return Err(Error::BackendError("Constraint check failed".to_string()));
+        }
+
+        if let Mode::Fuzz(options) = self.mode {
+            let accepted = self.fuzz(options, witness, machines, &witgen_callback, &challenges);
+            if let Some(output_dir) = &self.output_dir {
+                let path = output_dir.join(FUZZ_REPORT_FILE_NAME);
+                fuzz::write_accepted_mutations(&accepted, &path)?;
+                log::info!("Wrote accepted mutations to {}", path.display());
+            }
+            if !accepted.is_empty() {
+                return Err(Error::BackendError(format!(
+                    "{} of {} witness mutations were accepted",
+                    accepted.len(),
+                    options.iterations
+                )));
+            }
+        }
+
+        Ok(Vec::new())
     }
 
     fn verify(&self, _proof: &[u8], _instances: &[Vec<F>]) -> Result<(), Error> {
@@ -8,7 +8,10 @@ use powdr_executor_utils::expression_evaluator::ExpressionEvaluator;
 use powdr_number::FieldElement;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use super::machine::Machine;
+use super::{
+    machine::Machine,
+    report::{referenced_column_values, PolynomialConstraintFailure},
+};
 
 pub struct PolynomialConstraintChecker<'a, F> {
     machine: &'a Machine<'a, F>,
@@ -127,8 +130,24 @@ impl<F: fmt::Display> MachineResult<'_, F> {
             log::error!("  ... and {} more errors", num_errors - MAX_ERRORS);
         }
     }
+}
 
-    pub fn has_errors(&self) -> bool {
-        !self.errors.is_empty()
+impl<F: FieldElement> MachineResult<'_, F> {
+    /// Converts all errors into report entries, reading the column values from `machine`.
+    pub fn to_report(&self, machine: &Machine<'_, F>) -> Vec<PolynomialConstraintFailure> {
+        self.errors
+            .iter()
+            .map(|error| PolynomialConstraintFailure {
+                machine: self.machine_name.clone(),
+                identity_id: error.identity.id,
+                identity: error.identity.to_string(),
+                row: error.row,
+                columns: referenced_column_values(
+                    machine,
+                    error.identity.all_children(),
+                    error.row,
+                ),
+            })
+            .collect()
     }
 }
@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path};
+
+use powdr_ast::analyzed::{
+    AlgebraicExpression, AlgebraicReference, AlgebraicReferenceThin, PolynomialType,
+};
+use powdr_executor_utils::expression_evaluator::ExpressionEvaluator;
+use powdr_number::FieldElement;
+use serde::Serialize;
+
+use super::machine::Machine;
+
+/// The name of the file the report is written to, relative to the output directory.
+pub const REPORT_FILE_NAME: &str = "mock_report.json";
+
+/// A machine-readable report of all constraint violations found by the mock backend.
+///
+/// All field elements are rendered as decimal strings and all collections are sorted,
+/// so that reports of the same witness are byte-for-byte identical and can be diffed.
+#[derive(Serialize, Default)]
+pub struct Report {
+    pub polynomial_constraints: Vec<PolynomialConstraintFailure>,
+    pub connections: Vec<ConnectionFailure>,
+    pub bus_interactions: Vec<BusInteractionFailure>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.polynomial_constraints.is_empty()
+            && self.connections.is_empty()
+            && self.bus_interactions.is_empty()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), std::io::Error> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// A polynomial identity that is not satisfied on a given row.
+#[derive(Serialize)]
+pub struct PolynomialConstraintFailure {
+    pub machine: String,
+    pub identity_id: u64,
+    pub identity: String,
+    pub row: usize,
+    /// The values of all columns referenced by the identity, by column name.
+    pub columns: BTreeMap<String, ColumnValues>,
+}
+
+/// The values of a column on the failing row and the row after it.
+#[derive(Serialize)]
+pub struct ColumnValues {
+    pub current: String,
+    pub next: String,
+}
+
+/// A lookup or permutation whose two sides do not match.
+#[derive(Serialize)]
+pub struct ConnectionFailure {
+    pub identity_id: u64,
+    pub identity: String,
+    pub caller: Option<String>,
+    pub callee: Option<String>,
+    /// Tuples that are in the caller, but not in the callee.
+    pub not_in_callee: Vec<TupleWithMultiplicity>,
+    /// Tuples that are in the callee, but not in the caller.
+    pub not_in_caller: Vec<TupleWithMultiplicity>,
+    /// Tuples that appear on both sides, but with different multiplicities.
+    pub multiplicity_mismatches: Vec<MultiplicityMismatch>,
+}
+
+#[derive(Serialize)]
+pub struct TupleWithMultiplicity {
+    /// A row in which the tuple appears.
+    pub row: usize,
+    pub values: Vec<String>,
+    pub multiplicity: usize,
+}
+
+#[derive(Serialize)]
+pub struct MultiplicityMismatch {
+    pub row: usize,
+    pub values: Vec<String>,
+    pub caller_multiplicity: usize,
+    pub callee_multiplicity: usize,
+}
+
+/// A tuple that is not sent and received the same number of times on a bus.
+#[derive(Serialize)]
+pub struct BusInteractionFailure {
+    pub bus_id: String,
+    pub payload: Vec<String>,
+    pub sends: Vec<BusInteractionCount>,
+    pub receives: Vec<BusInteractionCount>,
+}
+
+#[derive(Serialize)]
+pub struct BusInteractionCount {
+    pub machine: String,
+    pub identity_id: u64,
+    pub identity: String,
+    pub count: usize,
+}
+
+/// Returns the values of all columns referenced in `expressions` on `row` and the row after it.
+/// Intermediate columns are evaluated using their definition.
+pub fn referenced_column_values<'b, F: FieldElement>(
+    machine: &Machine<'_, F>,
+    expressions: impl Iterator<Item = &'b AlgebraicExpression<F>>,
+    row: usize,
+) -> BTreeMap<String, ColumnValues> {
+    let next_row = (row + 1) % machine.size;
+    let value_at = |reference: &AlgebraicReference, row: usize| -> F {
+        match reference.poly_id.ptype {
+            PolynomialType::Committed | PolynomialType::Constant => {
+                machine.values.trace[&reference.poly_id][row]
+            }
+            PolynomialType::Intermediate => {
+                let reference = AlgebraicReferenceThin {
+                    poly_id: reference.poly_id,
+                    next: false,
+                };
+                let definition = &machine.intermediate_definitions[&reference];
+                ExpressionEvaluator::new(machine.values.row(row), &machine.intermediate_definitions)
+                    .evaluate(definition)
+            }
+        }
+    };
+    expressions
+        .filter_map(|expr| match expr {
+            AlgebraicExpression::Reference(reference) => Some(reference),
+            _ => None,
+        })
+        .map(|reference| {
+            (
+                reference.name.clone(),
+                ColumnValues {
+                    current: value_at(reference, row).to_string(),
+                    next: value_at(reference, next_row).to_string(),
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use powdr_ast::{
+    analyzed::{
+        AlgebraicBinaryOperation, AlgebraicExpression, AlgebraicReferenceThin,
+        AlgebraicUnaryOperation, Identity, LookupIdentity, PermutationIdentity, PolyID,
+        PolynomialIdentity, PolynomialType, SelectedExpressions,
+    },
+    parsed::visitor::AllChildren,
+};
+use powdr_number::{write_polys_csv_file, CsvRenderMode, FieldElement};
+
+use super::{
+    connection_constraint_checker::{Connection, ConnectionKind},
+    machine::Machine,
+    report::{ConnectionFailure, PolynomialConstraintFailure, Report},
+};
+
+/// The name of the file the reproducer PIL is written to, relative to the output directory.
+pub const REPRODUCER_PIL_FILE_NAME: &str = "mock_repro.pil";
+/// The name of the file the reproducer witness is written to, relative to the output directory.
+pub const REPRODUCER_WITNESS_FILE_NAME: &str = "mock_repro_witness.csv";
+
+/// A standalone PIL file and witness that reproduce a single constraint violation.
+///
+/// The reproducer only contains the machines involved in the violation, only the columns
+/// referenced by the failing constraint and only the rows needed to observe the violation.
+/// Fixed columns are turned into witness columns, so that their values can be provided
+/// together with the witness; intermediate columns are inlined.
+pub struct Reproducer<F> {
+    pub pil: String,
+    pub witness: Vec<(String, Vec<F>)>,
+}
+
+impl<F: FieldElement> Reproducer<F> {
+    /// Builds a reproducer for the first failure in the report.
+    pub fn try_new(
+        report: &Report,
+        machines: &BTreeMap<String, Machine<'_, F>>,
+        connections: &[Connection<F>],
+    ) -> Result<Self, String> {
+        if let Some(failure) = report.polynomial_constraints.first() {
+            Self::for_polynomial_constraint(failure, &machines[&failure.machine])
+        } else if let Some(failure) = report.connections.first() {
+            let connection = connections
+                .iter()
+                .find(|connection| connection.id() == failure.identity_id)
+                .unwrap();
+            Self::for_connection(failure, connection, machines)
+        } else if !report.bus_interactions.is_empty() {
+            Err("Shrinking failing bus interactions is not supported".to_string())
+        } else {
+            Err("There is no failure to reproduce".to_string())
+        }
+    }
+
+    fn for_polynomial_constraint(
+        failure: &PolynomialConstraintFailure,
+        machine: &Machine<'_, F>,
+    ) -> Result<Self, String> {
+        let identity = machine
+            .pil
+            .identities
+            .iter()
+            .find_map(|identity| match identity {
+                Identity::Polynomial(identity) if identity.id == failure.identity_id => {
+                    Some(identity)
+                }
+                _ => None,
+            })
+            .unwrap();
+        let expression = inline_intermediates(&identity.expression, machine)?;
+
+        let mut builder = Builder::new();
+        builder.add_rows(
+            machine,
+            window(machine, failure.row, &expression),
+            &expression,
+        );
+        builder.identities.push(
+            PolynomialIdentity {
+                expression,
+                ..identity.clone()
+            }
+            .to_string(),
+        );
+        builder.build()
+    }
+
+    fn for_connection(
+        failure: &ConnectionFailure,
+        connection: &Connection<F>,
+        machines: &BTreeMap<String, Machine<'_, F>>,
+    ) -> Result<Self, String> {
+        if failure.not_in_caller.is_empty() && failure.not_in_callee.is_empty() {
+            return Err(
+                "Shrinking connections with mismatching multiplicities is not supported"
+                    .to_string(),
+            );
+        }
+
+        let left =
+            inline_selected_expressions(&connection.left, failure.caller.as_ref(), machines)?;
+        let right =
+            inline_selected_expressions(&connection.right, failure.callee.as_ref(), machines)?;
+
+        let mut builder = Builder::new();
+        if let Some(caller) = &failure.caller {
+            let machine = &machines[caller];
+            // For a lookup, a single tuple that is missing in the callee is enough to
+            // observe the failure. In all other cases, we need the entire machine.
+            match (&connection.kind, failure.not_in_callee.first()) {
+                (ConnectionKind::Lookup, Some(tuple)) if failure.callee != failure.caller => {
+                    builder.add_rows(machine, window(machine, tuple.row, &left), &left)
+                }
+                _ => builder.add_rows(machine, (0..machine.size).collect(), &left),
+            }
+        }
+        if let Some(callee) = &failure.callee {
+            let machine = &machines[callee];
+            builder.add_rows(machine, (0..machine.size).collect(), &right);
+        }
+
+        let (id, source) = (connection.id(), Default::default());
+        builder.identities.push(match connection.kind {
+            ConnectionKind::Lookup => LookupIdentity {
+                id,
+                source,
+                left,
+                right,
+            }
+            .to_string(),
+            ConnectionKind::Permutation => PermutationIdentity {
+                id,
+                source,
+                left,
+                right,
+            }
+            .to_string(),
+        });
+        builder.build()
+    }
+
+    pub fn write_to_dir(&self, output_dir: &Path) -> Result<(), std::io::Error> {
+        let mut pil_file = BufWriter::new(File::create(output_dir.join(REPRODUCER_PIL_FILE_NAME))?);
+        pil_file.write_all(self.pil.as_bytes())?;
+
+        let witness_file =
+            BufWriter::new(File::create(output_dir.join(REPRODUCER_WITNESS_FILE_NAME))?);
+        let columns = self
+            .witness
+            .iter()
+            .map(|(name, values)| (name, values.as_slice()))
+            .collect::<Vec<_>>();
+        write_polys_csv_file(witness_file, CsvRenderMode::Hex, &columns);
+        Ok(())
+    }
+}
+
+/// Collects the parts of the machines that make it into the reproducer.
+struct Builder<'a, F> {
+    /// For each machine name, the machine, the rows to include and the referenced columns.
+    machines: BTreeMap<String, (&'a Machine<'a, F>, Vec<usize>, BTreeSet<PolyID>)>,
+    identities: Vec<String>,
+}
+
+impl<'a, F: FieldElement> Builder<'a, F> {
+    fn new() -> Self {
+        Self {
+            machines: Default::default(),
+            identities: vec![],
+        }
+    }
+
+    /// Includes the given rows of the machine and all columns referenced by `expressions`.
+    /// If the machine has already been added, the rows must be the same.
+    fn add_rows<E: AllChildren<AlgebraicExpression<F>>>(
+        &mut self,
+        machine: &'a Machine<'a, F>,
+        rows: Vec<usize>,
+        expressions: &E,
+    ) {
+        let (_, existing_rows, columns) = self
+            .machines
+            .entry(machine.machine_name.clone())
+            .or_insert_with(|| (machine, rows.clone(), Default::default()));
+        assert_eq!(*existing_rows, rows);
+        columns.extend(expressions.all_children().filter_map(|e| match e {
+            AlgebraicExpression::Reference(reference) => Some(reference.poly_id),
+            _ => None,
+        }));
+    }
+
+    fn build(self) -> Result<Reproducer<F>, String> {
+        let mut pil = String::new();
+        let mut witness = vec![];
+        for (machine_name, (machine, rows, columns)) in self.machines {
+            pil.push_str(&format!("namespace {machine_name}({});\n", rows.len()));
+            // Whole arrays are declared, even if only some of their elements are referenced.
+            let symbols = machine
+                .pil
+                .committed_polys_in_source_order()
+                .chain(machine.pil.constant_polys_in_source_order())
+                .map(|(symbol, _)| symbol)
+                .filter(|symbol| {
+                    symbol
+                        .array_elements()
+                        .any(|(_, poly_id)| columns.contains(&poly_id))
+                });
+            for symbol in symbols {
+                let name = symbol
+                    .absolute_name
+                    .strip_prefix(&format!("{machine_name}::"))
+                    .ok_or_else(|| {
+                        format!(
+                            "Column {} is not in namespace {machine_name}",
+                            symbol.absolute_name
+                        )
+                    })?;
+                let length = symbol.length.map(|l| format!("[{l}]")).unwrap_or_default();
+                pil.push_str(&format!("    col witness {name}{length};\n"));
+                witness.extend(symbol.array_elements().map(|(name, poly_id)| {
+                    let values = &machine.values.trace[&poly_id];
+                    (name, rows.iter().map(|row| values[*row]).collect())
+                }));
+            }
+        }
+        for identity in self.identities {
+            pil.push_str(&format!("{identity}\n"));
+        }
+        Ok(Reproducer { pil, witness })
+    }
+}
+
+/// Returns the rows needed to evaluate `expressions` on the given row.
+fn window<F, E: AllChildren<AlgebraicExpression<F>>>(
+    machine: &Machine<'_, F>,
+    row: usize,
+    expressions: &E,
+) -> Vec<usize> {
+    let uses_next = expressions
+        .all_children()
+        .any(|e| matches!(e, AlgebraicExpression::Reference(reference) if reference.next));
+    if uses_next {
+        vec![row, (row + 1) % machine.size]
+    } else {
+        vec![row]
+    }
+}
+
+fn inline_selected_expressions<F: FieldElement>(
+    selected_expressions: &SelectedExpressions<F>,
+    machine_name: Option<&String>,
+    machines: &BTreeMap<String, Machine<'_, F>>,
+) -> Result<SelectedExpressions<F>, String> {
+    let Some(machine_name) = machine_name else {
+        // There are no column references, so there is nothing to inline.
+        return Ok(selected_expressions.clone());
+    };
+    let machine = machines
+        .get(machine_name)
+        .ok_or_else(|| format!("Machine {machine_name} is empty"))?;
+    Ok(SelectedExpressions {
+        selector: inline_intermediates(&selected_expressions.selector, machine)?,
+        expressions: selected_expressions
+            .expressions
+            .iter()
+            .map(|e| inline_intermediates(e, machine))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Replaces all references to intermediate columns by their definition.
+/// Fails if the expression references publics or challenges, which the reproducer can't provide.
+fn inline_intermediates<F: FieldElement>(
+    e: &AlgebraicExpression<F>,
+    machine: &Machine<'_, F>,
+) -> Result<AlgebraicExpression<F>, String> {
+    match e {
+        AlgebraicExpression::Reference(reference)
+            if reference.poly_id.ptype == PolynomialType::Intermediate =>
+        {
+            let definition = machine
+                .intermediate_definitions
+                .get(&AlgebraicReferenceThin::from(reference))
+                .ok_or_else(|| format!("Cannot inline {reference}: its definition uses `'`"))?;
+            inline_intermediates(definition, machine)
+        }
+        AlgebraicExpression::PublicReference(_) | AlgebraicExpression::Challenge(_) => {
+            Err(format!(
+                "Shrinking constraints that reference publics or challenges is not supported: {e}"
+            ))
+        }
+        AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation { left, op, right }) => {
+            Ok(AlgebraicExpression::new_binary(
+                inline_intermediates(left, machine)?,
+                *op,
+                inline_intermediates(right, machine)?,
+            ))
+        }
+        AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation { op, expr }) => Ok(
+            AlgebraicExpression::new_unary(*op, inline_intermediates(expr, machine)?),
+        ),
+        AlgebraicExpression::Reference(_) | AlgebraicExpression::Number(_) => Ok(e.clone()),
+    }
+}
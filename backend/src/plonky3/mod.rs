@@ -5,7 +5,7 @@ use std::{io, path::PathBuf, sync::Arc};
 use powdr_ast::analyzed::Analyzed;
 use powdr_executor::{constant_evaluator::VariablySizedColumn, witgen::WitgenCallback};
 use powdr_number::{BabyBearField, GoldilocksField, KoalaBearField, Mersenne31Field};
-use powdr_plonky3::{Commitment, FieldElementMap, ProverData};
+use powdr_plonky3::{Commitment, FieldElementMap, FriParameters, ProverData, VerifierInput};
 use serde::{Deserialize, Serialize};
 use stark::Plonky3Prover;
 
@@ -13,6 +13,42 @@ use crate::{
     field_filter::generalize_factory, Backend, BackendFactory, BackendOptions, Error, Proof,
 };
 
+/// Parses the FRI parameters from the backend options, which are a profile
+/// (`fast-dev`, `standard` or `conservative`, the default being `standard`),
+/// optionally followed by comma-separated overrides, e.g.
+/// `conservative,num_queries=80` or `log_blowup=2,pow_bits=20`.
+pub(crate) fn fri_parameters(options: &str) -> Result<FriParameters, String> {
+    let mut parts = options
+        .split(',')
+        .filter(|part| !part.is_empty())
+        .peekable();
+    let mut parameters = match parts.next_if(|part| !part.contains('=')) {
+        Some("fast-dev") => FriParameters::FAST_DEV,
+        Some("standard") => FriParameters::STANDARD,
+        Some("conservative") => FriParameters::CONSERVATIVE,
+        Some(profile) => return Err(format!("Unknown Plonky3 profile: {profile}")),
+        None => FriParameters::default(),
+    };
+    for part in parts {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid Plonky3 option: {part}"))?;
+        let value = value
+            .parse()
+            .map_err(|_| format!("Invalid value for {key}: {value}"))?;
+        match key {
+            "log_blowup" => parameters.log_blowup = value,
+            "num_queries" => parameters.num_queries = value,
+            "pow_bits" => parameters.proof_of_work_bits = value,
+            _ => return Err(format!("Unknown Plonky3 option: {key}")),
+        }
+    }
+    if parameters.log_blowup == 0 || parameters.num_queries == 0 {
+        return Err("The blowup factor and the number of queries must be positive".to_string());
+    }
+    Ok(parameters)
+}
+
 struct RestrictedFactory;
 
 impl<T: FieldElementMap> BackendFactory<T> for RestrictedFactory
@@ -29,7 +65,7 @@ where
         proving_key: Option<&mut dyn io::Read>,
         verification_key: Option<&mut dyn io::Read>,
         verification_app_key: Option<&mut dyn io::Read>,
-        _: BackendOptions,
+        options: BackendOptions,
     ) -> Result<Box<dyn crate::Backend<T>>, Error> {
         if setup.is_some() {
             return Err(Error::NoSetupAvailable);
@@ -38,12 +74,14 @@ where
             return Err(Error::NoAggregationAvailable);
         }
 
-        let mut p3 = Box::new(Plonky3Prover::new(pil.clone(), fixed));
+        let fri_parameters = fri_parameters(&options).map_err(Error::BackendError)?;
+
+        let mut p3 = Box::new(Plonky3Prover::new(pil.clone(), fixed, fri_parameters));
 
         match (proving_key, verification_key) {
             (Some(pk), Some(vk)) => {
-                p3.set_proving_key(pk);
-                p3.set_verifying_key(vk);
+                p3.set_proving_key(pk)?;
+                p3.set_verifying_key(vk)?;
             }
             _ => {
                 p3.setup();
@@ -54,6 +92,27 @@ where
     }
 }
 
+/// Builds the input of a plonky3 verifier running in powdr from a proof of `pil`,
+/// its verification key and public values, see [VerifierInput].
+/// The verifier is only accelerated over Goldilocks, where the Poseidon2 permutation
+/// is delegated to the `Poseidon2GL` machine.
+pub fn verifier_input<T: FieldElementMap>(
+    pil: Arc<Analyzed<T>>,
+    proof: &[u8],
+    verification_key: &mut dyn io::Read,
+    instances: &[T],
+    options: BackendOptions,
+) -> Result<VerifierInput<T>, Error>
+where
+    ProverData<T>: Send + Serialize + for<'a> Deserialize<'a>,
+    Commitment<T>: Send,
+{
+    let fri_parameters = fri_parameters(&options)?;
+    let mut p3 = Plonky3Prover::new(pil, Arc::new(vec![]), fri_parameters);
+    p3.set_verifying_key(verification_key)?;
+    Ok(p3.into_verifier_input(proof, instances)?)
+}
+
 generalize_factory!(Factory <- RestrictedFactory, [BabyBearField, KoalaBearField, GoldilocksField, Mersenne31Field]);
 
 impl<T: FieldElementMap> Backend<T> for Plonky3Prover<T>
@@ -94,3 +153,42 @@ where
             .map_err(|e| Error::BackendError(e.to_string()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use powdr_plonky3::FriParameters;
+
+    use super::fri_parameters;
+
+    #[test]
+    fn parse_fri_parameters() {
+        assert_eq!(fri_parameters(""), Ok(FriParameters::STANDARD));
+        assert_eq!(fri_parameters("fast-dev"), Ok(FriParameters::FAST_DEV));
+        assert_eq!(
+            fri_parameters("conservative"),
+            Ok(FriParameters::CONSERVATIVE)
+        );
+        assert_eq!(
+            fri_parameters("conservative,num_queries=80"),
+            Ok(FriParameters {
+                num_queries: 80,
+                ..FriParameters::CONSERVATIVE
+            })
+        );
+        assert_eq!(
+            fri_parameters("log_blowup=3,num_queries=50,pow_bits=20"),
+            Ok(FriParameters {
+                log_blowup: 3,
+                num_queries: 50,
+                proof_of_work_bits: 20,
+            })
+        );
+        assert_eq!(
+            fri_parameters("fast"),
+            Err("Unknown Plonky3 profile: fast".to_string())
+        );
+        assert!(fri_parameters("fast,num_queries=80").is_err());
+        assert!(fri_parameters("num_queries=many").is_err());
+        assert!(fri_parameters("log_blowup=0").is_err());
+    }
+}
@@ -15,9 +15,12 @@ use powdr_ast::analyzed::Analyzed;
 
 use powdr_executor::witgen::WitgenCallback;
 
+use crate::Error;
+
 use powdr_plonky3::{
-    prove, verify, Challenger, Commitment, ConstraintSystem, FieldElementMap, PowdrCircuit, Proof,
-    ProverData, StarkProvingKey, StarkVerifyingKey, TableProvingKey, TableProvingKeyCollection,
+    prove, verify, Challenger, Commitment, ConstraintSystem, FieldElementMap, FriParameters,
+    PowdrCircuit, Proof, ProverData, StarkProvingKey, StarkVerifyingKey, TableProvingKey,
+    TableProvingKeyCollection, VerifierInput,
 };
 
 use p3_uni_stark::StarkGenericConfig;
@@ -37,6 +40,8 @@ where
     proving_key: Option<StarkProvingKey<T::Config>>,
     /// Verifying key
     verifying_key: Option<StarkVerifyingKey<T::Config>>,
+    /// The FRI parameters used for proving and verification
+    fri_parameters: FriParameters,
 }
 
 pub enum KeyExportError {
@@ -61,6 +66,7 @@ where
     pub fn new(
         analyzed: Arc<Analyzed<T>>,
         fixed: Arc<Vec<(String, VariablySizedColumn<T>)>>,
+        fri_parameters: FriParameters,
     ) -> Self {
         Self {
             split: powdr_backend_utils::split_pil(&analyzed)
@@ -74,15 +80,36 @@ where
             fixed,
             proving_key: None,
             verifying_key: None,
+            fri_parameters,
         }
     }
 
-    pub fn set_proving_key(&mut self, rdr: &mut dyn std::io::Read) {
-        self.proving_key = Some(bincode::deserialize_from(rdr).unwrap());
+    pub fn set_proving_key(&mut self, rdr: &mut dyn std::io::Read) -> Result<(), Error> {
+        let proving_key: StarkProvingKey<T::Config> = bincode::deserialize_from(rdr)
+            .map_err(|e| Error::BackendError(format!("Failed to deserialize proving key: {e}")))?;
+        self.check_fri_parameters(&proving_key.fri_parameters, "proving key")?;
+        self.proving_key = Some(proving_key);
+        Ok(())
     }
 
-    pub fn set_verifying_key(&mut self, rdr: &mut dyn std::io::Read) {
-        self.verifying_key = Some(bincode::deserialize_from(rdr).unwrap());
+    pub fn set_verifying_key(&mut self, rdr: &mut dyn std::io::Read) -> Result<(), Error> {
+        let verifying_key: StarkVerifyingKey<T::Config> =
+            bincode::deserialize_from(rdr).map_err(|e| {
+                Error::BackendError(format!("Failed to deserialize verification key: {e}"))
+            })?;
+        self.check_fri_parameters(&verifying_key.fri_parameters, "verification key")?;
+        self.verifying_key = Some(verifying_key);
+        Ok(())
+    }
+
+    fn check_fri_parameters(&self, fri_parameters: &FriParameters, key: &str) -> Result<(), Error> {
+        if fri_parameters != &self.fri_parameters {
+            return Err(Error::BackendError(format!(
+                "The {key} was generated with FRI parameters {fri_parameters:?}, but the backend is configured with {:?}",
+                self.fri_parameters
+            )));
+        }
+        Ok(())
     }
 
     pub fn export_proving_key(
@@ -139,7 +166,7 @@ where
                                     .collect::<Vec<_>>();
 
                                 // get the config
-                                let config = T::get_config();
+                                let config = T::get_config(&self.fri_parameters);
 
                                 // commit to the fixed columns
                                 let pcs = config.pcs();
@@ -196,8 +223,12 @@ where
                     )
                 })
                 .collect(),
+            fri_parameters: self.fri_parameters,
+        };
+        let proving_key = StarkProvingKey {
+            preprocessed,
+            fri_parameters: self.fri_parameters,
         };
-        let proving_key = StarkProvingKey { preprocessed };
 
         self.proving_key = Some(proving_key);
         self.verifying_key = Some(verifying_key);
@@ -236,7 +267,8 @@ where
             &circuit,
             &mut witness_by_machine,
             &mut challenger,
-        );
+            &self.fri_parameters,
+        )?;
 
         let mut challenger = T::get_challenger();
 
@@ -274,6 +306,7 @@ where
             &mut challenger,
             &proof,
             public_values,
+            &self.fri_parameters,
         )
         .unwrap();
         Ok(bincode::serialize(&proof).unwrap())
@@ -288,6 +321,50 @@ where
 
         let verifying_key = self.verifying_key.as_ref();
 
+        let instance_map = self.instance_map(instances);
+
+        verify(
+            verifying_key,
+            &self
+                .split
+                .iter()
+                .map(|(name, (_, constraints))| (name, constraints))
+                .collect(),
+            &mut challenger,
+            &proof,
+            instance_map,
+            &self.fri_parameters,
+        )
+        .map_err(|e| format!("Failed to verify proof: {e:?}"))
+    }
+
+    /// Builds the input of a verifier running in powdr, see [VerifierInput].
+    pub fn into_verifier_input(
+        self,
+        proof: &[u8],
+        instances: &[T],
+    ) -> Result<VerifierInput<T>, String> {
+        let proof: Proof<_> =
+            bincode::deserialize(proof).map_err(|e| format!("Failed to deserialize proof: {e}"))?;
+        let public_values = self.instance_map(instances);
+
+        Ok(VerifierInput {
+            machines: self
+                .split
+                .into_iter()
+                .map(|(name, (pil, _))| (name, pil))
+                .collect(),
+            verifying_key: self.verifying_key.ok_or_else(|| {
+                "A verification key is required to verify a proof in powdr".to_string()
+            })?,
+            proof,
+            public_values,
+            fri_parameters: self.fri_parameters,
+        })
+    }
+
+    /// Distributes the public values to the machines and stages they belong to.
+    fn instance_map(&self, instances: &[T]) -> BTreeMap<String, Vec<Vec<T>>> {
         let stage_count = self.analyzed.stage_count();
 
         let mut instance_map: BTreeMap<String, Vec<Vec<T>>> = self
@@ -308,18 +385,7 @@ where
                 instance_map.get_mut(namespace).unwrap()[*stage as usize].push(*value);
             });
 
-        verify(
-            verifying_key,
-            &self
-                .split
-                .iter()
-                .map(|(name, (_, constraints))| (name, constraints))
-                .collect(),
-            &mut challenger,
-            &proof,
-            instance_map,
-        )
-        .map_err(|e| format!("Failed to verify proof: {e:?}"))
+        instance_map
     }
 }
 
@@ -331,7 +397,7 @@ mod tests {
     use powdr_pipeline::Pipeline;
     use test_log::test;
 
-    use powdr_plonky3::{Commitment, FieldElementMap, ProverData};
+    use powdr_plonky3::{Commitment, FieldElementMap, FriParameters, ProverData, VerifierInput};
 
     /// Prove and verify execution over all supported fields
     fn run_test(pil: &str) {
@@ -355,7 +421,7 @@ mod tests {
         let witness = &mut pipeline.compute_witness().unwrap();
         let fixed = pipeline.compute_fixed_cols().unwrap();
 
-        let mut prover = Plonky3Prover::new(pil, fixed);
+        let mut prover = Plonky3Prover::new(pil, fixed, FriParameters::default());
         prover.setup();
         let proof = prover.prove(witness, witness_callback);
 
@@ -374,6 +440,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fri_parameters_mismatch() {
+        let content = "namespace Global(8); pol fixed z = [1, 2]*; pol witness a; a = z + 1;";
+        let mut pipeline = Pipeline::<GoldilocksField>::default().from_pil_string(content.into());
+        let pil = pipeline.compute_optimized_pil().unwrap();
+        let witness_callback = pipeline.witgen_callback().unwrap();
+        let witness = pipeline.compute_witness().unwrap();
+        let fixed = pipeline.compute_fixed_cols().unwrap();
+
+        let mut prover = Plonky3Prover::new(pil.clone(), fixed.clone(), FriParameters::FAST_DEV);
+        prover.setup();
+        let proof = prover.prove(&witness, witness_callback).unwrap();
+        prover.verify(&proof, &[]).unwrap();
+
+        let mut verifier = Plonky3Prover::new(pil, fixed, FriParameters::STANDARD);
+        verifier.setup();
+        let err = verifier.verify(&proof, &[]).unwrap_err();
+        assert!(err.contains("FriParametersMismatch"), "{err}");
+
+        let vk = prover.export_verifying_key().ok().unwrap();
+        assert!(verifier.set_verifying_key(&mut vk.as_slice()).is_err());
+    }
+
+    #[test]
+    fn verifier_input() {
+        let content = "
+        namespace Global(8);
+            pol fixed FIRST = [1] + [0]*;
+            pol witness x;
+            x' = (1 - FIRST') * (x + 1);
+            public out = x(7);
+        ";
+        let mut pipeline = Pipeline::<GoldilocksField>::default().from_pil_string(content.into());
+        let pil = pipeline.compute_optimized_pil().unwrap();
+        let witness_callback = pipeline.witgen_callback().unwrap();
+        let witness = pipeline.compute_witness().unwrap();
+        let fixed = pipeline.compute_fixed_cols().unwrap();
+
+        let mut prover = Plonky3Prover::new(pil, fixed, FriParameters::FAST_DEV);
+        prover.setup();
+        let proof = prover.prove(&witness, witness_callback).unwrap();
+
+        // The input is passed to the verifier serialized, as prover data.
+        let input = prover
+            .into_verifier_input(&proof, &[GoldilocksField::from(7)])
+            .unwrap();
+        let mut input: VerifierInput<GoldilocksField> =
+            bincode::deserialize(&bincode::serialize(&input).unwrap()).unwrap();
+        input.verify().unwrap();
+
+        input.public_values.get_mut("Global").unwrap()[0][0] = GoldilocksField::from(8);
+        assert!(input.verify().is_err());
+    }
+
     #[test]
     fn public_values() {
         let content = "
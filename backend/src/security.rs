@@ -0,0 +1,265 @@
+//! Estimates of the security of the FRI-based STARK backends.
+//!
+//! The conjectured bound follows the ethSTARK conjecture, i.e. it assumes that FRI
+//! queries are as sound as the rate of the code allows. The proven bound uses the
+//! list-decoding analysis up to the Johnson bound from the ethSTARK documentation
+//! (<https://eprint.iacr.org/2021/582>), where FRI only achieves about half the bits per query.
+
+use std::fmt::{self, Display, Formatter};
+
+use powdr_ast::analyzed::Analyzed;
+use powdr_number::{FieldElement, KnownField};
+
+use crate::{BackendOptions, BackendType};
+
+/// The parameters of a FRI-based STARK that determine its security.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecurityParameters {
+    /// The number of bits of the base field.
+    pub field_bits: u32,
+    /// The degree of the extension field challenges are drawn from.
+    pub extension_degree: u32,
+    /// The log2 of the FRI blowup factor.
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SecurityEstimate {
+    /// Bits of security under the ethSTARK conjecture.
+    pub conjectured_bits: f64,
+    /// Bits of security under the proven list-decoding bound.
+    pub proven_bits: f64,
+}
+
+impl Display for SecurityEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "conjectured: {:.1} bits, proven: {:.1} bits",
+            self.conjectured_bits, self.proven_bits
+        )
+    }
+}
+
+/// The largest proximity parameter considered when optimizing the proven bound.
+const MAX_PROXIMITY_PARAMETER: usize = 256;
+
+impl SecurityParameters {
+    /// Returns the parameters the given backend uses with the given options.
+    #[cfg_attr(not(feature = "plonky3"), allow(unused_variables))]
+    pub fn for_backend<T: FieldElement>(
+        backend: BackendType,
+        options: &BackendOptions,
+    ) -> Result<Self, String> {
+        let field = T::known_field();
+        match backend {
+            #[cfg(feature = "plonky3")]
+            BackendType::Plonky3 | BackendType::Plonky3Composite => {
+                let extension_degree = match field {
+                    Some(KnownField::GoldilocksField) => 2,
+                    Some(KnownField::BabyBearField | KnownField::KoalaBearField) => 4,
+                    Some(KnownField::Mersenne31Field) => 3,
+                    _ => return Err(format!("Unsupported field for {backend}")),
+                };
+                // The composite backend takes its own options before passing the
+                // rest on to the backend of each machine.
+                let options = match backend {
+                    BackendType::Plonky3Composite => {
+                        crate::composite::scheduler::CompositeOptions::split(options.clone())
+                            .map_err(|e| match e {
+                                crate::Error::BackendError(e) => e,
+                                e => e.to_string(),
+                            })?
+                            .1
+                    }
+                    _ => options.clone(),
+                };
+                let fri_parameters = crate::plonky3::fri_parameters(&options)?;
+                Ok(Self {
+                    field_bits: T::BITS,
+                    extension_degree,
+                    log_blowup: fri_parameters.log_blowup,
+                    num_queries: fri_parameters.num_queries,
+                    proof_of_work_bits: fri_parameters.proof_of_work_bits,
+                })
+            }
+            #[cfg(feature = "stwo")]
+            BackendType::Stwo | BackendType::StwoComposite => {
+                if field != Some(KnownField::Mersenne31Field) {
+                    return Err(format!("Unsupported field for {backend}"));
+                }
+                Ok(Self {
+                    field_bits: T::BITS,
+                    extension_degree: 4,
+                    log_blowup: crate::stwo::FRI_LOG_BLOWUP,
+                    num_queries: crate::stwo::FRI_NUM_QUERIES,
+                    proof_of_work_bits: crate::stwo::FRI_PROOF_OF_WORK_BITS,
+                })
+            }
+            #[cfg(feature = "estark-polygon")]
+            BackendType::EStarkPolygon | BackendType::EStarkPolygonComposite => {
+                Self::estark(field, backend)
+            }
+            #[cfg(feature = "estark-starky")]
+            BackendType::EStarkStarky
+            | BackendType::EStarkStarkyComposite
+            | BackendType::EStarkDump
+            | BackendType::EStarkDumpComposite => Self::estark(field, backend),
+            _ => Err(format!(
+                "Security estimates are only available for FRI-based backends, not for {backend}"
+            )),
+        }
+    }
+
+    #[cfg(any(feature = "estark-polygon", feature = "estark-starky"))]
+    fn estark(field: Option<KnownField>, backend: BackendType) -> Result<Self, String> {
+        if field != Some(KnownField::GoldilocksField) {
+            return Err(format!("Unsupported field for {backend}"));
+        }
+        Ok(Self {
+            field_bits: 64,
+            extension_degree: 3,
+            log_blowup: crate::estark::FRI_LOG_BLOWUP,
+            num_queries: crate::estark::FRI_NUM_QUERIES,
+            proof_of_work_bits: 0,
+        })
+    }
+
+    /// Estimates the security of a proof of traces of length `2^log_trace_length`
+    /// and constraints of degree at most `max_constraint_degree`.
+    pub fn estimate(
+        &self,
+        max_constraint_degree: usize,
+        log_trace_length: u32,
+    ) -> SecurityEstimate {
+        let extension_field_bits = (self.field_bits * self.extension_degree) as f64;
+        let log_blowup = self.log_blowup as f64;
+        let log_trace_length = log_trace_length as f64;
+        let log_lde_size = log_trace_length + log_blowup;
+        let log_constraint_degree = (max_constraint_degree.max(1) as f64).log2();
+        let num_queries = self.num_queries as f64;
+        let proof_of_work_bits = self.proof_of_work_bits as f64;
+
+        // Under the conjecture, each query contributes `log_blowup` bits and the
+        // out-of-domain sampling fails with probability about `degree * trace_length / |F|`.
+        let conjectured_bits = (num_queries * log_blowup + proof_of_work_bits)
+            .min(extension_field_bits - log_lde_size)
+            .min(extension_field_bits - log_constraint_degree - log_trace_length);
+
+        let proven_bits = (3..=MAX_PROXIMITY_PARAMETER)
+            .map(|m| {
+                let m = m as f64 + 0.5;
+                let sqrt_rate = (-log_blowup / 2.0).exp2();
+                // The maximal distance from the code is `1 - alpha`.
+                let log_alpha = (1.0 + 0.5 / (m - 0.5)).log2() + sqrt_rate.log2();
+                // The size of the list of codewords within that distance.
+                let log_list_size = m.log2() - sqrt_rate.log2();
+                let query_bits = -num_queries * log_alpha + proof_of_work_bits;
+                // The commit phase error of FRI, from the proximity gaps theorem.
+                let commit_bits = extension_field_bits
+                    - (7.0 * m.log2() - 3.0f64.log2() + 1.5 * log_blowup + 2.0 * log_lde_size);
+                // The error of the DEEP-ALI step, for each codeword in the list.
+                let deep_ali_bits = extension_field_bits
+                    - (log_list_size + log_constraint_degree + log_trace_length + 1.0);
+                query_bits.min(commit_bits).min(deep_ali_bits)
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        SecurityEstimate {
+            conjectured_bits,
+            proven_bits,
+        }
+    }
+}
+
+/// Estimates the security of a proof of the given PIL with the given backend and options.
+/// For composite backends, this is the security of the largest machine.
+pub fn estimate_security<T: FieldElement>(
+    backend: BackendType,
+    options: &BackendOptions,
+    pil: &Analyzed<T>,
+) -> Result<SecurityEstimate, String> {
+    let parameters = SecurityParameters::for_backend::<T>(backend, options)?;
+    let intermediate_definitions = pil.intermediate_definitions();
+    let max_constraint_degree = pil
+        .identities
+        .iter()
+        .map(|identity| identity.degree(&intermediate_definitions))
+        .max()
+        .unwrap_or(1);
+    let max_trace_length = pil
+        .degree_ranges()
+        .into_iter()
+        .map(|range| range.max)
+        .max()
+        .ok_or_else(|| "The PIL has no degree".to_string())?;
+    Ok(parameters.estimate(
+        max_constraint_degree,
+        max_trace_length.next_power_of_two().ilog2(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parameters(log_blowup: usize, num_queries: usize) -> SecurityParameters {
+        SecurityParameters {
+            field_bits: 64,
+            extension_degree: 2,
+            log_blowup,
+            num_queries,
+            proof_of_work_bits: 16,
+        }
+    }
+
+    #[test]
+    fn conjectured_security() {
+        // Limited by the queries.
+        let estimate = parameters(1, 50).estimate(3, 20);
+        assert_eq!(estimate.conjectured_bits, 66.0);
+        // Limited by the size of the field.
+        let estimate = parameters(1, 100).estimate(2, 20);
+        assert_eq!(estimate.conjectured_bits, 128.0 - 21.0);
+    }
+
+    #[test]
+    fn proven_is_below_conjectured() {
+        for log_blowup in 1..4 {
+            for num_queries in [16, 50, 100, 200] {
+                let estimate = parameters(log_blowup, num_queries).estimate(3, 22);
+                assert!(estimate.proven_bits > 0.0);
+                assert!(estimate.proven_bits < estimate.conjectured_bits);
+            }
+        }
+    }
+
+    #[test]
+    fn more_queries_are_more_secure() {
+        let fewer = parameters(2, 20).estimate(3, 20);
+        let more = parameters(2, 40).estimate(3, 20);
+        assert!(more.conjectured_bits > fewer.conjectured_bits);
+        assert!(more.proven_bits > fewer.proven_bits);
+    }
+
+    #[cfg(feature = "plonky3")]
+    #[test]
+    fn composite_options() {
+        use powdr_number::GoldilocksField;
+
+        let options = "conservative,memory_budget=16G".to_string();
+        let parameters = SecurityParameters::for_backend::<GoldilocksField>(
+            BackendType::Plonky3Composite,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(parameters.log_blowup, 2);
+        // The memory budget is not an option of the machine backends.
+        assert!(
+            SecurityParameters::for_backend::<GoldilocksField>(BackendType::Plonky3, &options)
+                .is_err()
+        );
+    }
+}
@@ -50,17 +50,40 @@ where
     CircleEvaluation::new(domain, column)
 }
 
+/// Returns the witness columns of each stage, in source order.
+/// The witness columns of stage `i` are committed in the trace `ORIGINAL_TRACE_IDX + i`.
+pub fn witness_columns_by_stage(analyzed: &Analyzed<M31>) -> Vec<Vec<(String, PolyID)>> {
+    let mut witness_columns = vec![vec![]; analyzed.stage_count()];
+    for (symbol, _) in analyzed.definitions_in_source_order(PolynomialType::Committed) {
+        witness_columns[symbol.stage.unwrap_or_default() as usize].extend(symbol.array_elements());
+    }
+    witness_columns
+}
+
+/// Returns the number of pre-processed columns of a machine: the fixed columns, the
+/// shifted fixed columns that are referenced with next, and a selector column for
+/// each public.
+pub fn preprocessed_column_count(analyzed: &Analyzed<M31>) -> usize {
+    analyzed.constant_count()
+        + get_constant_with_next_list(analyzed).len()
+        + analyzed.publics_count()
+}
+
 pub struct PowdrEval {
     log_degree: u32,
     analyzed: Analyzed<M31>,
     // the pre-processed are indexed in the whole proof, instead of in each component.
     // this offset represents the index of the first pre-processed column in this component
     preprocess_col_offset: usize,
-    witness_columns: BTreeMap<PolyID, usize>,
+    witness_columns_by_stage: Vec<Vec<PolyID>>,
     constant_shifted: BTreeMap<PolyID, usize>,
     constant_columns: BTreeMap<PolyID, usize>,
-    // stwo supports maximum 2 stages, challenges are only created after stage 0
+    // for each public, its name and the witness column it refers to,
+    // in the order of the selector columns
+    public_selectors: Vec<(String, PolyID)>,
+    // the challenges of all stages
     pub challenges: BTreeMap<u64, M31>,
+    pub publics: BTreeMap<String, M31>,
 }
 
 impl PowdrEval {
@@ -69,12 +92,11 @@ impl PowdrEval {
         preprocess_col_offset: usize,
         log_degree: u32,
         challenges: BTreeMap<u64, M31>,
+        publics: BTreeMap<String, M31>,
     ) -> Self {
-        let witness_columns: BTreeMap<PolyID, usize> = analyzed
-            .definitions_in_source_order(PolynomialType::Committed)
-            .flat_map(|(symbol, _)| symbol.array_elements())
-            .enumerate()
-            .map(|(index, (_, id))| (id, index))
+        let witness_columns_by_stage = witness_columns_by_stage(&analyzed)
+            .into_iter()
+            .map(|columns| columns.into_iter().map(|(_, id)| id).collect())
             .collect();
 
         let constant_with_next_list = get_constant_with_next_list(&analyzed);
@@ -94,14 +116,22 @@ impl PowdrEval {
             .map(|(index, (_, id))| (id, index))
             .collect();
 
+        let public_selectors = analyzed
+            .get_publics()
+            .into_iter()
+            .map(|(name, _, poly_id, _, _)| (name, poly_id))
+            .collect();
+
         Self {
             log_degree,
             analyzed,
             preprocess_col_offset,
-            witness_columns,
+            witness_columns_by_stage,
             constant_shifted,
             constant_columns,
+            public_selectors,
             challenges,
+            publics,
         }
     }
 }
@@ -110,8 +140,8 @@ struct Data<'a, F> {
     witness_eval: &'a BTreeMap<PolyID, [F; 2]>,
     constant_shifted_eval: &'a BTreeMap<PolyID, F>,
     constant_eval: &'a BTreeMap<PolyID, F>,
-    // challenges for stage 1
     challenges: &'a BTreeMap<u64, F>,
+    publics: &'a BTreeMap<String, F>,
 }
 
 impl<F: Clone> TerminalAccess<F> for &Data<'_, F> {
@@ -129,8 +159,11 @@ impl<F: Clone> TerminalAccess<F> for &Data<'_, F> {
         }
     }
 
-    fn get_public(&self, _public: &str) -> F {
-        unimplemented!("Public references are not supported in stwo yet")
+    fn get_public(&self, public: &str) -> F {
+        self.publics
+            .get(public)
+            .expect("Referenced public value does not exist")
+            .clone()
     }
 
     fn get_challenge(&self, challenge: &Challenge) -> F {
@@ -146,18 +179,15 @@ impl FrameworkEval for PowdrEval {
         self.log_degree + 1
     }
     fn evaluate<E: EvalAtRow>(&self, mut eval: E) -> E {
-        assert!(
-            self.analyzed.publics_count() == 0,
-            "Error: Expected no public inputs, as they are not supported yet.",
-        );
-
         let witness_eval: BTreeMap<PolyID, [<E as EvalAtRow>::F; 2]> = self
-            .witness_columns
-            .keys()
-            .map(|poly_id| {
+            .witness_columns_by_stage
+            .iter()
+            .enumerate()
+            .flat_map(|(stage, poly_ids)| poly_ids.iter().map(move |poly_id| (stage, poly_id)))
+            .map(|(stage, poly_id)| {
                 (
                     *poly_id,
-                    eval.next_interaction_mask(ORIGINAL_TRACE_IDX, [0, 1]),
+                    eval.next_interaction_mask(ORIGINAL_TRACE_IDX + stage, [0, 1]),
                 )
             })
             .collect();
@@ -189,11 +219,34 @@ impl FrameworkEval for PowdrEval {
                 )
             })
             .collect();
+
+        let public_selector_eval = (0..self.public_selectors.len())
+            .map(|i| {
+                eval.get_preprocessed_column(PreprocessedColumn::Plonk(
+                    i + constant_eval.len()
+                        + constant_shifted_eval.len()
+                        + self.preprocess_col_offset,
+                ))
+            })
+            .collect::<Vec<_>>();
+
         let challenges = self
             .challenges
             .iter()
             .map(|(k, v)| (*k, E::F::from(into_stwo_field(v))))
             .collect();
+        let publics = self
+            .publics
+            .iter()
+            .map(|(k, v)| (k.clone(), E::F::from(into_stwo_field(v))))
+            .collect::<BTreeMap<_, _>>();
+
+        // constrain the public values to the witness, using a selector column
+        // that is 1 on the row of the public and 0 elsewhere: s * (pub - x) = 0
+        for ((name, poly_id), selector) in self.public_selectors.iter().zip(public_selector_eval) {
+            let witness = witness_eval[poly_id][0].clone();
+            eval.add_constraint(selector * (publics[name].clone() - witness));
+        }
 
         let intermediate_definitions = self.analyzed.intermediate_definitions();
         let data = Data {
@@ -201,6 +254,7 @@ impl FrameworkEval for PowdrEval {
             constant_shifted_eval: &constant_shifted_eval,
             constant_eval: &constant_eval,
             challenges: &challenges,
+            publics: &publics,
         };
         let mut evaluator =
             ExpressionEvaluator::new_with_custom_expr(&data, &intermediate_definitions, |v| {
@@ -12,6 +12,7 @@ use powdr_executor::constant_evaluator::VariablySizedColumn;
 use powdr_executor::witgen::WitgenCallback;
 use powdr_number::Mersenne31Field as M31;
 use prover::StwoProver;
+pub(crate) use prover::{FRI_LOG_BLOWUP, FRI_NUM_QUERIES, FRI_PROOF_OF_WORK_BITS};
 use stwo_prover::core::backend::{simd::SimdBackend, BackendForChannel};
 use stwo_prover::core::channel::{Blake2sChannel, Channel, MerkleChannel};
 use stwo_prover::core::vcs::blake2_merkle::Blake2sMerkleChannel;
@@ -35,23 +36,26 @@ impl BackendFactory<M31> for RestrictedFactory {
         verification_app_key: Option<&mut dyn io::Read>,
         options: BackendOptions,
     ) -> Result<Box<dyn crate::Backend<M31>>, Error> {
-        if proving_key.is_some() {
-            return Err(Error::BackendError("Proving key unused".to_string()));
+        if setup.is_some() {
+            return Err(Error::NoSetupAvailable);
+        }
+        if verification_app_key.is_some() {
+            return Err(Error::NoAggregationAvailable);
         }
-
-        assert!(pil.stage_count() <= 2, "stwo supports max 2 stages");
 
         let mut stwo: Box<StwoProver<SimdBackend, Blake2sMerkleChannel, Blake2sChannel>> =
             Box::new(StwoProver::new(pil, fixed)?);
 
         match (proving_key, verification_key) {
-            (Some(pk), Some(vk)) => {
+            (Some(pk), vk) => {
                 stwo.set_proving_key(pk);
-                //stwo.set_verifying_key(vk);
-            }
-            _ => {
-                stwo.setup();
+                if let Some(vk) = vk {
+                    stwo.set_verifying_key(vk)?;
+                }
             }
+            // A verification key is enough to verify proofs, no setup needed.
+            (None, Some(vk)) => stwo.set_verifying_key(vk)?,
+            (None, None) => stwo.setup(),
         }
 
         Ok(stwo)
@@ -87,6 +91,15 @@ where
         }
         Ok(StwoProver::prove(self, witness, witgen_callback)?)
     }
+
+    fn export_verification_key(&self, output: &mut dyn io::Write) -> Result<(), Error> {
+        let vk = self
+            .export_verifying_key()
+            .map_err(|e| Error::BackendError(e.to_string()))?;
+        output.write_all(&vk)?;
+        Ok(())
+    }
+
     fn export_proving_key(&self, output: &mut dyn io::Write) -> Result<(), Error> {
         self.export_proving_key(output)
             .map_err(|e| Error::BackendError(e.to_string()))
@@ -9,6 +9,7 @@ use stwo_prover::core::fields::m31::BaseField;
 use stwo_prover::core::poly::circle::{CanonicCoset, CircleEvaluation};
 use stwo_prover::core::poly::BitReversedOrder;
 use stwo_prover::core::prover::StarkProof;
+use stwo_prover::core::vcs::ops::MerkleHasher;
 use stwo_prover::core::ColumnVec;
 
 /// For each possible size, the commitment and prover data
@@ -134,3 +135,13 @@ where
     pub stark_proof: StarkProof<MC::H>,
     pub machine_log_sizes: BTreeMap<String, u32>,
 }
+
+/// The verification key, which fixes the size of each machine and the fixed columns.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StarkVerifyingKey<MC: MerkleChannel> {
+    /// The log size of each machine.
+    pub machine_log_sizes: BTreeMap<String, u32>,
+    /// The commitment to the preprocessed columns of all machines, of the sizes above.
+    pub preprocessed_commitment: <MC::H as MerkleHasher>::Hash,
+}
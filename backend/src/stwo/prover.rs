@@ -19,10 +19,12 @@ use std::sync::Arc;
 use std::{fmt, io};
 
 use crate::stwo::circuit_builder::{
-    gen_stwo_circle_column, get_constant_with_next_list, PowdrComponent, PowdrEval,
+    gen_stwo_circle_column, get_constant_with_next_list, preprocessed_column_count,
+    witness_columns_by_stage, PowdrComponent, PowdrEval,
 };
 use crate::stwo::proof::{
-    Proof, SerializableStarkProvingKey, StarkProvingKey, TableProvingKey, TableProvingKeyCollection,
+    Proof, SerializableStarkProvingKey, StarkProvingKey, StarkVerifyingKey, TableProvingKey,
+    TableProvingKeyCollection,
 };
 
 use stwo_prover::constraint_framework::{
@@ -38,23 +40,33 @@ use stwo_prover::core::fri::FriConfig;
 use stwo_prover::core::pcs::{CommitmentSchemeProver, CommitmentSchemeVerifier, PcsConfig};
 use stwo_prover::core::poly::circle::{CanonicCoset, CircleDomain, CircleEvaluation};
 use stwo_prover::core::poly::BitReversedOrder;
+use stwo_prover::core::vcs::ops::MerkleHasher;
 use stwo_prover::core::ColumnVec;
 
-const FRI_LOG_BLOWUP: usize = 1;
-const FRI_NUM_QUERIES: usize = 100;
-const FRI_PROOF_OF_WORK_BITS: usize = 16;
+pub(crate) const FRI_LOG_BLOWUP: usize = 1;
+pub(crate) const FRI_NUM_QUERIES: usize = 100;
+pub(crate) const FRI_PROOF_OF_WORK_BITS: usize = 16;
 const LOG_LAST_LAYER_DEGREE_BOUND: usize = 0;
 
 pub enum KeyExportError {
     NoProvingKey,
-    //NoVerificationKey,
+    NoVerificationKey,
+    /// There is no verification key because the given machines do not have a fixed size.
+    VariableMachineSizes(Vec<String>),
 }
 
 impl fmt::Display for KeyExportError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::NoProvingKey => write!(f, "No proving key set"),
-            // Self::NoVerificationKey => write!(f, "No verification key set"),
+            Self::NoVerificationKey => write!(f, "No verification key set"),
+            Self::VariableMachineSizes(machines) => write!(
+                f,
+                "No verification key available: the verification key commits to the fixed \
+                columns of all machines at a single size each, but the size of the following \
+                machines is not fixed: {}",
+                machines.join(", ")
+            ),
         }
     }
 }
@@ -68,8 +80,8 @@ pub struct StwoProver<B: BackendForChannel<MC> + Send, MC: MerkleChannel, C: Cha
 
     /// Proving key
     proving_key: StarkProvingKey<B>,
-    /// Verifying key placeholder
-    _verifying_key: Option<()>,
+    /// Verifying key, only set if the size of each machine is fixed
+    verifying_key: Option<StarkVerifyingKey<MC>>,
     _channel_marker: PhantomData<C>,
     _merkle_channel_marker: PhantomData<MC>,
 }
@@ -95,7 +107,7 @@ where
             split,
             fixed,
             proving_key: StarkProvingKey { preprocessed: None },
-            _verifying_key: None,
+            verifying_key: None,
             _channel_marker: PhantomData,
             _merkle_channel_marker: PhantomData,
         })
@@ -106,6 +118,16 @@ where
         self.proving_key = StarkProvingKey::from(serializable_key);
     }
 
+    pub fn set_verifying_key(&mut self, rdr: &mut dyn std::io::Read) -> Result<(), String> {
+        let verifying_key: StarkVerifyingKey<MC> = bincode::deserialize_from(rdr)
+            .map_err(|e| format!("Failed to deserialize verification key: {e}"))?;
+        if !verifying_key.machine_log_sizes.keys().eq(self.split.keys()) {
+            return Err("The verification key does not match the machines of the PIL".to_string());
+        }
+        self.verifying_key = Some(verifying_key);
+        Ok(())
+    }
+
     pub fn export_proving_key(
         &self,
         writer: &mut dyn std::io::Write,
@@ -119,6 +141,32 @@ where
         Ok(())
     }
 
+    pub fn export_verifying_key(&self) -> Result<Vec<u8>, KeyExportError> {
+        let verifying_key = self.verifying_key.as_ref().ok_or_else(|| {
+            let machines = self.variably_sized_machines();
+            if self.proving_key.preprocessed.is_some() && !machines.is_empty() {
+                KeyExportError::VariableMachineSizes(machines)
+            } else {
+                KeyExportError::NoVerificationKey
+            }
+        })?;
+        Ok(bincode::serialize(verifying_key).unwrap())
+    }
+
+    /// Returns the names of the machines that do not have a unique size.
+    fn variably_sized_machines(&self) -> Vec<String> {
+        self.split
+            .iter()
+            .filter(|(_, pil)| {
+                pil.committed_polys_in_source_order()
+                    .find_map(|(s, _)| s.degree)
+                    .and_then(|range| range.try_into_unique())
+                    .is_none()
+            })
+            .map(|(namespace, _)| namespace.clone())
+            .collect()
+    }
+
     pub fn setup(&mut self) {
         let domain_degree_range = DegreeRange {
             min: self
@@ -151,8 +199,8 @@ where
             .split
             .iter()
             .filter_map(|(namespace, pil)| {
-                // if we have no fixed columns, we don't need to commit to anything.
-                if pil.constant_count() == 0 {
+                // if we have neither fixed columns nor publics, we don't need to commit to anything.
+                if pil.constant_count() + pil.publics_count() == 0 {
                     None
                 } else {
                     let fixed_columns = machine_fixed_columns(&self.fixed, pil);
@@ -165,7 +213,10 @@ where
                             .iter()
                             .map(|size| {
                                 //Group the fixed columns by size
-                                let fixed_columns = &fixed_columns[&size];
+                                let fixed_columns = fixed_columns
+                                    .get(&size)
+                                    .map(|columns| columns.as_slice())
+                                    .unwrap_or_default();
                                 let mut constant_trace: ColumnVec<
                                     CircleEvaluation<B, BaseField, BitReversedOrder>,
                                 > = fixed_columns
@@ -199,6 +250,21 @@ where
 
                                 constant_trace.extend(constant_shifted_trace);
 
+                                // selector columns for the public values, which are 1 on the
+                                // row of the public and 0 elsewhere
+                                let public_selectors =
+                                    pil.get_publics().into_iter().map(|(_, _, _, row, _)| {
+                                        let values = (0..size)
+                                            .map(|i| M31::from(i == row as u64))
+                                            .collect::<Vec<_>>();
+                                        gen_stwo_circle_column::<_, BaseField>(
+                                            *domain_map.get(&(size.ilog2() as usize)).unwrap(),
+                                            &values,
+                                        )
+                                    });
+
+                                constant_trace.extend(public_selectors);
+
                                 (
                                     size as usize,
                                     TableProvingKey {
@@ -215,6 +281,87 @@ where
             preprocessed: Some(preprocessed),
         };
         self.proving_key = proving_key;
+
+        // The verification key commits to the fixed columns of all machines at once,
+        // so we can only create it if the size of each machine is fixed. Creating a key
+        // for every combination of machine sizes is not feasible, as their number grows
+        // exponentially with the number of machines.
+        let machine_log_sizes = self
+            .split
+            .iter()
+            .map(|(namespace, pil)| {
+                pil.committed_polys_in_source_order()
+                    .find_map(|(s, _)| s.degree)
+                    .and_then(|range| range.try_into_unique())
+                    .map(|size| (namespace.clone(), size.ilog2()))
+            })
+            .collect::<Option<BTreeMap<_, _>>>();
+        self.verifying_key = machine_log_sizes.map(|machine_log_sizes| StarkVerifyingKey {
+            preprocessed_commitment: self.commit_to_preprocessed(&machine_log_sizes).unwrap(),
+            machine_log_sizes,
+        });
+    }
+
+    /// Commits to the preprocessed columns of the proving key for the machines of the
+    /// given sizes, in the same way as the prover does. Returns None if there is no proving key.
+    fn commit_to_preprocessed(
+        &self,
+        machine_log_sizes: &BTreeMap<String, u32>,
+    ) -> Option<<MC::H as MerkleHasher>::Hash> {
+        let preprocessed = self.proving_key.preprocessed.as_ref()?;
+        let constant_cols = machine_log_sizes
+            .iter()
+            .filter_map(|(machine, &log_size)| {
+                preprocessed
+                    .get(machine)
+                    .and_then(|table_provingkey| table_provingkey.get(&(1usize << log_size)))
+            })
+            .flat_map(|table_provingkey_machine_size| {
+                table_provingkey_machine_size
+                    .constant_trace_circle_domain
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+
+        let max_log_size = machine_log_sizes
+            .values()
+            .copied()
+            .max()
+            .unwrap_or_default();
+        let twiddles = B::precompute_twiddles(
+            CanonicCoset::new(max_log_size + 1 + FRI_LOG_BLOWUP as u32)
+                .circle_domain()
+                .half_coset,
+        );
+        let mut commitment_scheme =
+            CommitmentSchemeProver::<'_, B, MC>::new(get_config(), &twiddles);
+        let mut tree_builder = commitment_scheme.tree_builder();
+        tree_builder.extend_evals(constant_cols);
+        tree_builder.commit(&mut <MC as MerkleChannel>::C::default());
+
+        Some(commitment_scheme.roots()[PREPROCESSED_TRACE_IDX])
+    }
+
+    /// Returns the commitment to the preprocessed columns the proof has to be verified against,
+    /// taken from the verification key or, if there is none, computed from the proving key.
+    fn preprocessed_commitment(
+        &self,
+        machine_log_sizes: &BTreeMap<String, u32>,
+    ) -> Result<<MC::H as MerkleHasher>::Hash, String> {
+        match &self.verifying_key {
+            Some(verifying_key) => {
+                if &verifying_key.machine_log_sizes != machine_log_sizes {
+                    return Err(format!(
+                        "The verification key is for machine sizes {:?}, but the proof has sizes {machine_log_sizes:?}",
+                        verifying_key.machine_log_sizes
+                    ));
+                }
+                Ok(verifying_key.preprocessed_commitment)
+            }
+            None => self
+                .commit_to_preprocessed(machine_log_sizes)
+                .ok_or_else(|| KeyExportError::NoVerificationKey.to_string()),
+        }
     }
 
     pub fn prove(
@@ -253,7 +400,7 @@ where
         // Generate witness for stage 0, build constant columns in circle domain at the same time
         let mut machine_log_sizes: BTreeMap<String, u32> = BTreeMap::new();
         let mut constant_cols = Vec::new();
-        let witness_by_machine = self
+        let mut witness_by_machine = self
             .split
             .iter()
             .filter_map(|(machine, pil)| {
@@ -292,40 +439,6 @@ where
             })
             .collect::<BTreeMap<_, _>>();
 
-        // remember the witness cols that are already transferred to circle domain, so that we don't need to transfer them again in stage 1
-        let mut witness_col_circle_domain_index = BTreeMap::new();
-        let mut index_acc = 0;
-
-        // Get witness columns in circle domain for stage 0
-        let mut witness_cols_circle_domain_eval: ColumnVec<
-            Option<CircleEvaluation<B, BaseField, BitReversedOrder>>,
-        > = witness_by_machine
-            .clone()
-            .values()
-            .flat_map(|witness_cols| {
-                let witness_cols_in_circle_domain = witness_cols
-                    .iter()
-                    .enumerate()
-                    .map(|(index, (name, col))| {
-                        witness_col_circle_domain_index.insert(name.clone(), index + index_acc);
-                        Some(gen_stwo_circle_column::<_, BaseField>(
-                            *domain_map
-                                .get(&(col.len().ilog2() as usize))
-                                .expect("Domain not found for given size"),
-                            col,
-                        ))
-                    })
-                    .collect::<Vec<_>>();
-
-                index_acc += witness_cols_in_circle_domain.len();
-
-                witness_cols_in_circle_domain
-            })
-            .collect();
-
-        // TODO: Commit witness and constant columns of stage 0 to generate sound challenges for stage 1. This is not implemented yet.
-        // To commit, Stwo requires the witness and constant columns to be already evaluated in the circle domain,
-        // so `witness_cols_circle_domain_eval` and `constant_cols` should be available at this point.
         let twiddles_max_degree = B::precompute_twiddles(
             CanonicCoset::new(domain_degree_range.max.ilog2() + 1 + FRI_LOG_BLOWUP as u32)
                 .circle_domain()
@@ -342,45 +455,74 @@ where
 
         tree_builder.commit(prover_channel);
 
-        // Generate challenges for stage 1 based on stage 0 traces.
-        // Stwo supports a maximum of 2 stages, and challenges are created only for stage 0.
-
-        let stage0_challenges = get_dummy_challenges::<MC>(&self.analyzed);
-
-        if self.analyzed.stage_count() > 1 {
-            // build witness columns for stage 1 using the callback function, with the generated challenges
-            witness_cols_circle_domain_eval = witness_by_machine
-                .into_iter()
-                .flat_map(|(machine_name, machine_witness)| {
-                    witgen_callback.next_stage_witness(
-                        &self.split[&machine_name],
-                        &machine_witness,
-                        stage0_challenges.clone(),
-                        1,
-                    )
-                })
-                .map(|(witness_name, vec)| {
-                    if let Some(index) = witness_col_circle_domain_index.get(&witness_name) {
-                        witness_cols_circle_domain_eval[*index].take()
-                    } else {
-                        Some(gen_stwo_circle_column::<B, BaseField>(
+        // Commit to the witness columns of each stage in a separate tree. The challenges of a stage
+        // are drawn after committing to its witness columns and its public values, and are used to
+        // generate the witness columns of the next stage.
+        let challenges_by_stage = challenges_by_stage(&self.analyzed);
+        let publics = self.analyzed.get_publics();
+        let mut challenges = BTreeMap::new();
+        let mut public_values = BTreeMap::new();
+        for stage in 0..self.analyzed.stage_count() {
+            if stage > 0 {
+                witness_by_machine = witness_by_machine
+                    .into_iter()
+                    .map(|(machine_name, machine_witness)| {
+                        let pil = &self.split[&machine_name];
+                        let machine_witness = if stage < pil.stage_count() {
+                            witgen_callback.next_stage_witness(
+                                pil,
+                                &machine_witness,
+                                challenges.clone(),
+                                stage as u8,
+                            )
+                        } else {
+                            machine_witness
+                        };
+                        (machine_name, machine_witness)
+                    })
+                    .collect();
+            }
+
+            let mut tree_builder = commitment_scheme.tree_builder();
+            tree_builder.extend_evals(
+                witness_by_machine
+                    .iter()
+                    .flat_map(|(machine_name, machine_witness)| {
+                        stage_witness_columns(&self.split[machine_name], machine_witness, stage)
+                    })
+                    .map(|col| {
+                        gen_stwo_circle_column::<B, BaseField>(
                             *domain_map
-                                .get(&(vec.len().ilog2() as usize))
+                                .get(&(col.len().ilog2() as usize))
                                 .expect("Domain not found for given size"),
-                            &vec,
-                        ))
-                    }
+                            col,
+                        )
+                    }),
+            );
+            tree_builder.commit(prover_channel);
+
+            let stage_public_values = publics
+                .iter()
+                .filter(|(.., public_stage)| *public_stage as usize == stage)
+                .map(|(name, column_name, _, row, _)| {
+                    let value = witness_by_machine
+                        .values()
+                        .flatten()
+                        .find(|(witness_name, _)| witness_name == column_name)
+                        .map(|(_, column)| column[*row])
+                        .expect("public value should be available at this point");
+                    (name.clone(), value)
                 })
-                .collect();
-        }
+                .collect::<BTreeMap<_, _>>();
+            mix_public_values(prover_channel, &stage_public_values);
+            public_values.extend(stage_public_values);
 
-        let mut tree_builder = commitment_scheme.tree_builder();
-        tree_builder.extend_evals(witness_cols_circle_domain_eval.into_iter().flatten());
-        tree_builder.commit(prover_channel);
+            challenges.extend(draw_challenges(prover_channel, &challenges_by_stage[stage]));
+        }
 
         let tree_span_provider = &mut TraceLocationAllocator::default();
 
-        // Build the circuit. The circuit includes constraints of all the machines in both stage 0 and stage 1
+        // Build the circuit. The circuit includes constraints of all the machines in all stages
         let mut constant_cols_offset_acc = 0;
         let components = self
             .split
@@ -396,13 +538,13 @@ where
                             (*pil).clone(),
                             constant_cols_offset_acc,
                             machine_log_size,
-                            stage0_challenges.clone(),
+                            challenges.clone(),
+                            public_values.clone(),
                         ),
                         (SecureField::zero(), None),
                     );
 
-                    constant_cols_offset_acc +=
-                        pil.constant_count() + get_constant_with_next_list(pil).len();
+                    constant_cols_offset_acc += preprocessed_column_count(pil);
                     component
                 },
             )
@@ -431,65 +573,110 @@ where
         Ok(bincode::serialize(&proof).unwrap())
     }
 
-    pub fn verify(&self, proof: &[u8], _instances: &[M31]) -> Result<(), String> {
-        assert!(
-            _instances.is_empty(),
-            "Expected _instances slice to be empty, but it has {} elements.",
-            _instances.len()
-        );
-
+    pub fn verify(&self, proof: &[u8], instances: &[M31]) -> Result<(), String> {
         let config = get_config();
 
         let proof: Proof<MC> =
             bincode::deserialize(proof).map_err(|e| format!("Failed to deserialize proof: {e}"))?;
 
+        // The prover could commit to any fixed columns, so we check them against ours.
+        let preprocessed_commitment = self.preprocessed_commitment(&proof.machine_log_sizes)?;
+        if proof.stark_proof.commitments[PREPROCESSED_TRACE_IDX] != preprocessed_commitment {
+            return Err(
+                "The commitment to the fixed columns does not match the verification key"
+                    .to_string(),
+            );
+        }
+
+        let publics = self.analyzed.get_publics();
+        if publics.len() != instances.len() {
+            return Err(format!(
+                "Expected {} public values, but got {}",
+                publics.len(),
+                instances.len()
+            ));
+        }
+
+        let stage_count = self.analyzed.stage_count();
+        if proof.stark_proof.commitments.len() < ORIGINAL_TRACE_IDX + stage_count {
+            return Err("The proof does not contain a commitment for each stage".to_string());
+        }
+
         let verifier_channel = &mut <MC as MerkleChannel>::C::default();
         let commitment_scheme = &mut CommitmentSchemeVerifier::<MC>::new(config);
 
+        let mut constant_col_log_sizes = vec![];
+        let mut witness_col_log_sizes = vec![vec![]; stage_count];
+        for ((machine_name, pil), (proof_machine_name, &machine_log_size)) in
+            self.split.iter().zip_eq(proof.machine_log_sizes.iter())
+        {
+            assert_eq!(machine_name, proof_machine_name);
+
+            constant_col_log_sizes
+                .extend(repeat(machine_log_size).take(preprocessed_column_count(pil)));
+            for (stage, columns) in witness_columns_by_stage(pil).iter().enumerate() {
+                witness_col_log_sizes[stage].extend(repeat(machine_log_size).take(columns.len()));
+            }
+        }
+
+        commitment_scheme.commit(
+            proof.stark_proof.commitments[PREPROCESSED_TRACE_IDX],
+            &constant_col_log_sizes,
+            verifier_channel,
+        );
+
+        // Replay the commitments of the prover, to draw the same challenges.
+        let challenges_by_stage = challenges_by_stage(&self.analyzed);
+        let mut challenges = BTreeMap::new();
+        let mut public_values = BTreeMap::new();
+        for (stage, log_sizes) in witness_col_log_sizes.iter().enumerate() {
+            commitment_scheme.commit(
+                proof.stark_proof.commitments[ORIGINAL_TRACE_IDX + stage],
+                log_sizes,
+                verifier_channel,
+            );
+
+            let stage_public_values = publics
+                .iter()
+                .zip_eq(instances)
+                .filter(|((.., public_stage), _)| *public_stage as usize == stage)
+                .map(|((name, ..), value)| (name.clone(), *value))
+                .collect::<BTreeMap<_, _>>();
+            mix_public_values(verifier_channel, &stage_public_values);
+            public_values.extend(stage_public_values);
+
+            challenges.extend(draw_challenges(
+                verifier_channel,
+                &challenges_by_stage[stage],
+            ));
+        }
+
         // Constraints that are to be proved
 
         let tree_span_provider = &mut TraceLocationAllocator::default();
 
         let mut constant_cols_offset_acc = 0;
 
-        let mut constant_col_log_sizes = vec![];
-        let mut witness_col_log_sizes = vec![];
-
-        // TODO: make the challenge sound, now the challenge is built the same way in prover.
-        let stage0_challenges = get_dummy_challenges::<MC>(&self.analyzed);
-
         let mut components = self
             .split
             .iter()
             .zip_eq(proof.machine_log_sizes.iter())
-            .map(
-                |((machine_name, pil), (proof_machine_name, &machine_log_size))| {
-                    assert_eq!(machine_name, proof_machine_name);
-
-                    let machine_component = PowdrComponent::new(
-                        tree_span_provider,
-                        PowdrEval::new(
-                            (*pil).clone(),
-                            constant_cols_offset_acc,
-                            machine_log_size,
-                            stage0_challenges.clone(),
-                        ),
-                        (SecureField::zero(), None),
-                    );
-
-                    constant_cols_offset_acc += pil.constant_count();
-
-                    constant_cols_offset_acc += get_constant_with_next_list(pil).len();
-
-                    constant_col_log_sizes.extend(
-                        repeat(machine_log_size)
-                            .take(pil.constant_count() + get_constant_with_next_list(pil).len()),
-                    );
-                    witness_col_log_sizes
-                        .extend(repeat(machine_log_size).take(pil.commitment_count()));
-                    machine_component
-                },
-            )
+            .map(|((_, pil), (_, &machine_log_size))| {
+                let machine_component = PowdrComponent::new(
+                    tree_span_provider,
+                    PowdrEval::new(
+                        (*pil).clone(),
+                        constant_cols_offset_acc,
+                        machine_log_size,
+                        challenges.clone(),
+                        public_values.clone(),
+                    ),
+                    (SecureField::zero(), None),
+                );
+
+                constant_cols_offset_acc += preprocessed_column_count(pil);
+                machine_component
+            })
             .collect::<Vec<_>>();
 
         let mut components_slice: Vec<&dyn Component> = components
@@ -499,18 +686,6 @@ where
 
         let components_slice = components_slice.as_mut_slice();
 
-        commitment_scheme.commit(
-            proof.stark_proof.commitments[PREPROCESSED_TRACE_IDX],
-            &constant_col_log_sizes,
-            verifier_channel,
-        );
-
-        commitment_scheme.commit(
-            proof.stark_proof.commitments[ORIGINAL_TRACE_IDX],
-            &witness_col_log_sizes,
-            verifier_channel,
-        );
-
         stwo_prover::core::prover::verify(
             components_slice,
             verifier_channel,
@@ -532,24 +707,50 @@ fn get_config() -> PcsConfig {
     }
 }
 
-fn get_dummy_challenges<MC: MerkleChannel>(analyzed: &Analyzed<M31>) -> BTreeMap<u64, M31> {
-    let identities = &analyzed.identities;
-    let challenges_stage0 = identities
+/// Returns the witness columns of the given stage of a machine, in the order in which
+/// they are committed.
+fn stage_witness_columns<'a>(
+    pil: &Analyzed<M31>,
+    machine_witness: &'a [(String, Vec<M31>)],
+    stage: usize,
+) -> Vec<&'a [M31]> {
+    let columns = machine_witness
         .iter()
-        .flat_map(|identity| {
-            identity.all_children().filter_map(|expr| match expr {
-                AlgebraicExpression::Challenge(challenge) => Some(challenge.id),
-                _ => None,
-            })
-        })
-        .collect::<BTreeSet<_>>();
+        .map(|(name, column)| (name.as_str(), column.as_slice()))
+        .collect::<BTreeMap<_, _>>();
+    witness_columns_by_stage(pil)
+        .get(stage)
+        .into_iter()
+        .flatten()
+        .map(|(name, _)| columns[name.as_str()])
+        .collect()
+}
 
-    let challenge_channel = &mut <MC as MerkleChannel>::C::default();
+/// Returns the ids of the challenges drawn after each stage.
+fn challenges_by_stage(analyzed: &Analyzed<M31>) -> Vec<BTreeSet<u64>> {
+    let mut challenges_by_stage = vec![BTreeSet::new(); analyzed.stage_count()];
+    analyzed.all_children().for_each(|expr| {
+        if let AlgebraicExpression::Challenge(challenge) = expr {
+            challenges_by_stage
+                .get_mut(challenge.stage as usize)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Challenge {} is drawn after stage {}, which is the last stage",
+                        challenge.id, challenge.stage
+                    )
+                })
+                .insert(challenge.id);
+        }
+    });
+    challenges_by_stage
+}
 
+/// Draws the values of the given challenges from the channel.
+fn draw_challenges<C: Channel>(channel: &mut C, ids: &BTreeSet<u64>) -> BTreeMap<u64, M31> {
     // Stwo provides a function to draw challenges from the secure field `QM31`,
     // which consists of 4 `M31` elements.
     let draw_challenges = std::iter::repeat_with(|| {
-        let qm31_challenge = challenge_channel.draw_felt();
+        let qm31_challenge = channel.draw_felt();
         [
             qm31_challenge.0 .0,
             qm31_challenge.0 .1,
@@ -559,10 +760,21 @@ fn get_dummy_challenges<MC: MerkleChannel>(analyzed: &Analyzed<M31>) -> BTreeMap
     })
     .flatten();
 
-    challenges_stage0
-        .into_iter()
+    ids.iter()
+        .copied()
         .zip(draw_challenges.map(|challenge| from_stwo_field(&challenge)))
-        .collect::<BTreeMap<_, _>>()
+        .collect()
+}
+
+/// Mixes the public values into the channel, so that the challenges drawn afterwards
+/// depend on them.
+fn mix_public_values<C: Channel>(channel: &mut C, public_values: &BTreeMap<String, M31>) {
+    channel.mix_felts(
+        &public_values
+            .values()
+            .map(|value| SecureField::from(into_stwo_field(value)))
+            .collect::<Vec<_>>(),
+    );
 }
 
 pub fn into_stwo_field(powdr_m31: &M31) -> BaseField {
@@ -7,14 +7,20 @@ use env_logger::fmt::Color;
 use env_logger::{Builder, Target};
 use log::LevelFilter;
 
+use powdr::executor::witgen::QueryCallback;
 use powdr::number::{
     BabyBearField, BigUint, Bn254Field, FieldElement, GoldilocksField, KnownField, KoalaBearField,
 };
 use powdr::riscv::{CompilerOptions, RuntimeLibs};
-use powdr::riscv_executor::{write_executor_csv, ProfilerOptions};
+use powdr::riscv_executor::{
+    write_executor_csv, Breakpoint, CommandLineFrontend, DebugFrontend, GdbStub, InputRecorder,
+    InputRecording, ProfilerOptions,
+};
 use powdr::Pipeline;
 
 use std::ffi::OsStr;
+use std::net::TcpListener;
+use std::sync::Arc;
 use std::time::Instant;
 use std::{
     io::{self, Write},
@@ -149,6 +155,42 @@ enum Commands {
         #[arg(long)]
         #[arg(default_value_t = false)]
         generate_callgrind: bool,
+
+        /// Record all nondeterministic inputs of the execution to the given file.
+        #[arg(long)]
+        record: Option<String>,
+
+        /// Replay the inputs recorded to the given file using --record,
+        /// instead of using the provided inputs.
+        #[arg(long)]
+        #[arg(conflicts_with_all = ["inputs", "record"])]
+        replay: Option<String>,
+    },
+    /// Execute a RISCV powdr-asm file with given inputs in an interactive debugger.
+    Debug {
+        /// input powdr-asm code compiled from Rust/RISCV
+        file: String,
+
+        /// The field to use
+        #[arg(long)]
+        #[arg(default_value_t = FieldArgument::Gl)]
+        #[arg(value_parser = clap_enum_variants!(FieldArgument))]
+        field: FieldArgument,
+
+        /// Comma-separated list of free inputs (numbers).
+        #[arg(short, long)]
+        #[arg(default_value_t = String::new())]
+        inputs: String,
+
+        /// Breakpoints to set at the start, each one of <pc>, <function>,
+        /// <file>:<line> or syscall:<name>.
+        #[arg(short, long = "break")]
+        breakpoints: Vec<String>,
+
+        /// Instead of reading commands from stdin, wait for GDB to connect
+        /// on the given port on localhost.
+        #[arg(long)]
+        gdb: Option<u16>,
     },
     /// Execute and generate a valid witness for a RISCV powdr-asm file with the given inputs.
     Witgen {
@@ -190,6 +232,17 @@ enum Commands {
         #[arg(long)]
         #[arg(default_value_t = false)]
         generate_callgrind: bool,
+
+        /// Record all nondeterministic inputs of the execution to the given file.
+        #[arg(long)]
+        #[arg(conflicts_with = "continuations")]
+        record: Option<String>,
+
+        /// Replay the inputs recorded to the given file using --record,
+        /// instead of using the provided inputs.
+        #[arg(long)]
+        #[arg(conflicts_with_all = ["inputs", "record", "continuations"])]
+        replay: Option<String>,
     },
 }
 
@@ -280,6 +333,8 @@ fn run_command(command: Commands) {
             output_directory,
             generate_flamegraph,
             generate_callgrind,
+            record,
+            replay,
         } => {
             let profiling = if generate_callgrind || generate_flamegraph {
                 Some(ProfilerOptions {
@@ -298,9 +353,23 @@ fn run_command(command: Commands) {
                 Path::new(&file),
                 split_inputs(&inputs),
                 Path::new(&output_directory),
-                profiling
+                profiling,
+                record.as_deref().map(Path::new),
+                replay.as_deref().map(Path::new)
             ))
         }
+        Commands::Debug {
+            file,
+            field,
+            inputs,
+            breakpoints,
+            gdb,
+        } => call_with_field!(debug::<field>(
+            Path::new(&file),
+            split_inputs(&inputs),
+            breakpoints,
+            gdb
+        )),
         Commands::Witgen {
             file,
             field,
@@ -310,6 +379,8 @@ fn run_command(command: Commands) {
             executor_csv,
             generate_flamegraph,
             generate_callgrind,
+            record,
+            replay,
         } => {
             let profiling = if generate_callgrind || generate_flamegraph {
                 Some(ProfilerOptions {
@@ -330,7 +401,9 @@ fn run_command(command: Commands) {
                 Path::new(&output_directory),
                 continuations,
                 executor_csv,
-                profiling
+                profiling,
+                record.as_deref().map(Path::new),
+                replay.as_deref().map(Path::new)
             ))
         }
     };
@@ -382,6 +455,8 @@ fn execute_fast<F: FieldElement>(
     inputs: Vec<F>,
     output_dir: &Path,
     profiling: Option<ProfilerOptions>,
+    record: Option<&Path>,
+    replay: Option<&Path>,
 ) -> Result<(), Vec<String>> {
     let mut pipeline = Pipeline::<F>::default()
         .from_asm_file(file_name.to_path_buf())
@@ -390,18 +465,93 @@ fn execute_fast<F: FieldElement>(
 
     let asm = pipeline.compute_analyzed_asm().unwrap().clone();
 
+    let replay = replay
+        .map(InputRecording::<F>::read)
+        .transpose()
+        .map_err(|e| vec![e])?;
+    let recorder = record.map(|_| InputRecorder::new(pipeline.data_callback().unwrap()));
+
     let start = Instant::now();
 
-    let trace_len = powdr::riscv_executor::execute::<F>(
+    let trace_len = match (&replay, &recorder) {
+        (Some(recording), _) => powdr::riscv_executor::execute::<F>(
+            &asm,
+            recording.initial_memory.clone(),
+            &recording.replay_callback(),
+            &recording.bootloader_inputs,
+            profiling,
+        ),
+        (None, Some(recorder)) => powdr::riscv_executor::execute::<F>(
+            &asm,
+            powdr::riscv_executor::MemoryState::new(),
+            &recorder.callback(),
+            &[],
+            profiling,
+        ),
+        (None, None) => powdr::riscv_executor::execute::<F>(
+            &asm,
+            powdr::riscv_executor::MemoryState::new(),
+            pipeline.data_callback().unwrap(),
+            &[],
+            profiling,
+        ),
+    };
+
+    let duration = start.elapsed();
+    log::info!("Executor done in: {:?}", duration);
+    log::info!("Execution trace length: {}", trace_len);
+
+    if let (Some(path), Some(recorder)) = (record, recorder) {
+        recorder
+            .finish(powdr::riscv_executor::MemoryState::new(), &[])
+            .write(path)
+            .map_err(|e| vec![e])?;
+        log::info!("Wrote the inputs of the execution to {}", path.display());
+    }
+    Ok(())
+}
+
+fn debug<F: FieldElement>(
+    file_name: &Path,
+    inputs: Vec<F>,
+    breakpoints: Vec<String>,
+    gdb_port: Option<u16>,
+) -> Result<(), Vec<String>> {
+    let breakpoints = breakpoints
+        .iter()
+        .map(|b| b.parse())
+        .collect::<Result<Vec<Breakpoint>, _>>()
+        .map_err(|e| vec![e])?;
+
+    let mut pipeline = Pipeline::<F>::default()
+        .from_asm_file(file_name.to_path_buf())
+        .with_prover_inputs(inputs);
+
+    let asm = pipeline.compute_analyzed_asm().unwrap().clone();
+
+    let mut frontend: Box<dyn DebugFrontend<F>> = match gdb_port {
+        Some(port) => {
+            let listener =
+                TcpListener::bind(("127.0.0.1", port)).map_err(|e| vec![e.to_string()])?;
+            Box::new(
+                GdbStub::accept(&listener)
+                    .map_err(|e| vec![e.to_string()])?
+                    .with_breakpoints(breakpoints),
+            )
+        }
+        None => Box::new(
+            CommandLineFrontend::new(io::stdin().lock(), io::stdout())
+                .with_breakpoints(breakpoints),
+        ),
+    };
+    let trace_len = powdr::riscv_executor::execute_with_debugger::<F>(
         &asm,
         powdr::riscv_executor::MemoryState::new(),
         pipeline.data_callback().unwrap(),
         &[],
-        profiling,
+        frontend.as_mut(),
     );
 
-    let duration = start.elapsed();
-    log::info!("Executor done in: {:?}", duration);
     log::info!("Execution trace length: {}", trace_len);
     Ok(())
 }
@@ -414,11 +564,20 @@ fn execute<F: FieldElement>(
     continuations: bool,
     executor_csv: bool,
     profiling: Option<ProfilerOptions>,
+    record: Option<&Path>,
+    replay: Option<&Path>,
 ) -> Result<(), Vec<String>> {
-    let mut pipeline = Pipeline::<F>::default()
-        .from_asm_file(file_name.to_path_buf())
-        .with_prover_inputs(inputs)
-        .with_output(output_dir.into(), true);
+    let replay = replay
+        .map(InputRecording::<F>::read)
+        .transpose()
+        .map_err(|e| vec![e])?;
+    let pipeline = Pipeline::<F>::default().from_asm_file(file_name.to_path_buf());
+    let mut pipeline = match &replay {
+        // Witness generation repeats some of the queries of the execution.
+        Some(recording) => pipeline.add_query_callback(Arc::new(recording.lookup_callback())),
+        None => pipeline.with_prover_inputs(inputs),
+    }
+    .with_output(output_dir.into(), true);
 
     let generate_witness = |pipeline: &mut Pipeline<F>| -> Result<(), Vec<String>> {
         pipeline.compute_witness().unwrap();
@@ -434,15 +593,31 @@ fn execute<F: FieldElement>(
         let asm = pipeline.compute_analyzed_asm().unwrap().clone();
         let pil = pipeline.compute_optimized_pil().unwrap();
 
+        let recorder = record.map(|_| InputRecorder::new(pipeline.data_callback().unwrap()));
+        let replay_callback = replay.as_ref().map(|r| r.replay_callback());
+        let record_callback = recorder.as_ref().map(|r| r.callback());
+        let callback: &dyn QueryCallback<F> = match (&replay_callback, &record_callback) {
+            (Some(callback), _) => callback,
+            (None, Some(callback)) => callback,
+            (None, None) => pipeline.data_callback().unwrap(),
+        };
+        let (initial_memory, bootloader_inputs) = match &replay {
+            Some(recording) => (
+                recording.initial_memory.clone(),
+                &recording.bootloader_inputs[..],
+            ),
+            None => (powdr::riscv_executor::MemoryState::new(), &[][..]),
+        };
+
         let start = Instant::now();
 
         let execution = powdr::riscv_executor::execute_with_witness::<F>(
             &asm,
             &pil,
             fixed,
-            powdr::riscv_executor::MemoryState::new(),
-            pipeline.data_callback().unwrap(),
-            &[],
+            initial_memory,
+            callback,
+            bootloader_inputs,
             None,
             profiling,
         );
@@ -451,6 +626,14 @@ fn execute<F: FieldElement>(
         log::info!("Executor done in: {:?}", duration);
         log::info!("Execution trace length: {}", execution.trace_len);
 
+        if let (Some(path), Some(recorder)) = (record, recorder) {
+            recorder
+                .finish(powdr::riscv_executor::MemoryState::new(), &[])
+                .write(path)
+                .map_err(|e| vec![e])?;
+            log::info!("Wrote the inputs of the execution to {}", path.display());
+        }
+
         let witness_cols: Vec<_> = pil
             .committed_polys_in_source_order()
             .flat_map(|(s, _)| s.array_elements().map(|(name, _)| name))
@@ -6,7 +6,8 @@ use clap::{CommandFactory, Parser, Subcommand};
 use env_logger::fmt::Color;
 use env_logger::{Builder, Target};
 use log::{max_level, LevelFilter};
-use powdr::backend::BackendType;
+use powdr::backend::security::{estimate_security, SecurityParameters};
+use powdr::backend::{composite_proof_info, BackendType};
 use powdr::number::{buffered_write_file, read_polys_csv_file, CsvRenderMode};
 use powdr::number::{
     BabyBearField, BigUint, Bn254Field, FieldElement, GoldilocksField, KoalaBearField,
@@ -152,6 +153,12 @@ enum Commands {
 
         /// Backend options. Halo2: "poseidon", "snark_single" or "snark_aggr".
         /// EStark and PilStarkCLI: "stark_gl", "stark_bn" or "snark_bn".
+        /// Mock: "shrink" to write a minimal reproducer of a failure to the output directory,
+        /// "fuzz[=<iterations>[,<seed>]]" to check randomly mutated witnesses.
+        /// Plonky3: "fast-dev", "standard" or "conservative" FRI parameters, optionally followed by
+        /// overrides such as ",log_blowup=2,num_queries=80,pow_bits=16".
+        /// Composite backends: additionally "memory_budget=<bytes>[K|M|G]" to prove machines concurrently
+        /// within the estimated memory budget.
         #[arg(long)]
         backend_options: Option<String>,
 
@@ -179,6 +186,21 @@ enum Commands {
         #[arg(default_value_t = CsvRenderModeCLI::Hex)]
         #[arg(value_parser = clap_enum_variants!(CsvRenderModeCLI))]
         csv_mode: CsvRenderModeCLI,
+
+        /// Write a trace of witness generation in the Chrome trace event format
+        /// (viewable in Perfetto or chrome://tracing) to the given file.
+        #[arg(long)]
+        witgen_trace: Option<String>,
+
+        /// Interpret the code derived for witness generation instead of compiling it,
+        /// which does not need a Rust toolchain but runs slower.
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        jit_interpreter: bool,
+
+        /// Directory in which the optimized PIL and fixed columns are cached across runs.
+        #[arg(long)]
+        artifact_cache: Option<String>,
     },
     Prove {
         /// Input PIL file
@@ -202,6 +224,12 @@ enum Commands {
 
         /// Backend options. Halo2: "poseidon", "snark_single" or "snark_aggr".
         /// EStark and PilStarkCLI: "stark_gl", "stark_bn" or "snark_bn".
+        /// Mock: "shrink" to write a minimal reproducer of a failure to the output directory,
+        /// "fuzz[=<iterations>[,<seed>]]" to check randomly mutated witnesses.
+        /// Plonky3: "fast-dev", "standard" or "conservative" FRI parameters, optionally followed by
+        /// overrides such as ",log_blowup=2,num_queries=80,pow_bits=16".
+        /// Composite backends: additionally "memory_budget=<bytes>[K|M|G]" to prove machines concurrently
+        /// within the estimated memory budget.
         #[arg(long)]
         backend_options: Option<String>,
 
@@ -244,6 +272,12 @@ enum Commands {
 
         /// Backend options. Halo2: "poseidon", "snark_single" or "snark_aggr".
         /// EStark and PilStarkCLI: "stark_gl", "stark_bn" or "snark_bn".
+        /// Mock: "shrink" to write a minimal reproducer of a failure to the output directory,
+        /// "fuzz[=<iterations>[,<seed>]]" to check randomly mutated witnesses.
+        /// Plonky3: "fast-dev", "standard" or "conservative" FRI parameters, optionally followed by
+        /// overrides such as ",log_blowup=2,num_queries=80,pow_bits=16".
+        /// Composite backends: additionally "memory_budget=<bytes>[K|M|G]" to prove machines concurrently
+        /// within the estimated memory budget.
         #[arg(long)]
         backend_options: Option<String>,
 
@@ -287,6 +321,12 @@ enum Commands {
 
         /// Backend options. Halo2: "poseidon", "snark_single" or "snark_aggr".
         /// EStark and PilStarkCLI: "stark_gl", "stark_bn" or "snark_bn".
+        /// Mock: "shrink" to write a minimal reproducer of a failure to the output directory,
+        /// "fuzz[=<iterations>[,<seed>]]" to check randomly mutated witnesses.
+        /// Plonky3: "fast-dev", "standard" or "conservative" FRI parameters, optionally followed by
+        /// overrides such as ",log_blowup=2,num_queries=80,pow_bits=16".
+        /// Composite backends: additionally "memory_budget=<bytes>[K|M|G]" to prove machines concurrently
+        /// within the estimated memory budget.
         #[arg(long)]
         backend_options: Option<String>,
 
@@ -323,6 +363,12 @@ enum Commands {
 
         /// Backend options. Halo2: "poseidon", "snark_single" or "snark_aggr".
         /// EStark and PilStarkCLI: "stark_gl", "stark_bn" or "snark_bn".
+        /// Mock: "shrink" to write a minimal reproducer of a failure to the output directory,
+        /// "fuzz[=<iterations>[,<seed>]]" to check randomly mutated witnesses.
+        /// Plonky3: "fast-dev", "standard" or "conservative" FRI parameters, optionally followed by
+        /// overrides such as ",log_blowup=2,num_queries=80,pow_bits=16".
+        /// Composite backends: additionally "memory_budget=<bytes>[K|M|G]" to prove machines concurrently
+        /// within the estimated memory budget.
         #[arg(long)]
         backend_options: Option<String>,
 
@@ -363,6 +409,18 @@ enum Commands {
         file: String,
     },
 
+    /// Formats .asm and .pil files in place, keeping comments.
+    Fmt {
+        /// Input files
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Only check that the files are formatted, without modifying them.
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        check: bool,
+    },
+
     /// Optimizes the PIL file and outputs it on stdout.
     OptimizePIL {
         /// Input file
@@ -375,6 +433,76 @@ enum Commands {
         field: FieldArgument,
     },
 
+    /// Checks the PIL for underconstrained witness columns and identities of a too high degree.
+    /// Exits with an error if any problems are found.
+    Lint {
+        /// Input file
+        file: String,
+
+        /// The field to use
+        #[arg(long)]
+        #[arg(default_value_t = FieldArgument::Gl)]
+        #[arg(value_parser = clap_enum_variants!(FieldArgument))]
+        field: FieldArgument,
+
+        /// The maximum degree of identities supported by the backend.
+        #[arg(long)]
+        max_degree: Option<usize>,
+    },
+
+    /// Estimates the bits of security of proofs of the PIL with a FRI-based backend,
+    /// under both the conjectured and the proven FRI soundness bounds.
+    Security {
+        /// Input file
+        file: String,
+
+        /// The field to use
+        #[arg(long)]
+        #[arg(default_value_t = FieldArgument::Gl)]
+        #[arg(value_parser = clap_enum_variants!(FieldArgument))]
+        field: FieldArgument,
+
+        /// The backend to estimate the security of.
+        #[arg(short, long)]
+        #[arg(value_parser = clap_enum_variants!(BackendType))]
+        backend: BackendType,
+
+        /// Backend options, as for the prove command.
+        #[arg(long)]
+        backend_options: Option<String>,
+    },
+
+    /// Prints the size, proof size, stage count, public values and verification key
+    /// entry of each machine in a proof of a composite backend.
+    ProofInfo {
+        /// Input PIL file
+        file: String,
+
+        /// The field to use
+        #[arg(long)]
+        #[arg(default_value_t = FieldArgument::Gl)]
+        #[arg(value_parser = clap_enum_variants!(FieldArgument))]
+        field: FieldArgument,
+
+        /// File containing the proof.
+        #[arg(long)]
+        proof: String,
+
+        /// Comma-separated list of the public values the proof is verified against (numbers).
+        #[arg(long)]
+        #[arg(default_value_t = String::new())]
+        publics: String,
+
+        /// File containing the verification key.
+        #[arg(long)]
+        vkey: Option<String>,
+
+        /// Print the information as JSON.
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        json: bool,
+    },
+
     /// Executes all functions starting with `test_` in every module called
     /// `test` (or sub-module thereof) starting from the given module.
     Test {
@@ -459,10 +587,40 @@ fn run_command(command: Commands) {
             };
             Ok(())
         }
+        Commands::Fmt { files, check } => format_files(&files, check),
         Commands::OptimizePIL { file, field } => {
             call_with_field!(optimize_and_output::<field>(&file));
             Ok(())
         }
+        Commands::Lint {
+            file,
+            field,
+            max_degree,
+        } => call_with_field!(lint::<field>(&file, max_degree)),
+        Commands::ProofInfo {
+            file,
+            field,
+            proof,
+            publics,
+            vkey,
+            json,
+        } => call_with_field!(proof_info::<field>(
+            &file,
+            &proof,
+            &publics,
+            vkey.as_deref(),
+            json
+        )),
+        Commands::Security {
+            file,
+            field,
+            backend,
+            backend_options,
+        } => call_with_field!(security::<field>(
+            &file,
+            backend,
+            backend_options.unwrap_or_default()
+        )),
         Commands::Pil {
             file,
             field,
@@ -479,6 +637,9 @@ fn run_command(command: Commands) {
             export_witness_csv,
             export_all_columns_csv,
             csv_mode,
+            witgen_trace,
+            jit_interpreter,
+            artifact_cache,
         } => {
             call_with_field!(run_pil::<field>(
                 file,
@@ -494,7 +655,10 @@ fn run_command(command: Commands) {
                 degree_mode,
                 export_witness_csv,
                 export_all_columns_csv,
-                csv_mode
+                csv_mode,
+                witgen_trace,
+                jit_interpreter,
+                artifact_cache
             ))
         }
         Commands::Test { file, field } => {
@@ -687,6 +851,9 @@ fn run_pil<F: FieldElement>(
     export_witness: bool,
     export_all_columns: bool,
     csv_mode: CsvRenderModeCLI,
+    witgen_trace: Option<String>,
+    jit_interpreter: bool,
+    artifact_cache: Option<String>,
 ) -> Result<(), Vec<String>> {
     let inputs = split_inputs::<F>(&inputs);
 
@@ -696,7 +863,10 @@ fn run_pil<F: FieldElement>(
             .with_linker_params(LinkerParams {
                 mode: linker_mode.unwrap_or_default(),
                 degree_mode: degree_mode.unwrap_or_default(),
-            }),
+            })
+            .with_witgen_trace_file(witgen_trace.map(PathBuf::from))
+            .with_jit_interpreter(jit_interpreter)
+            .with_artifact_cache_dir(artifact_cache.map(PathBuf::from)),
         inputs.clone(),
         PathBuf::from(output_directory),
         force,
@@ -802,6 +972,101 @@ fn optimize_and_output<T: FieldElement>(file: &str) {
     );
 }
 
+fn lint<T: FieldElement>(file: &str, max_degree: Option<usize>) -> Result<(), Vec<String>> {
+    let mut pipeline = Pipeline::<T>::default().from_file(PathBuf::from(file));
+    // Lint the PIL before optimization, since the optimizer removes unreferenced columns.
+    let pil = pipeline.compute_analyzed_pil()?;
+    let warnings = powdr::pilopt::lint::lint(pil, max_degree);
+    if warnings.is_empty() {
+        Ok(())
+    } else {
+        Err(warnings.iter().map(|w| format!("Warning: {w}")).collect())
+    }
+}
+
+/// Formats the files in place. In check mode, only reports the files that are not formatted.
+fn format_files(files: &[String], check: bool) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    for file in files {
+        let contents =
+            fs::read_to_string(file).map_err(|e| vec![format!("Error reading {file}: {e}")])?;
+        let formatted = if Path::new(file).extension().is_some_and(|ext| ext == "pil") {
+            powdr::parser::format_pil(Some(file), &contents)
+        } else {
+            powdr::parser::format_asm(Some(file), &contents)
+        };
+        match formatted {
+            Ok(formatted) if formatted == contents => {}
+            Ok(_) if check => errors.push(format!("{file} is not formatted.")),
+            Ok(formatted) => fs::write(file, formatted)
+                .map_err(|e| vec![format!("Error writing {file}: {e}")])?,
+            Err(file_errors) => {
+                file_errors.iter().for_each(|e| e.output_to_stderr());
+                errors.push(format!("Could not format {file}."));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn proof_info<T: FieldElement>(
+    file: &str,
+    proof: &str,
+    publics: &str,
+    vkey: Option<&str>,
+    json: bool,
+) -> Result<(), Vec<String>> {
+    let mut pipeline = Pipeline::<T>::default().from_file(PathBuf::from(file));
+    let pil = pipeline.compute_optimized_pil()?;
+    let read = |path: &str| fs::read(path).map_err(|e| vec![format!("Error reading {path}: {e}")]);
+    let proof = read(proof)?;
+    let vkey = vkey.map(read).transpose()?;
+    let publics = split_inputs(publics);
+    let info =
+        composite_proof_info(&pil, &proof, vkey.as_deref(), &publics).map_err(|e| match e {
+            powdr::backend::Error::BackendError(e) => vec![e],
+            e => vec![e.to_string()],
+        })?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info).unwrap());
+    } else {
+        print!("{info}");
+    }
+    Ok(())
+}
+
+fn security<T: FieldElement>(
+    file: &str,
+    backend: BackendType,
+    backend_options: String,
+) -> Result<(), Vec<String>> {
+    let mut pipeline = Pipeline::<T>::default().from_file(PathBuf::from(file));
+    let pil = pipeline.compute_optimized_pil()?;
+    let parameters =
+        SecurityParameters::for_backend::<T>(backend, &backend_options).map_err(|e| vec![e])?;
+    let estimate = estimate_security(backend, &backend_options, &pil).map_err(|e| vec![e])?;
+    println!(
+        "Field: {} bits, extension degree {}",
+        parameters.field_bits, parameters.extension_degree
+    );
+    println!(
+        "FRI: blowup factor {}, {} queries, {} proof of work bits",
+        1 << parameters.log_blowup,
+        parameters.num_queries,
+        parameters.proof_of_work_bits
+    );
+    println!(
+        "Conjectured security: {:.1} bits",
+        estimate.conjectured_bits
+    );
+    println!("Proven security: {:.1} bits", estimate.proven_bits);
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use crate::{run_command, Commands, CsvRenderModeCLI, FieldArgument};
@@ -830,6 +1095,9 @@ mod test {
             export_witness_csv: false,
             export_all_columns_csv: true,
             csv_mode: CsvRenderModeCLI::Hex,
+            witgen_trace: None,
+            jit_interpreter: false,
+            artifact_cache: None,
         };
         run_command(pil_command);
 
@@ -854,4 +1122,16 @@ mod test {
             run_command(prove_command);
         }
     }
+
+    #[cfg(feature = "plonky3")]
+    #[test]
+    fn security() {
+        let security_command = Commands::Security {
+            file: "../test_data/asm/simple_sum.asm".to_string(),
+            field: FieldArgument::Gl,
+            backend: BackendType::Plonky3Composite,
+            backend_options: Some("conservative,memory_budget=1G".to_string()),
+        };
+        run_command(security_command);
+    }
 }
@@ -209,7 +209,9 @@ mod test {
 
         let fixed_data = FixedData::new(&analyzed, &fixed_col_vals, &[], Default::default(), 0);
         let fixed_data = global_constraints::set_global_constraints(fixed_data);
-        let machines = MachineExtractor::new(&fixed_data).split_out_machines();
+        let machines = MachineExtractor::new(&fixed_data)
+            .split_out_machines()
+            .unwrap();
         let [KnownMachine::BlockMachine(machine)] = machines
             .iter()
             .filter(|m| m.name().contains(machine_name))
@@ -447,7 +447,7 @@ fn format_condition<T: FieldElement>(
     match min.cmp(&max) {
         Ordering::Equal => format!("{var} == {min}",),
         Ordering::Less => format!("{min} <= {var} && {var} <= {max}"),
-        Ordering::Greater => format!("{var} <= {min} || {var} >= {max}"),
+        Ordering::Greater => format!("{min} <= {var} || {var} <= {max}"),
     }
 }
 
@@ -1021,6 +1021,42 @@ extern \"C\" fn witgen(
         assert_eq!(y_val, GoldilocksField::from(4));
     }
 
+    #[test]
+    fn branches_wrapping_range() {
+        let x = param(0);
+        let y = param(1);
+        // The range wraps around, i.e. it contains all values from 20 up to
+        // the modulus and from 0 up to 7.
+        let effects = vec![Effect::Branch(
+            BranchCondition {
+                variable: x.clone(),
+                condition: RangeConstraint::from_range(20.into(), 7.into()),
+            },
+            vec![assignment(&y, symbol(&x) + number(1))],
+            vec![assignment(&y, symbol(&x) + number(2))],
+        )];
+        let f = compile_effects(0, 1, &[x], &effects).unwrap();
+        for (x_val, expected) in [(2, 3), (7, 8), (10, 12), (20, 21), (25, 26)] {
+            let x_val: GoldilocksField = x_val.into();
+            let mut y_val: GoldilocksField = 0.into();
+            let mut data = vec![];
+            let mut known = vec![];
+            let mut params = vec![LookupCell::Input(&x_val), LookupCell::Output(&mut y_val)];
+            let params = WitgenFunctionParams {
+                data: data.as_mut_slice().into(),
+                known: known.as_mut_ptr(),
+                row_offset: 0,
+                params: params.as_mut_slice().into(),
+                mutable_state: std::ptr::null(),
+                call_machine: no_call_machine,
+                fixed_data: null(),
+                get_fixed_value: get_fixed_data_test,
+            };
+            (f.function)(params);
+            assert_eq!(y_val, GoldilocksField::from(expected));
+        }
+    }
+
     #[test]
     fn branches_codegen() {
         let x = param(0);
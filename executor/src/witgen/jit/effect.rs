@@ -208,7 +208,7 @@ fn format_condition<T: FieldElement>(
     match min.cmp(&max) {
         Ordering::Equal => format!("{variable} == {min}"),
         Ordering::Less => format!("{min} <= {variable} && {variable} <= {max}"),
-        Ordering::Greater => format!("{variable} <= {min} || {variable} >= {max}"),
+        Ordering::Greater => format!("{min} <= {variable} || {variable} <= {max}"),
     }
 }
 
@@ -18,6 +18,7 @@ use crate::witgen::{
 use super::{
     block_machine_processor::BlockMachineProcessor,
     compiler::{compile_effects, WitgenFunction},
+    interpreter::EffectsInterpreter,
     variable::Variable,
     witgen_inference::CanProcessCall,
 };
@@ -42,10 +43,35 @@ pub struct FunctionCache<'a, T: FieldElement> {
 }
 
 pub struct CacheEntry<T: FieldElement> {
-    pub function: WitgenFunction<T>,
+    pub function: WitgenCode<T>,
     pub range_constraints: Vec<RangeConstraint<T>>,
 }
 
+/// The derived witgen code, either compiled to machine code or prepared for the interpreter.
+pub enum WitgenCode<T: FieldElement> {
+    Compiled(WitgenFunction<T>),
+    Interpreted(EffectsInterpreter<T>),
+}
+
+impl<T: FieldElement> WitgenCode<T> {
+    pub fn call<Q: QueryCallback<T>>(
+        &self,
+        fixed_data: &FixedData<'_, T>,
+        mutable_state: &MutableState<'_, T, Q>,
+        params: &mut [LookupCell<T>],
+        data: CompactDataRef<'_, T>,
+    ) {
+        match self {
+            WitgenCode::Compiled(function) => {
+                function.call(fixed_data, mutable_state, params, data)
+            }
+            WitgenCode::Interpreted(interpreter) => {
+                interpreter.call(fixed_data, mutable_state, params, data)
+            }
+        }
+    }
+}
+
 impl<'a, T: FieldElement> FunctionCache<'a, T> {
     pub fn new(
         fixed_data: &'a FixedData<'a, T>,
@@ -157,15 +183,26 @@ impl<'a, T: FieldElement> FunctionCache<'a, T> {
             .filter_map(|(i, b)| if b { Some(Variable::Param(i)) } else { None })
             .collect::<Vec<_>>();
 
-        log::trace!("Compiling effects...");
-        let function = compile_effects(
-            self.column_layout.first_column_id,
-            self.column_layout.column_count,
-            &known_inputs,
-            &code,
-        )
-        .unwrap();
-        log::trace!("Compilation done.");
+        let function = if self.fixed_data.use_jit_interpreter() {
+            log::trace!("Preparing effects for the interpreter...");
+            // The interpreter does not support prover functions, in which case
+            // we fall back to the run-time solver.
+            let interpreter = EffectsInterpreter::try_new(&known_inputs, &code)
+                .map_err(|e| log::debug!("=> Cannot interpret JIT code: {e}"))
+                .ok()?;
+            WitgenCode::Interpreted(interpreter)
+        } else {
+            log::trace!("Compiling effects...");
+            let function = compile_effects(
+                self.column_layout.first_column_id,
+                self.column_layout.column_count,
+                &known_inputs,
+                &code,
+            )
+            .unwrap();
+            log::trace!("Compilation done.");
+            WitgenCode::Compiled(function)
+        };
 
         Some(CacheEntry {
             function,
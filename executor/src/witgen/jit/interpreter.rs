@@ -1,6 +1,4 @@
-// TODO: the unused is only here because the interpreter is not integrated in the final code yet
-#![allow(unused)]
-use super::effect::{Assertion, Effect};
+use super::effect::{Assertion, BranchCondition, Effect, ProverFunctionCall};
 
 use super::symbolic_expression::{BinaryOperator, BitOperator, SymbolicExpression, UnaryOperator};
 use super::variable::{Cell, Variable};
@@ -15,6 +13,8 @@ use powdr_number::FieldElement;
 use std::collections::{BTreeSet, HashMap};
 
 /// Interpreter for instructions compiled from witgen effects.
+/// This is an alternative to compiling the effects to machine code
+/// (see [super::compiler::compile_effects]) that does not need a Rust toolchain.
 pub struct EffectsInterpreter<T: FieldElement> {
     var_count: usize,
     actions: Vec<InterpreterAction<T>>,
@@ -31,6 +31,12 @@ enum InterpreterAction<T: FieldElement> {
     WriteParam(usize, usize),
     MachineCall(u64, Vec<MachineCallArgumentIdx>),
     Assertion(RPNExpression<T, usize>, RPNExpression<T, usize>, bool),
+    /// Executes the first list of actions if the condition holds and the second otherwise.
+    Branch(
+        BranchTest<T>,
+        Vec<InterpreterAction<T>>,
+        Vec<InterpreterAction<T>>,
+    ),
 }
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -41,22 +47,58 @@ pub enum MachineCallArgumentIdx {
     Unknown(usize),
 }
 
+/// The condition of a branch: The variable is in the inclusive range from `min` to `max`,
+/// which wraps around if `min > max`.
+struct BranchTest<T> {
+    var: usize,
+    min: T,
+    max: T,
+}
+
+impl<T: FieldElement> BranchTest<T> {
+    fn new(var_mapper: &mut VariableMapper, condition: &BranchCondition<T, Variable>) -> Self {
+        let (min, max) = condition.condition.range();
+        Self {
+            var: var_mapper.map_var(&condition.variable),
+            min,
+            max,
+        }
+    }
+
+    fn holds(&self, vars: &[T]) -> bool {
+        let value = vars[self.var];
+        if self.min <= self.max {
+            self.min <= value && value <= self.max
+        } else {
+            self.min <= value || value <= self.max
+        }
+    }
+}
+
 impl<T: FieldElement> EffectsInterpreter<T> {
-    pub fn new(known_inputs: &[Variable], effects: &[Effect<T, Variable>]) -> Self {
+    /// Compiles the effects into instructions for the interpreter.
+    /// Fails if the effects contain calls to prover functions or if the resulting
+    /// instructions read variables before they are written.
+    pub fn try_new(
+        known_inputs: &[Variable],
+        effects: &[Effect<T, Variable>],
+    ) -> Result<Self, String> {
         let mut actions = vec![];
         let mut var_mapper = VariableMapper::new();
 
         Self::load_fixed_column_values(&mut var_mapper, &mut actions, effects);
         Self::load_known_inputs(&mut var_mapper, &mut actions, known_inputs);
-        Self::process_effects(&mut var_mapper, &mut actions, effects);
+        Self::process_effects(&mut var_mapper, &mut actions, effects)?;
         Self::write_data(&mut var_mapper, &mut actions, effects);
 
         let ret = Self {
             var_count: var_mapper.var_count(),
             actions,
         };
-        assert!(ret.is_valid());
-        ret
+        if !ret.is_valid() {
+            return Err("Variables are read before they are written or written twice".to_string());
+        }
+        Ok(ret)
     }
 
     fn load_fixed_column_values(
@@ -102,8 +144,8 @@ impl<T: FieldElement> EffectsInterpreter<T> {
         var_mapper: &mut VariableMapper,
         actions: &mut Vec<InterpreterAction<T>>,
         effects: &[Effect<T, Variable>],
-    ) {
-        effects.iter().for_each(|effect| {
+    ) -> Result<(), String> {
+        for effect in effects {
             let action = match effect {
                 Effect::Assignment(var, e) => {
                     let idx = var_mapper.map_var(var);
@@ -135,16 +177,25 @@ impl<T: FieldElement> EffectsInterpreter<T> {
                         .collect();
                     InterpreterAction::MachineCall(*id, arguments)
                 }
-                Effect::ProverFunctionCall(..) => {
-                    // TODO We cannot compile them here, but we should be able to use the PIL evaluator.
-                    unimplemented!("Prover function calls are not supported in the interpreter yet")
+                Effect::ProverFunctionCall(ProverFunctionCall { function_index, .. }) => {
+                    // TODO We should be able to use the PIL evaluator here.
+                    return Err(format!(
+                        "Prover function calls are not supported in the interpreter yet \
+                        (prover function {function_index})"
+                    ));
                 }
-                Effect::Branch(..) => {
-                    unimplemented!("Branches are not supported in the interpreter yet")
+                Effect::Branch(condition, first, second) => {
+                    let test = BranchTest::new(var_mapper, condition);
+                    let mut first_actions = vec![];
+                    Self::process_effects(var_mapper, &mut first_actions, first)?;
+                    let mut second_actions = vec![];
+                    Self::process_effects(var_mapper, &mut second_actions, second)?;
+                    InterpreterAction::Branch(test, first_actions, second_actions)
                 }
             };
             actions.push(action);
-        })
+        }
+        Ok(())
     }
 
     fn write_data(
@@ -152,10 +203,13 @@ impl<T: FieldElement> EffectsInterpreter<T> {
         actions: &mut Vec<InterpreterAction<T>>,
         effects: &[Effect<T, Variable>],
     ) {
+        // Variables assigned in both branches of a branch are written only once.
         effects
             .iter()
             .flat_map(Effect::written_vars)
-            .for_each(|(var, _mutable)| {
+            .map(|(var, _mutable)| var)
+            .unique()
+            .for_each(|var| {
                 match var {
                     Variable::WitnessCell(cell) => {
                         let idx = var_mapper.get_var(var).unwrap();
@@ -175,15 +229,35 @@ impl<T: FieldElement> EffectsInterpreter<T> {
 
     /// Check that actions are valid (e.g., variables written to only once, and only read after being written to)
     fn is_valid(&self) -> bool {
-        let mut prev_writes = BTreeSet::new();
-        for action in &self.actions {
+        Self::are_valid(&self.actions, &mut BTreeSet::new())
+    }
+
+    /// Checks that the actions are valid, given the variables written to before.
+    /// Adds the variables written to by the actions to `prev_writes`.
+    fn are_valid(actions: &[InterpreterAction<T>], prev_writes: &mut BTreeSet<usize>) -> bool {
+        for action in actions {
+            if let InterpreterAction::Branch(test, first, second) = action {
+                if !prev_writes.contains(&test.var) {
+                    return false;
+                }
+                let mut first_writes = prev_writes.clone();
+                let mut second_writes = prev_writes.clone();
+                if !Self::are_valid(first, &mut first_writes)
+                    || !Self::are_valid(second, &mut second_writes)
+                {
+                    return false;
+                }
+                // Only the variables written to in both branches can be read afterwards.
+                *prev_writes = &first_writes & &second_writes;
+                continue;
+            }
             let writes = action.writes();
             // writing to a variable already written?
-            if !writes.is_disjoint(&prev_writes) {
+            if !writes.is_disjoint(prev_writes) {
                 return false;
             }
             // reading a variable that was not written to?
-            if !action.reads().is_subset(&prev_writes) {
+            if !action.reads().is_subset(prev_writes) {
                 return false;
             }
             prev_writes.extend(writes);
@@ -203,7 +277,13 @@ impl<T: FieldElement> EffectsInterpreter<T> {
 
         let row_offset: i64 = data.row_offset.try_into().unwrap();
         let mut eval_stack = vec![];
-        for action in &self.actions {
+        // The actions still to be executed, with those of the innermost branch taken last.
+        let mut pending = vec![self.actions.iter()];
+        while let Some(actions) = pending.last_mut() {
+            let Some(action) = actions.next() else {
+                pending.pop();
+                continue;
+            };
             match action {
                 InterpreterAction::AssignExpression(idx, e) => {
                     let val = e.evaluate(&mut eval_stack, &vars[..]);
@@ -251,7 +331,7 @@ impl<T: FieldElement> EffectsInterpreter<T> {
                             }
                         })
                         .collect::<Vec<_>>();
-                    mutable_state.call_direct(*id, &mut args[..]).unwrap();
+                    assert!(mutable_state.call_direct(*id, &mut args[..]).unwrap());
                 }
                 InterpreterAction::Assertion(e1, e2, expected_equal) => {
                     let lhs_value = e1.evaluate(&mut eval_stack, &vars);
@@ -262,6 +342,13 @@ impl<T: FieldElement> EffectsInterpreter<T> {
                         assert_ne!(lhs_value, rhs_value, "Assertion failed");
                     }
                 }
+                InterpreterAction::Branch(test, first, second) => {
+                    pending.push(if test.holds(&vars) {
+                        first.iter()
+                    } else {
+                        second.iter()
+                    });
+                }
             }
         }
         assert!(eval_stack.is_empty());
@@ -351,13 +438,6 @@ impl VariableMapper {
         idx
     }
 
-    /// reserve a new variable index
-    pub fn reserve_idx(&mut self) -> usize {
-        let idx = self.count;
-        self.count += 1;
-        idx
-    }
-
     /// get the index of a variable if it was previously mapped
     pub fn get_var(&mut self, var: &Variable) -> Option<usize> {
         self.var_idx.get(var).copied()
@@ -486,11 +566,14 @@ mod test {
     };
     use crate::witgen::global_constraints;
     use crate::witgen::jit::block_machine_processor::BlockMachineProcessor;
+    use crate::witgen::jit::effect::{BranchCondition, Effect, ProverFunctionCall};
+    use crate::witgen::jit::symbolic_expression::SymbolicExpression;
     use crate::witgen::jit::test_util::read_pil;
     use crate::witgen::jit::variable::Variable;
     use crate::witgen::machines::{
         machine_extractor::MachineExtractor, KnownMachine, LookupCell, Machine,
     };
+    use crate::witgen::range_constraints::RangeConstraint;
     use crate::witgen::FixedData;
 
     use bit_vec::BitVec;
@@ -508,7 +591,9 @@ mod test {
 
         let fixed_data = FixedData::new(&analyzed, &fixed_col_vals, &[], Default::default(), 0);
         let fixed_data = global_constraints::set_global_constraints(fixed_data);
-        let machines = MachineExtractor::new(&fixed_data).split_out_machines();
+        let machines = MachineExtractor::new(&fixed_data)
+            .split_out_machines()
+            .unwrap();
         let [KnownMachine::BlockMachine(machine)] = machines
             .iter()
             .filter(|m| m.name().contains(machine_name))
@@ -541,7 +626,7 @@ mod test {
         let known_inputs = (0..12).map(Variable::Param).collect::<Vec<_>>();
 
         // generate interpreter
-        let interpreter = EffectsInterpreter::new(&known_inputs, &effects);
+        let interpreter = EffectsInterpreter::try_new(&known_inputs, &effects).unwrap();
         // call it
         let mut params = [GoldilocksField::default(); 16];
         let mut param_lookups = params
@@ -587,4 +672,95 @@ mod test {
             ]
         )
     }
+
+    fn param(i: usize) -> Variable {
+        Variable::Param(i)
+    }
+
+    fn symbol(var: &Variable) -> SymbolicExpression<GoldilocksField, Variable> {
+        SymbolicExpression::from_symbol(var.clone(), Default::default())
+    }
+
+    fn number(n: u64) -> SymbolicExpression<GoldilocksField, Variable> {
+        SymbolicExpression::from(GoldilocksField::from(n))
+    }
+
+    /// Runs the effects with the first parameter as input and returns the second parameter.
+    fn run_with_param(effects: &[Effect<GoldilocksField, Variable>], input: u64) -> u64 {
+        let (analyzed, fixed_col_vals) =
+            read_pil::<GoldilocksField>("namespace main(4); col witness x;");
+        let fixed_data = FixedData::new(&analyzed, &fixed_col_vals, &[], Default::default(), 0);
+        let mutable_state = MutableState::new(std::iter::empty(), &|_| {
+            Err("Query not implemented".to_string())
+        });
+
+        let interpreter = EffectsInterpreter::try_new(&[param(0)], effects).unwrap();
+        let input = GoldilocksField::from(input);
+        let mut output = GoldilocksField::default();
+        let mut params = [LookupCell::Input(&input), LookupCell::Output(&mut output)];
+        let poly_ids = analyzed
+            .committed_polys_in_source_order()
+            .flat_map(|p| p.0.array_elements().map(|e| e.1))
+            .collect_vec();
+        let mut data = CompactData::new(poly_ids.iter());
+        let data_ref = CompactDataRef::new(&mut data, 0);
+        interpreter.call(&fixed_data, &mutable_state, &mut params, data_ref);
+        output.to_degree()
+    }
+
+    #[test]
+    fn branches() {
+        // if (10 <= p_0 || p_0 <= 2) { p_1 = p_0 + 1; } else { p_1 = p_0 * 2; }
+        let effects = vec![Effect::Branch(
+            BranchCondition {
+                variable: param(0),
+                condition: RangeConstraint::from_range(10.into(), 2.into()),
+            },
+            vec![Effect::Assignment(param(1), symbol(&param(0)) + number(1))],
+            vec![Effect::Assignment(param(1), symbol(&param(0)) * number(2))],
+        )];
+        assert_eq!(run_with_param(&effects, 1), 2);
+        assert_eq!(run_with_param(&effects, 5), 10);
+        assert_eq!(run_with_param(&effects, 12), 13);
+    }
+
+    #[test]
+    fn nested_branches() {
+        // if (p_0 == 0) { p_1 = 7; } else if (p_0 == 1) { p_1 = 8; } else { p_1 = p_0; }
+        let effects = vec![Effect::Branch(
+            BranchCondition {
+                variable: param(0),
+                condition: RangeConstraint::from_value(0.into()),
+            },
+            vec![Effect::Assignment(param(1), number(7))],
+            vec![Effect::Branch(
+                BranchCondition {
+                    variable: param(0),
+                    condition: RangeConstraint::from_value(1.into()),
+                },
+                vec![Effect::Assignment(param(1), number(8))],
+                vec![Effect::Assignment(param(1), symbol(&param(0)))],
+            )],
+        )];
+        assert_eq!(run_with_param(&effects, 0), 7);
+        assert_eq!(run_with_param(&effects, 1), 8);
+        assert_eq!(run_with_param(&effects, 2), 2);
+    }
+
+    #[test]
+    fn prover_function_call() {
+        let effects = vec![Effect::ProverFunctionCall(ProverFunctionCall {
+            target: param(1),
+            function_index: 0,
+            row_offset: 0,
+            inputs: vec![param(0)],
+        })];
+        let err = EffectsInterpreter::<GoldilocksField>::try_new(&[param(0)], &effects)
+            .err()
+            .unwrap();
+        assert!(
+            err.contains("Prover function calls are not supported"),
+            "{err}"
+        );
+    }
 }
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use bit_vec::BitVec;
+use itertools::Itertools;
+
+use super::{LookupCell, Machine, MachineParts};
+use crate::witgen::data_structures::caller_data::CallerData;
+use crate::witgen::data_structures::mutable_state::MutableState;
+use crate::witgen::jit::witgen_inference::CanProcessCall;
+use crate::witgen::processor::OuterQuery;
+use crate::witgen::range_constraints::RangeConstraint;
+use crate::witgen::rows::RowPair;
+use crate::witgen::{EvalError, EvalResult, EvalValue, IncompleteCause, QueryCallback};
+
+use powdr_ast::analyzed::DegreeRange;
+use powdr_number::FieldElement;
+
+/// A hand-written witness generator for a single machine, i.e. for all witness
+/// columns of a namespace.
+///
+/// The machine calls are processed in the order in which the other machines make
+/// them. The arguments of a call are the expressions on the receiving side of the
+/// connection, so for a machine compiled from powdr-asm, they are the operation id
+/// followed by the inputs and outputs of the operation.
+pub trait MachineWitnessProvider<T: FieldElement>: Send + Sync {
+    /// Returns true if a call via the given connecting identity can be processed
+    /// whenever the arguments flagged in `known_arguments` are known.
+    fn can_process_call(&self, identity_id: u64, known_arguments: &[bool]) -> bool;
+
+    /// Processes a call via the given connecting identity.
+    /// All outputs in `arguments` need to be set. An error is unrecoverable.
+    fn process_call(
+        &mut self,
+        identity_id: u64,
+        arguments: &mut [LookupCell<'_, T>],
+    ) -> Result<(), String>;
+
+    /// Returns the values of the witness columns of the machine, keyed by their
+    /// fully qualified name. All columns need to have the same size, which has to be
+    /// a power of two in the given degree range.
+    /// Multiplicity columns of the connections can be omitted, they are
+    /// computed by powdr.
+    fn take_witness(&mut self, degree_range: DegreeRange) -> HashMap<String, Vec<T>>;
+}
+
+/// A machine whose witness is generated by a [MachineWitnessProvider].
+pub struct ExternalMachine<'a, T: FieldElement> {
+    name: String,
+    parts: MachineParts<'a, T>,
+    provider: Box<dyn MachineWitnessProvider<T>>,
+}
+
+impl<'a, T: FieldElement> ExternalMachine<'a, T> {
+    pub fn new(
+        name: String,
+        parts: MachineParts<'a, T>,
+        provider: Box<dyn MachineWitnessProvider<T>>,
+    ) -> Self {
+        Self {
+            name,
+            parts,
+            provider,
+        }
+    }
+
+    fn known_arguments(arguments: &[LookupCell<'_, T>]) -> Vec<bool> {
+        arguments.iter().map(|a| a.is_input()).collect()
+    }
+}
+
+impl<'a, T: FieldElement> Machine<'a, T> for ExternalMachine<'a, T> {
+    fn can_process_call_fully(
+        &mut self,
+        _can_process: impl CanProcessCall<T>,
+        identity_id: u64,
+        known_arguments: &BitVec,
+        range_constraints: &[RangeConstraint<T>],
+    ) -> Option<Vec<RangeConstraint<T>>> {
+        assert!(self.parts.connections.contains_key(&identity_id));
+        let known_arguments = known_arguments.iter().collect_vec();
+        self.provider
+            .can_process_call(identity_id, &known_arguments)
+            .then(|| vec![RangeConstraint::unconstrained(); range_constraints.len()])
+    }
+
+    fn process_lookup_direct<'b, 'c, Q: QueryCallback<T>>(
+        &mut self,
+        _mutable_state: &'b MutableState<'a, T, Q>,
+        identity_id: u64,
+        values: &mut [LookupCell<'c, T>],
+    ) -> Result<bool, EvalError<T>> {
+        if !self
+            .provider
+            .can_process_call(identity_id, &Self::known_arguments(values))
+        {
+            return Ok(false);
+        }
+        self.provider
+            .process_call(identity_id, values)
+            .map_err(|e| EvalError::Generic(format!("Error in machine {}: {e}", self.name)))?;
+        Ok(true)
+    }
+
+    fn process_plookup<Q: QueryCallback<T>>(
+        &mut self,
+        mutable_state: &MutableState<'a, T, Q>,
+        identity_id: u64,
+        caller_rows: &RowPair<'_, 'a, T>,
+    ) -> EvalResult<'a, T> {
+        let connection = self.parts.connections[&identity_id];
+        let outer_query = match OuterQuery::try_new(caller_rows, connection) {
+            Ok(outer_query) => outer_query,
+            Err(incomplete_cause) => return Ok(EvalValue::incomplete(incomplete_cause)),
+        };
+        let mut data = CallerData::from(&outer_query);
+        if self.process_lookup_direct(mutable_state, identity_id, &mut data.as_lookup_cells())? {
+            Ok(EvalResult::from(data)?.report_side_effect())
+        } else {
+            Ok(EvalValue::incomplete(
+                IncompleteCause::NonConstantRequiredArgument("input of external machine"),
+            ))
+        }
+    }
+
+    fn take_witness_col_values<'b, Q: QueryCallback<T>>(
+        &mut self,
+        _mutable_state: &'b MutableState<'a, T, Q>,
+    ) -> HashMap<String, Vec<T>> {
+        let degree_range = self.parts.common_degree_range();
+        let mut columns = self.provider.take_witness(degree_range);
+
+        let size = columns
+            .values()
+            .map(|c| c.len())
+            .unique()
+            .exactly_one()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "The columns provided for machine {} need to have one common size.",
+                    self.name
+                )
+            }) as u64;
+        assert!(
+            size.is_power_of_two() && degree_range.fit(size) == size,
+            "The size {size} of machine {} is not a power of two in the range {degree_range}.",
+            self.name
+        );
+
+        let multiplicity_columns = self
+            .parts
+            .connections
+            .values()
+            .filter_map(|c| c.multiplicity_column)
+            .collect_vec();
+        for multiplicity_column in multiplicity_columns {
+            let name = self.parts.column_name(&multiplicity_column).to_string();
+            columns
+                .entry(name)
+                .or_insert_with(|| vec![T::zero(); size as usize]);
+        }
+
+        let expected = self
+            .parts
+            .witnesses
+            .iter()
+            .map(|w| self.parts.column_name(w))
+            .sorted()
+            .collect_vec();
+        let provided = columns.keys().map(|n| n.as_str()).sorted().collect_vec();
+        assert_eq!(
+            expected, provided,
+            "The columns provided for machine {} do not match its witness columns.",
+            self.name
+        );
+
+        columns
+    }
+
+    fn identity_ids(&self) -> Vec<u64> {
+        self.parts.identity_ids()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
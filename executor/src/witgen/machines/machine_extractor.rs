@@ -5,6 +5,7 @@ use itertools::Itertools;
 use super::block_machine::BlockMachine;
 use super::double_sorted_witness_machine_16::DoubleSortedWitnesses16;
 use super::double_sorted_witness_machine_32::DoubleSortedWitnesses32;
+use super::external_machine::{ExternalMachine, MachineWitnessProvider};
 use super::fixed_lookup_machine::FixedLookup;
 use super::sorted_witness_machine::SortedWitnesses;
 use super::FixedData;
@@ -15,6 +16,7 @@ use crate::witgen::machines::dynamic_machine::DynamicMachine;
 use crate::witgen::machines::second_stage_machine::SecondStageMachine;
 use crate::witgen::machines::Connection;
 use crate::witgen::machines::{write_once_memory::WriteOnceMemory, MachineParts};
+use crate::witgen::EvalError;
 
 use powdr_ast::analyzed::{
     self, AlgebraicExpression as Expression, PolyID, PolynomialReference, Reference,
@@ -24,16 +26,34 @@ use powdr_number::FieldElement;
 
 pub struct MachineExtractor<'a, T: FieldElement> {
     fixed: &'a FixedData<'a, T>,
+    /// Witness providers for machines, by namespace.
+    machine_witness_providers: BTreeMap<String, Box<dyn MachineWitnessProvider<T>>>,
 }
 
 impl<'a, T: FieldElement> MachineExtractor<'a, T> {
     pub fn new(fixed: &'a FixedData<'a, T>) -> Self {
-        Self { fixed }
+        Self {
+            fixed,
+            machine_witness_providers: Default::default(),
+        }
+    }
+
+    /// Generates the witness of the machines in the given namespaces using the providers
+    /// instead of the built-in machines.
+    pub fn with_machine_witness_providers(
+        self,
+        machine_witness_providers: BTreeMap<String, Box<dyn MachineWitnessProvider<T>>>,
+    ) -> Self {
+        Self {
+            machine_witness_providers,
+            ..self
+        }
     }
 
     /// Finds machines in the witness columns and identities and returns a list of machines.
     /// The first returned machine is the "main machine", i.e. a machine that has no incoming connections.
-    pub fn split_out_machines(&self) -> Vec<KnownMachine<'a, T>> {
+    /// Fails if a witness provider has been given for a namespace without a secondary machine.
+    pub fn split_out_machines(mut self) -> Result<Vec<KnownMachine<'a, T>>, EvalError<T>> {
         // Ignore prover functions that reference columns of later stages.
         let all_witnesses = self.fixed.witness_cols.keys().collect::<HashSet<_>>();
         let current_stage_witnesses = self
@@ -182,9 +202,26 @@ impl<'a, T: FieldElement> MachineExtractor<'a, T> {
             id_counter += 1;
             let name_with_type = |t: &str| format!("Secondary machine {id}: {name} ({t})");
 
-            machines.push(build_machine(self.fixed, machine_parts, name_with_type));
+            let machine = match self.machine_witness_providers.remove(&name) {
+                Some(provider) => {
+                    log::debug!("Using external witness provider for machine {name}");
+                    KnownMachine::ExternalMachine(ExternalMachine::new(
+                        name_with_type("External"),
+                        machine_parts,
+                        provider,
+                    ))
+                }
+                None => build_machine(self.fixed, machine_parts, name_with_type),
+            };
+            machines.push(machine);
         }
         publics.add_all(base_identities.as_slice()).unwrap();
+        if !self.machine_witness_providers.is_empty() {
+            return Err(EvalError::Generic(format!(
+                "No secondary machine found for the witness providers of namespaces: {}",
+                self.machine_witness_providers.keys().format(", ")
+            )));
+        }
 
         // Always add a fixed lookup machine.
         // Note that this machine comes last, because some machines do a fixed lookup
@@ -244,14 +281,16 @@ impl<'a, T: FieldElement> MachineExtractor<'a, T> {
             base_prover_functions,
         );
 
-        if let Some(main_machine) = build_main_machine(self.fixed, base_parts) {
-            std::iter::once(main_machine).chain(machines).collect()
-        } else {
-            if !machines.is_empty() {
-                log::error!("No main machine was extracted, but secondary machines were. Does the system have a cycle?");
-            }
-            vec![]
-        }
+        Ok(
+            if let Some(main_machine) = build_main_machine(self.fixed, base_parts) {
+                std::iter::once(main_machine).chain(machines).collect()
+            } else {
+                if !machines.is_empty() {
+                    log::error!("No main machine was extracted, but secondary machines were. Does the system have a cycle?");
+                }
+                vec![]
+            },
+        )
     }
 
     /// Extends a set of witnesses to the full set of row-connected witnesses.
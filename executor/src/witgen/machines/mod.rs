@@ -13,6 +13,7 @@ use crate::witgen::data_structures::mutable_state::MutableState;
 use self::block_machine::BlockMachine;
 use self::double_sorted_witness_machine_16::DoubleSortedWitnesses16;
 use self::double_sorted_witness_machine_32::DoubleSortedWitnesses32;
+use self::external_machine::ExternalMachine;
 pub use self::fixed_lookup_machine::FixedLookup;
 use self::profiling::{record_end, record_start};
 use self::second_stage_machine::SecondStageMachine;
@@ -29,6 +30,7 @@ mod block_machine;
 mod double_sorted_witness_machine_16;
 mod double_sorted_witness_machine_32;
 mod dynamic_machine;
+pub mod external_machine;
 mod fixed_lookup_machine;
 pub mod machine_extractor;
 pub mod profiling;
@@ -169,6 +171,7 @@ pub enum KnownMachine<'a, T: FieldElement> {
     BlockMachine(BlockMachine<'a, T>),
     DynamicMachine(DynamicMachine<'a, T>),
     FixedLookup(FixedLookup<'a, T>),
+    ExternalMachine(ExternalMachine<'a, T>),
 }
 
 /// A macro to dispatch a method call to the correct variant of [KnownMachine].
@@ -183,6 +186,7 @@ macro_rules! match_variant {
             KnownMachine::BlockMachine($i) => $e,
             KnownMachine::DynamicMachine($i) => $e,
             KnownMachine::FixedLookup($i) => $e,
+            KnownMachine::ExternalMachine($i) => $e,
         }
     };
 }
@@ -1,9 +1,16 @@
 use std::{
     cell::RefCell,
     collections::BTreeMap,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 
+use serde::Serialize;
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum Event {
     Start,
@@ -16,6 +23,24 @@ thread_local! {
     /// Maps a machine name (assumed to be globally unique) to an ID.
     /// This is done so that we can use a usize in the event log.
     static NAME_TO_ID: RefCell<BTreeMap<String, usize>> = const { RefCell::new(BTreeMap::new()) };
+    /// A process-wide unique ID of the current thread, used as the track in the Chrome trace.
+    static THREAD_ID: usize = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether events are currently collected into [CHROME_TRACE].
+/// Checked before locking, so that profiling stays cheap if no trace is requested.
+static CHROME_TRACE_ENABLED: AtomicBool = AtomicBool::new(false);
+/// Events of all threads, collected between [start_chrome_trace] and [write_chrome_trace].
+static CHROME_TRACE: Mutex<Option<ChromeTrace>> = Mutex::new(None);
+
+struct ChromeTrace {
+    start: Instant,
+    /// The (event, name, thread ID, time) tuples, in the order they were recorded.
+    events: Vec<(Event, String, usize, Instant)>,
+    /// The names of all threads that recorded events, by thread ID.
+    thread_names: BTreeMap<usize, String>,
 }
 
 /// Returns the ID for a given machine name, creating a new one if necessary.
@@ -33,13 +58,112 @@ fn id_from_name(name: &str) -> usize {
 /// Adds the start of a computation to the event log.
 pub fn record_start(name: &str) {
     let id = id_from_name(name);
-    EVENT_LOG.with(|s| s.borrow_mut().push((Event::Start, id, Instant::now())));
+    let time = Instant::now();
+    EVENT_LOG.with(|s| s.borrow_mut().push((Event::Start, id, time)));
+    record_chrome_trace_event(Event::Start, name, time);
 }
 
 /// Adds the end of a computation to the event log.
 pub fn record_end(name: &str) {
     let id = id_from_name(name);
-    EVENT_LOG.with(|s| s.borrow_mut().push((Event::End, id, Instant::now())));
+    let time = Instant::now();
+    EVENT_LOG.with(|s| s.borrow_mut().push((Event::End, id, time)));
+    record_chrome_trace_event(Event::End, name, time);
+}
+
+fn record_chrome_trace_event(event: Event, name: &str, time: Instant) {
+    if !CHROME_TRACE_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(trace) = CHROME_TRACE.lock().unwrap().as_mut() {
+        let thread_id = THREAD_ID.with(|id| *id);
+        trace.thread_names.entry(thread_id).or_insert_with(|| {
+            std::thread::current()
+                .name()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("thread {thread_id}"))
+        });
+        trace
+            .events
+            .push((event, name.to_string(), thread_id, time));
+    }
+}
+
+/// Starts collecting the events of all threads, so that they can be exported
+/// using [write_chrome_trace]. Any previously collected events are discarded.
+pub fn start_chrome_trace() {
+    *CHROME_TRACE.lock().unwrap() = Some(ChromeTrace {
+        start: Instant::now(),
+        events: vec![],
+        thread_names: Default::default(),
+    });
+    CHROME_TRACE_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// An entry of the Chrome trace event format, see
+/// https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+#[derive(Serialize)]
+struct ChromeTraceEvent<'a> {
+    name: &'a str,
+    ph: &'static str,
+    /// The timestamp in microseconds.
+    ts: f64,
+    pid: u32,
+    tid: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<BTreeMap<&'static str, &'a str>>,
+}
+
+/// Stops collecting events and writes all events collected since [start_chrome_trace]
+/// in the Chrome trace event format, which can be loaded into Perfetto or chrome://tracing.
+/// Each thread is a separate track and nested machine calls show up as nested slices.
+pub fn write_chrome_trace(writer: impl Write) -> io::Result<()> {
+    CHROME_TRACE_ENABLED.store(false, Ordering::Relaxed);
+    let Some(trace) = CHROME_TRACE.lock().unwrap().take() else {
+        return Err(io::Error::other("No Chrome trace was started"));
+    };
+
+    let thread_names = trace
+        .thread_names
+        .iter()
+        .map(|(thread_id, thread_name)| ChromeTraceEvent {
+            name: "thread_name",
+            ph: "M",
+            ts: 0.0,
+            pid: 0,
+            tid: *thread_id,
+            args: Some([("name", thread_name.as_str())].into()),
+        });
+    let events = trace
+        .events
+        .iter()
+        .map(|(event, name, thread_id, time)| ChromeTraceEvent {
+            name,
+            ph: match event {
+                Event::Start => "B",
+                Event::End => "E",
+            },
+            ts: time.duration_since(trace.start).as_secs_f64() * 1e6,
+            pid: 0,
+            tid: *thread_id,
+            args: None,
+        });
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ChromeTraceFile<'a> {
+        trace_events: Vec<ChromeTraceEvent<'a>>,
+        display_time_unit: &'static str,
+    }
+
+    serde_json::to_writer(
+        writer,
+        &ChromeTraceFile {
+            trace_events: thread_names.chain(events).collect(),
+            display_time_unit: "ms",
+        },
+    )?;
+    Ok(())
 }
 
 pub fn reset_and_print_profile_summary() {
@@ -119,3 +243,38 @@ pub fn reset_and_print_profile_summary() {
         log::debug!("\n");
     });
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chrome_trace() {
+        start_chrome_trace();
+        record_start("test_outer");
+        record_start("test_inner");
+        record_end("test_inner");
+        record_end("test_outer");
+        let mut output = vec![];
+        write_chrome_trace(&mut output).unwrap();
+
+        let trace: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        // Other tests might run witness generation concurrently, so we only look at our events.
+        let events = events
+            .iter()
+            .filter(|e| e["name"].as_str().unwrap().starts_with("test_"))
+            .map(|e| (e["name"].as_str().unwrap(), e["ph"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                ("test_outer", "B"),
+                ("test_inner", "B"),
+                ("test_inner", "E"),
+                ("test_outer", "E")
+            ]
+        );
+        assert!(events.len() < trace["traceEvents"].as_array().unwrap().len());
+    }
+}
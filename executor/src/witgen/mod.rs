@@ -49,6 +49,9 @@ mod vm_processor;
 
 pub use affine_expression::{AffineExpression, AffineResult, AlgebraicVariable};
 pub use evaluators::partial_expression_evaluator::{PartialExpressionEvaluator, SymbolicVariables};
+pub use machines::external_machine::MachineWitnessProvider;
+pub use machines::profiling::{start_chrome_trace, write_chrome_trace};
+pub use machines::LookupCell;
 
 static OUTER_CODE_NAME: &str = "witgen (outer code)";
 
@@ -66,6 +69,7 @@ pub struct WitgenCallbackContext<T> {
     /// Arc was moved one level up... but I have to investigate this further.
     fixed_col_values: Arc<Vec<(String, VariablySizedColumn<T>)>>,
     query_callback: Arc<dyn QueryCallback<T>>,
+    use_jit_interpreter: bool,
 }
 
 impl<T: FieldElement> WitgenCallbackContext<T> {
@@ -77,6 +81,16 @@ impl<T: FieldElement> WitgenCallbackContext<T> {
         Self {
             fixed_col_values,
             query_callback,
+            use_jit_interpreter: false,
+        }
+    }
+
+    /// Interprets the derived witgen code instead of compiling it, see
+    /// [WitnessGenerator::with_jit_interpreter].
+    pub fn with_jit_interpreter(self, use_jit_interpreter: bool) -> Self {
+        Self {
+            use_jit_interpreter,
+            ..self
         }
     }
 
@@ -140,6 +154,7 @@ impl<T: FieldElement> WitgenCallbackContext<T> {
             WitnessGenerator::new(pil, &fixed_col_values, &*self.query_callback)
                 .with_external_witness_values(current_witness)
                 .with_challenges(stage, challenges)
+                .with_jit_interpreter(self.use_jit_interpreter)
                 .generate()
         }
     }
@@ -162,8 +177,10 @@ pub struct WitnessGenerator<'a, 'b, T: FieldElement> {
     fixed_col_values: &'b Vec<(String, VariablySizedColumn<T>)>,
     query_callback: &'b dyn QueryCallback<T>,
     external_witness_values: &'b [(String, Vec<T>)],
+    machine_witness_providers: BTreeMap<String, Box<dyn MachineWitnessProvider<T>>>,
     stage: u8,
     challenges: BTreeMap<u64, T>,
+    use_jit_interpreter: bool,
 }
 
 impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
@@ -177,8 +194,10 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
             fixed_col_values,
             query_callback,
             external_witness_values: &[],
+            machine_witness_providers: BTreeMap::new(),
             stage: 0,
             challenges: BTreeMap::new(),
+            use_jit_interpreter: false,
         }
     }
 
@@ -192,6 +211,18 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
         }
     }
 
+    /// Generates the witness of the machine in the given namespace using the provider,
+    /// which receives all calls to the machine.
+    pub fn with_machine_witness_provider(
+        mut self,
+        namespace: &str,
+        provider: Box<dyn MachineWitnessProvider<T>>,
+    ) -> Self {
+        self.machine_witness_providers
+            .insert(namespace.to_string(), provider);
+        self
+    }
+
     pub fn with_challenges(self, stage: u8, challenges: BTreeMap<u64, T>) -> Self {
         WitnessGenerator {
             stage,
@@ -200,9 +231,26 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
         }
     }
 
+    /// Interprets the code derived for block machines instead of compiling it to machine code.
+    /// The interpreter does not need a Rust toolchain and avoids the compilation time,
+    /// but the code runs slower.
+    pub fn with_jit_interpreter(self, use_jit_interpreter: bool) -> Self {
+        WitnessGenerator {
+            use_jit_interpreter,
+            ..self
+        }
+    }
+
     /// Generates the committed polynomial values
     /// @returns the values (in source order) and the degree of the polynomials.
     pub fn generate(self) -> Vec<(String, Vec<T>)> {
+        self.try_generate()
+            .unwrap_or_else(|e| panic!("Witness generation failed: {e}"))
+    }
+
+    /// Like [WitnessGenerator::generate], but returns an error if the machine witness
+    /// providers do not match the machines.
+    pub fn try_generate(self) -> Result<Vec<(String, Vec<T>)>, EvalError<T>> {
         record_start(OUTER_CODE_NAME);
         let fixed = FixedData::new(
             self.analyzed,
@@ -210,7 +258,8 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
             self.external_witness_values,
             self.challenges,
             self.stage,
-        );
+        )
+        .with_jit_interpreter(self.use_jit_interpreter);
         let fixed = fixed.filter_identities(|fixed, identity| {
             let references_later_stage_challenge = identity.expr_any(|expr| {
                 if let AlgebraicExpression::Challenge(challenge) = expr {
@@ -238,7 +287,10 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
         // Removes identities like X * (X - 1) = 0 or [ A ] in [ BYTES ]
         // These are already captured in the range constraints.
         let fixed = global_constraints::set_global_constraints(fixed);
-        let machines = MachineExtractor::new(&fixed).split_out_machines();
+        let machines = MachineExtractor::new(&fixed)
+            .with_machine_witness_providers(self.machine_witness_providers)
+            .split_out_machines()
+            .inspect_err(|_| record_end(OUTER_CODE_NAME))?;
 
         // Run main machine and extract columns from all machines.
         let columns = MutableState::new(machines.into_iter(), &self.query_callback).run();
@@ -279,7 +331,7 @@ impl<'a, 'b, T: FieldElement> WitnessGenerator<'a, 'b, T> {
                 (name, column)
             })
             .collect::<Vec<_>>();
-        witness_cols
+        Ok(witness_cols)
     }
 }
 
@@ -319,6 +371,7 @@ pub struct FixedData<'a, T: FieldElement> {
     global_range_constraints: GlobalConstraints<T>,
     intermediate_definitions: BTreeMap<AlgebraicReferenceThin, AlgebraicExpression<T>>,
     stage: u8,
+    use_jit_interpreter: bool,
 }
 
 impl<'a, T: FieldElement> FixedData<'a, T> {
@@ -410,6 +463,14 @@ impl<'a, T: FieldElement> FixedData<'a, T> {
             global_range_constraints,
             intermediate_definitions,
             stage,
+            use_jit_interpreter: false,
+        }
+    }
+
+    pub fn with_jit_interpreter(self, use_jit_interpreter: bool) -> Self {
+        Self {
+            use_jit_interpreter,
+            ..self
         }
     }
 
@@ -509,6 +570,11 @@ impl<'a, T: FieldElement> FixedData<'a, T> {
         self.stage
     }
 
+    /// Returns true if the derived witgen code is interpreted instead of compiled.
+    pub fn use_jit_interpreter(&self) -> bool {
+        self.use_jit_interpreter
+    }
+
     pub fn global_range_constraints(&self) -> &GlobalConstraints<T> {
         &self.global_range_constraints
     }
@@ -15,7 +15,7 @@ use powdr_ast::parsed::{
     types::{ExpressionInArrayLength, Type, TypeScheme},
     visitor::{Children, ExpressionVisitable},
     ArrayLiteral, BinaryOperation, BlockExpression, EnumDeclaration, EnumVariant, Expression,
-    FunctionCall, IndexAccess, LambdaExpression, LetStatementInsideBlock, MatchArm,
+    FieldAccess, FunctionCall, IndexAccess, LambdaExpression, LetStatementInsideBlock, MatchArm,
     MatchExpression, NamedExpression, NamedType, Pattern, PilStatement, StatementInsideBlock,
     StructDeclaration, StructExpression, TraitDeclaration, UnaryOperation,
 };
@@ -57,7 +57,11 @@ impl Folder for Canonicalizer<'_> {
                 .statements
                 .into_iter()
                 .filter_map(|statement| match statement {
-                    ModuleStatement::SymbolDefinition(SymbolDefinition { name, value }) => {
+                    ModuleStatement::SymbolDefinition(SymbolDefinition {
+                        source,
+                        name,
+                        value,
+                    }) => {
                         match value {
                             SymbolValue::Machine(m) => {
                                 // canonicalize the machine based on the same path, so we can reuse the same instance
@@ -80,7 +84,16 @@ impl Folder for Canonicalizer<'_> {
                                 .transpose(),
                             },
                         }
-                        .map(|value| value.map(|value| SymbolDefinition { name, value }.into()))
+                        .map(|value| {
+                            value.map(|value| {
+                                SymbolDefinition {
+                                    source,
+                                    name,
+                                    value,
+                                }
+                                .into()
+                            })
+                        })
                     }
                     ModuleStatement::PilStatement(mut pil_statement) => {
                         canonicalize_inside_pil_statement(
@@ -168,7 +181,8 @@ fn free_inputs_in_expression<'a>(
         Expression::BinaryOperation(_, BinaryOperation { left, right, .. }) => {
             Box::new(free_inputs_in_expression(left).chain(free_inputs_in_expression(right)))
         }
-        Expression::UnaryOperation(_, UnaryOperation { expr, .. }) => {
+        Expression::UnaryOperation(_, UnaryOperation { expr, .. })
+        | Expression::FieldAccess(_, FieldAccess { object: expr, .. }) => {
             free_inputs_in_expression(expr)
         }
         Expression::FunctionCall(
@@ -206,7 +220,8 @@ fn free_inputs_in_expression_mut<'a>(
         Expression::BinaryOperation(_, BinaryOperation { left, right, .. }) => Box::new(
             free_inputs_in_expression_mut(left).chain(free_inputs_in_expression_mut(right)),
         ),
-        Expression::UnaryOperation(_, UnaryOperation { expr, .. }) => {
+        Expression::UnaryOperation(_, UnaryOperation { expr, .. })
+        | Expression::FieldAccess(_, FieldAccess { object: expr, .. }) => {
             free_inputs_in_expression_mut(expr)
         }
         Expression::FunctionCall(
@@ -287,12 +302,25 @@ fn canonicalize_inside_expression(
 ) {
     e.pre_visit_expressions_mut(&mut |e| {
         match e {
-            Expression::Reference(_, reference) => {
+            Expression::Reference(source_ref, reference) => {
                 // If resolving the reference fails, we assume it is a local variable that has been checked below.
                 if let Some(n) = paths.get(&path.clone().join(reference.path.clone())) {
                     reference.path = n.relative_to(&Default::default());
-                } else {
-                    assert!(reference.path.try_to_identifier().is_some());
+                } else if reference.path.try_to_identifier().is_none() {
+                    // The parser reads `a.b` as the path `a::b`. Since it does not resolve,
+                    // it is an access to the field `b` of the value `a`, which has been checked below.
+                    let mut object = reference.clone();
+                    let field = object.path.name().clone();
+                    object.path.pop();
+                    object.type_args = None;
+                    let source_ref = source_ref.clone();
+                    *e = Expression::FieldAccess(
+                        source_ref.clone(),
+                        FieldAccess {
+                            object: Box::new(Expression::Reference(source_ref, object)),
+                            field,
+                        },
+                    );
                 }
             }
             Expression::BlockExpression(_, BlockExpression { statements, .. }) => {
@@ -500,9 +528,11 @@ fn check_path_internal<'a>(
                         .statements
                         .iter()
                         .find_map(|s| match s {
-                            ModuleStatement::SymbolDefinition(SymbolDefinition { name, value }) => {
-                                (name == member).then_some(value.as_ref())
-                            }
+                            ModuleStatement::SymbolDefinition(SymbolDefinition {
+                                name,
+                                value,
+                                ..
+                            }) => (name == member).then_some(value.as_ref()),
                             // some pil statements introduce names
                             ModuleStatement::PilStatement(s) => match s {
                                 PilStatement::EnumDeclaration(_, d) => (d.name == member)
@@ -644,20 +674,22 @@ fn check_module(
             ModuleStatement::PilStatement(p) => {
                 check_pil_statement_inside_module(location.clone(), p, state)?;
             }
-            ModuleStatement::SymbolDefinition(SymbolDefinition { name, value }) => match value {
-                SymbolValue::Machine(machine) => {
-                    check_machine(location.with_part(name), machine, state)?;
-                }
-                SymbolValue::Module(module) => {
-                    let m = match module {
-                        Module::External(_) => unreachable!(),
-                        Module::Local(m) => m,
-                    };
-                    check_module(location.with_part(name), m, state)?;
+            ModuleStatement::SymbolDefinition(SymbolDefinition { name, value, .. }) => {
+                match value {
+                    SymbolValue::Machine(machine) => {
+                        check_machine(location.with_part(name), machine, state)?;
+                    }
+                    SymbolValue::Module(module) => {
+                        let m = match module {
+                            Module::External(_) => unreachable!(),
+                            Module::Local(m) => m,
+                        };
+                        check_module(location.with_part(name), m, state)?;
+                    }
+                    SymbolValue::Import(s) => check_import(location.clone(), s.clone(), state)
+                        .map_err(|e| SourceRef::default().with_error(e))?,
                 }
-                SymbolValue::Import(s) => check_import(location.clone(), s.clone(), state)
-                    .map_err(|e| SourceRef::default().with_error(e))?,
-            },
+            }
         }
     }
     Ok(())
@@ -843,8 +875,20 @@ fn check_expression(
                     return Ok(());
                 }
             }
-            check_path_try_prelude(location.clone(), reference.path.clone(), state)
-                .map_err(|e| source_ref.with_error(e))
+            match check_path_try_prelude(location.clone(), reference.path.clone(), state) {
+                Ok(()) => Ok(()),
+                // The parser reads `a.b` as the path `a::b`, but it can also be
+                // an access to the field `b` of the value `a`.
+                Err(e) if reference.path.parts().len() > 1 => {
+                    let mut object = reference.clone();
+                    object.path.pop();
+                    object.type_args = None;
+                    let object = Expression::Reference(source_ref.clone(), object);
+                    check_expression(location, &object, state, type_vars, local_variables)
+                        .map_err(|_| source_ref.with_error(e))
+                }
+                Err(e) => Err(source_ref.with_error(e)),
+            }
         }
         Expression::PublicReference(_, _) | Expression::Number(_, _) | Expression::String(_, _) => {
             Ok(())
@@ -877,6 +921,7 @@ fn check_expression(
             check_expression(location, b.as_ref(), state, type_vars, local_variables)
         }
         Expression::UnaryOperation(_, UnaryOperation { expr, .. })
+        | Expression::FieldAccess(_, FieldAccess { object: expr, .. })
         | Expression::FreeInput(_, expr) => {
             check_expression(location, expr, state, type_vars, local_variables)
         }
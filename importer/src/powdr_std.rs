@@ -11,6 +11,7 @@ use powdr_ast::parsed::{
     folder::Folder,
 };
 use powdr_parser::parse_asm;
+use powdr_parser_util::SourceRef;
 
 use crate::load_module_files;
 
@@ -69,6 +70,7 @@ impl Folder for StdAdder {
         let mut main = p.main;
         main.statements
             .push(ModuleStatement::SymbolDefinition(SymbolDefinition {
+                source: SourceRef::unknown(),
                 name: "std".to_string(),
                 value: SymbolValue::Module(Module::Local(load_std())),
             }));
@@ -109,6 +111,7 @@ impl Folder for StdAdder {
             let std_import_path =
                 SymbolPath::from_parts([Part::Super, Part::Named("std".to_string())]);
             statements.push(ModuleStatement::SymbolDefinition(SymbolDefinition {
+                source: SourceRef::unknown(),
                 name: "std".to_string(),
                 value: SymbolValue::Import(Import {
                     path: std_import_path,
@@ -0,0 +1,149 @@
+//! A persistent on-disk cache for JIT-compiled libraries.
+//!
+//! Libraries are stored under a key that is the hash of everything that influences
+//! the compiled artifact: The generated code (which already contains all field-specific
+//! parts like the modulus), the `Cargo.toml`, the `RUSTFLAGS`, the version of
+//! the Rust compiler, the version of powdr and the host CPU and its target features.
+//! The latter are needed because libraries are compiled with `-C target-cpu=native`,
+//! so a library compiled on one machine might not run on another one sharing the cache.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::RwLock,
+};
+
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+/// The environment variable that sets the cache directory,
+/// unless it is overwritten using [set_cache_dir].
+pub const CACHE_DIR_ENV_VAR: &str = "POWDR_JIT_CACHE_DIR";
+
+lazy_static! {
+    static ref CACHE_DIR: RwLock<Option<PathBuf>> =
+        RwLock::new(std::env::var_os(CACHE_DIR_ENV_VAR).map(PathBuf::from));
+    /// The output of `rustc -vV`, or None if it could not be determined.
+    static ref COMPILER_VERSION: Option<String> = rustc(&["-vV"]);
+    /// The name and the target features of the host CPU, or None if they could not be determined.
+    static ref HOST_CPU: Option<String> = host_cpu();
+}
+
+/// Sets the directory in which JIT-compiled libraries are cached across runs.
+/// If `None`, caching is disabled.
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+    *CACHE_DIR.write().unwrap() = dir;
+}
+
+/// Returns the directory in which JIT-compiled libraries are cached, if any.
+pub fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.read().unwrap().clone()
+}
+
+/// Runs `rustc` with the given arguments and returns its output.
+fn rustc(args: &[&str]) -> Option<String> {
+    // Run in the same kind of directory as the compilation itself,
+    // so that rustup selects the same toolchain.
+    let out = Command::new("rustc")
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Returns the name of the host CPU together with the configuration `rustc` uses
+/// for `-C target-cpu=native`, which includes all enabled target features.
+fn host_cpu() -> Option<String> {
+    // The `native` entry reads "native - Select the CPU of the current host (currently <name>)."
+    let cpus = rustc(&["--print", "target-cpus"])?;
+    let native = cpus
+        .lines()
+        .find(|line| line.trim_start().starts_with("native"))?
+        .trim()
+        .to_string();
+    let cfg = rustc(&["--print", "cfg", "-C", "target-cpu=native"])?;
+    Some(format!("{native}\n{cfg}"))
+}
+
+/// Returns the cache key for the given inputs of a compilation,
+/// or None if caching is not possible.
+pub(crate) fn cache_key(cargo_toml: &str, code: &str, rustflags: &str) -> Option<String> {
+    let compiler_version = COMPILER_VERSION.as_ref()?;
+    let host_cpu = HOST_CPU.as_ref()?;
+    let mut hasher = Sha256::default();
+    for part in [
+        env!("CARGO_PKG_VERSION"),
+        compiler_version,
+        host_cpu,
+        rustflags,
+        cargo_toml,
+        code,
+    ] {
+        // Hash the length as well, so that the boundaries between the parts are unambiguous.
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+fn cached_library_path(dir: &Path, key: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{key}.{extension}"))
+}
+
+/// Returns the path to the cached library with the given key, if it exists.
+pub(crate) fn lookup(key: &str, extension: &str) -> Option<PathBuf> {
+    let path = cached_library_path(&cache_dir()?, key, extension);
+    path.exists().then_some(path)
+}
+
+/// Copies the library at `lib_path` into the cache under the given key.
+/// Failures are logged but otherwise ignored, since the cache is only an optimization.
+pub(crate) fn store(key: &str, extension: &str, lib_path: &Path) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    let target = cached_library_path(&dir, key, extension);
+    // Copy to a temporary file first and then rename, so that concurrent processes
+    // never observe a partially written library.
+    let tmp_target = dir.join(format!("{key}.{}.tmp", std::process::id()));
+    let result = fs::create_dir_all(&dir)
+        .and_then(|_| fs::copy(lib_path, &tmp_target))
+        .and_then(|_| fs::rename(&tmp_target, &target));
+    match result {
+        Ok(()) => log::debug!("Stored JIT-compiled library in {}", target.display()),
+        Err(e) => {
+            log::warn!(
+                "Could not store JIT-compiled library in {}: {e}",
+                target.display()
+            );
+            let _ = fs::remove_file(&tmp_target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cache_key_depends_on_all_inputs() {
+        let key = cache_key("toml", "code", "flags").unwrap();
+        assert_eq!(key, cache_key("toml", "code", "flags").unwrap());
+        assert_ne!(key, cache_key("toml", "code2", "flags").unwrap());
+        assert_ne!(key, cache_key("toml2", "code", "flags").unwrap());
+        assert_ne!(key, cache_key("toml", "code", "flags2").unwrap());
+        // The boundaries between the parts matter.
+        assert_ne!(key, cache_key("tomlc", "ode", "flags").unwrap());
+    }
+
+    #[test]
+    fn host_cpu_contains_target_features() {
+        let host_cpu = host_cpu().unwrap();
+        assert!(host_cpu.starts_with("native"));
+        assert!(host_cpu.contains("target_feature="));
+    }
+}
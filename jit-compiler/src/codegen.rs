@@ -9,9 +9,10 @@ use powdr_ast::{
         types::{ArrayType, FunctionType, TupleType, Type, TypeScheme},
         visitor::AllChildren,
         ArrayLiteral, BinaryOperation, BinaryOperator, BlockExpression, EnumDeclaration,
-        FunctionCall, IfExpression, IndexAccess, LambdaExpression, LetStatementInsideBlock,
-        MatchArm, MatchExpression, Number, Pattern, StatementInsideBlock, TypeDeclaration,
-        UnaryOperation,
+        FieldAccess, FunctionCall, IfExpression, IndexAccess, LambdaExpression,
+        LetStatementInsideBlock, MatchArm, MatchExpression, NamedExpression, Number, Pattern,
+        StatementInsideBlock, StructDeclaration, StructExpression, TraitDeclaration,
+        TypeDeclaration, UnaryOperation,
     },
 };
 use powdr_number::{BigInt, BigUint, FieldElement, LargeInt};
@@ -44,26 +45,104 @@ impl<'a, T: FieldElement> CodeGenerator<'a, T> {
     /// After a failure, `self` can still be used to request other symbols.
     /// The code can later be retrieved via `generated_code`.
     pub fn request_symbol(&mut self, name: &str, type_args: &[Type]) -> Result<String, String> {
+        let analyzed = self.analyzed;
+        if let Some((_, Some(FunctionValueDefinition::TraitFunction(trait_decl, _)))) =
+            analyzed.definitions.get(name)
+        {
+            return self.request_trait_function(name, trait_decl, type_args);
+        }
         // For now, code generation is generic, only the reference uses the type args.
         // If that changes at some point, we need to store the type args in the symbol map as well.
-        match self.symbols.get(name) {
-            Some(Err(e)) => return Err(e.clone()),
-            Some(_) => {}
+        self.ensure_generated(name, |s| s.generate_code(name))?;
+        Ok(self.symbol_reference(name, type_args))
+    }
+
+    /// Generates the code for the implementation of the trait function `name` selected
+    /// by the type arguments and returns an expression string referencing it.
+    /// The implementation is generated as a function on its own, which is generic
+    /// if the implementation is generic.
+    fn request_trait_function(
+        &mut self,
+        name: &str,
+        trait_decl: &TraitDeclaration,
+        type_args: &[Type],
+    ) -> Result<String, String> {
+        if !type_args.iter().all(|t| t.is_concrete_type()) {
+            return Err(format!(
+                "Trait function references with generic type arguments not yet supported: {name}::<{}>",
+                type_args.iter().format(", ")
+            ));
+        }
+        let analyzed = self.analyzed;
+        // All references with concrete type arguments have been resolved during analysis.
+        let resolved = analyzed
+            .solved_impls
+            .try_resolve_trait_function(name, type_args)
+            .ok_or_else(|| {
+                format!(
+                    "No implementation found for trait function {name}::<{}>",
+                    type_args.iter().format(", ")
+                )
+            })?;
+        let Expression::LambdaExpression(_, lambda) = resolved.function else {
+            return Err(format!(
+                "Expected lambda expression for trait function implementation: {name}"
+            ));
+        };
+        let trait_impl = &analyzed.trait_impls[resolved.index];
+        let fn_name = name.rsplit_once("::").unwrap().1;
+        let type_scheme = TypeScheme {
+            vars: trait_impl.type_scheme.vars.clone(),
+            ty: trait_impl.type_of_function(trait_decl, fn_name),
+        };
+
+        let impl_symbol = format!("{name}::impl_{}", resolved.index);
+        self.ensure_generated(&impl_symbol, |s| {
+            s.try_format_function(&impl_symbol, lambda, &type_scheme)
+        })?;
+
+        let impl_type_args = if type_scheme.vars.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "::<{}>",
+                type_scheme
+                    .vars
+                    .vars()
+                    .map(|var| map_type(&resolved.type_vars[var]))
+                    .join(", ")
+            )
+        };
+        Ok(format!(
+            "Callable::Fn({}{impl_type_args})",
+            escape_symbol(&impl_symbol)
+        ))
+    }
+
+    /// Generates the code for `symbol` using `generate` unless this has already been tried.
+    fn ensure_generated(
+        &mut self,
+        symbol: &str,
+        generate: impl FnOnce(&mut Self) -> Result<String, String>,
+    ) -> Result<(), String> {
+        match self.symbols.get(symbol) {
+            Some(Err(e)) => Err(e.clone()),
+            Some(_) => Ok(()),
             None => {
-                let name = name.to_string();
-                self.symbols.insert(name.clone(), Ok(None));
-                match self.generate_code(&name) {
+                let symbol = symbol.to_string();
+                self.symbols.insert(symbol.clone(), Ok(None));
+                match generate(self) {
                     Ok(code) => {
-                        self.symbols.insert(name.clone(), Ok(Some(code)));
+                        self.symbols.insert(symbol, Ok(Some(code)));
+                        Ok(())
                     }
                     Err(err) => {
-                        self.symbols.insert(name.clone(), Err(err.clone()));
-                        return Err(err);
+                        self.symbols.insert(symbol, Err(err.clone()));
+                        Err(err)
                     }
                 }
             }
         }
-        Ok(self.symbol_reference(name, type_args))
     }
 
     /// Returns the concatenation of all successfully compiled symbols.
@@ -98,8 +177,10 @@ impl<'a, T: FieldElement> CodeGenerator<'a, T> {
                 variants,
                 ..
             })) => Ok(format!(
-                "#[derive(Clone)]\nenum {}<{type_vars}> {{\n{}\n}}\n",
+                "#[derive(Clone)]\nenum {}<{}> {{\n{}\n}}\n",
                 escape_symbol(symbol),
+                // The bounds are only relevant for the type checker.
+                type_vars.vars().join(", "),
                 variants
                     .iter()
                     .map(|v| {
@@ -112,6 +193,19 @@ impl<'a, T: FieldElement> CodeGenerator<'a, T> {
                     })
                     .join(",\n")
             )),
+            FunctionValueDefinition::TypeDeclaration(TypeDeclaration::Struct(
+                StructDeclaration {
+                    type_vars, fields, ..
+                },
+            )) => Ok(format!(
+                "#[derive(Clone)]\nstruct {}<{}> {{\n{}\n}}\n",
+                escape_symbol(symbol),
+                type_vars.vars().join(", "),
+                fields
+                    .iter()
+                    .map(|f| format!("    {}: {}", f.name, map_type(&f.ty)))
+                    .join(",\n")
+            )),
             FunctionValueDefinition::TypeConstructor(decl, _) => {
                 self.request_symbol(&decl.name, &[])?;
                 Ok(String::new())
@@ -286,6 +380,36 @@ impl<'a, T: FieldElement> CodeGenerator<'a, T> {
                     self.format_expr(index, var_height)?
                 )
             }
+            Expression::FieldAccess(_, FieldAccess { object, field }) => {
+                format!(
+                    "({}).{field}.clone()",
+                    self.format_expr(object, var_height)?
+                )
+            }
+            Expression::StructExpression(
+                _,
+                StructExpression {
+                    name: Reference::Poly(PolynomialReference { name, .. }),
+                    fields,
+                },
+            ) => {
+                // Make sure the struct itself is generated.
+                self.request_symbol(name, &[])?;
+                format!(
+                    "{} {{ {} }}",
+                    escape_symbol(name),
+                    fields
+                        .iter()
+                        .map(|NamedExpression { name, body }| {
+                            Ok(format!(
+                                "{name}: ({}).clone()",
+                                self.format_expr(body, var_height)?
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, String>>()?
+                        .join(", ")
+                )
+            }
             Expression::LambdaExpression(
                 _,
                 LambdaExpression {
@@ -726,6 +850,18 @@ mod test {
         assert_eq!(result, "");
     }
 
+    #[test]
+    fn bounded_generic_struct() {
+        let result = compile(
+            "struct Point<T: FromLiteral + Add> { x: T, y: T }",
+            &["Point"],
+        );
+        assert_eq!(
+            result,
+            "#[derive(Clone)]\nstruct Point<T> {\n    x: T,\n    y: T\n}\n"
+        );
+    }
+
     #[test]
     fn simple_fun() {
         let result = compile("let c: int -> int = |i| i;", &["c"]);
@@ -746,4 +882,23 @@ mod test {
             "
         );
     }
+
+    #[test]
+    fn generic_trait_impl() {
+        let result = compile(
+            "
+            trait Id<T> { id: T -> T }
+            impl<A> Id<(A, int)> { id: |(a, b)| (a, b + 1) }
+            let f: int -> (fe, int) = |i| Id::id((7, i));
+            ",
+            &["f"],
+        );
+        assert_eq!(
+            result,
+            "fn Id_id_impl_0<A: Clone + Send + Sync + 'static>(((a, b)): ((A, ibig::IBig))) -> (A, ibig::IBig) { ((a.clone()), (Add::add((b).clone(), (ibig::IBig::from(1_u64)).clone()).clone())) }\n\
+            \n\
+            fn f((i): (ibig::IBig)) -> (FieldElement, ibig::IBig) { (Callable::Fn(Id_id_impl_0::<FieldElement>)).call((((FieldElement::from(7_u64).clone()), (i.clone())).clone())) }\n\
+            "
+        );
+    }
 }
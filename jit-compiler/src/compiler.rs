@@ -15,7 +15,7 @@ use powdr_ast::{
 };
 use powdr_number::{FieldElement, LargeInt};
 
-use crate::{codegen::escape_symbol, CompiledPIL, FixedColFunction};
+use crate::{cache, codegen::escape_symbol, CompiledPIL, FixedColFunction};
 
 pub fn generate_glue_code<T: FieldElement>(
     symbols: &[(&str, String)],
@@ -211,20 +211,41 @@ fn cargo_toml(opt_level: Option<u32>) -> String {
 /// Compiles the given code and returns the path to the
 /// temporary directory containing the compiled library
 /// and the path to the compiled library.
+/// If a cache directory is configured (see [crate::set_cache_dir]), the library is
+/// loaded from the cache if it has been compiled before and stored in the cache otherwise.
 pub fn call_cargo(code: &str, opt_level: Option<u32>) -> Result<PathInTempDir, String> {
+    let output_asm = false;
+    let rustflags = format!(
+        "-C target-cpu=native{}",
+        if output_asm { " --emit asm" } else { "" }
+    );
+    let cargo_toml = cargo_toml(opt_level);
+    let extension = library_extension();
+
     let dir = mktemp::Temp::new_dir().unwrap();
-    fs::write(dir.join("Cargo.toml"), cargo_toml(opt_level)).unwrap();
+    let lib_path = dir.join(format!("libpowdr_jit_compiled.{extension}"));
+
+    let cache_key = cache::cache_key(&cargo_toml, code, &rustflags);
+    if let Some(cached) = cache_key
+        .as_ref()
+        .and_then(|key| cache::lookup(key, extension))
+    {
+        log::debug!("Using cached JIT-compiled library {}", cached.display());
+        // We copy the library so that every call gets its own instance,
+        // just like a freshly compiled one.
+        fs::copy(&cached, &lib_path)
+            .map_err(|e| format!("Error copying cached library {}: {e}", cached.display()))?;
+        return Ok(PathInTempDir {
+            dir,
+            path: lib_path.to_str().unwrap().to_string(),
+        });
+    }
+
+    fs::write(dir.join("Cargo.toml"), cargo_toml).unwrap();
     fs::create_dir(dir.join("src")).unwrap();
     fs::write(dir.join("src").join("lib.rs"), code).unwrap();
-    let output_asm = false;
     let out = Command::new("cargo")
-        .env(
-            "RUSTFLAGS",
-            format!(
-                "-C target-cpu=native{}",
-                if output_asm { " --emit asm" } else { "" }
-            ),
-        )
+        .env("RUSTFLAGS", rustflags)
         .arg("build")
         .arg("--release")
         .current_dir(dir.clone())
@@ -249,23 +270,31 @@ pub fn call_cargo(code: &str, opt_level: Option<u32>) -> Result<PathInTempDir, S
             .join("powdr_jit_compiled.s");
         println!("{}", fs::read_to_string(&asm_file).unwrap());
     }
-    let extension = if cfg!(target_os = "windows") {
-        "dll"
-    } else if cfg!(target_os = "macos") {
-        "dylib"
-    } else {
-        "so"
-    };
-    let lib_path = dir
+    let compiled_lib = dir
         .join("target")
         .join("release")
         .join(format!("libpowdr_jit_compiled.{extension}"));
+    fs::rename(&compiled_lib, &lib_path)
+        .map_err(|e| format!("Error moving {}: {e}", compiled_lib.display()))?;
+    if let Some(key) = cache_key {
+        cache::store(&key, extension, &lib_path);
+    }
     Ok(PathInTempDir {
         dir,
         path: lib_path.to_str().unwrap().to_string(),
     })
 }
 
+fn library_extension() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "dll"
+    } else if cfg!(target_os = "macos") {
+        "dylib"
+    } else {
+        "so"
+    }
+}
+
 /// Loads the given library and functions.
 pub fn load_library(path: &str, fixed_column_names: &[&str]) -> Result<CompiledPIL, String> {
     let library = Arc::new(
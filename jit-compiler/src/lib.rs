@@ -1,3 +1,4 @@
+mod cache;
 mod codegen;
 mod compiler;
 
@@ -14,6 +15,7 @@ use itertools::Itertools;
 use powdr_ast::analyzed::Analyzed;
 use powdr_number::FieldElement;
 
+pub use cache::{cache_dir, set_cache_dir, CACHE_DIR_ENV_VAR};
 pub use compiler::call_cargo;
 
 pub struct CompiledPIL {
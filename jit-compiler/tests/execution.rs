@@ -330,6 +330,20 @@ fn enums() {
     assert_eq!(c.call(3), 99);
 }
 
+#[test]
+fn structs() {
+    let input = r#"
+        namespace main;
+            struct Point<T: FromLiteral + Add> { x: T, y: T }
+            let sum: Point<int> -> int = |p| p.x + p.y;
+            let c: int -> int = |i| sum(Point { x: i, y: 2 });
+        "#;
+    let c = compile_fun(input, "main::c");
+
+    assert_eq!(c.call(0), 2);
+    assert_eq!(c.call(5), 7);
+}
+
 #[test]
 fn clone_locals() {
     let f = compile_fun(
@@ -0,0 +1,397 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+};
+
+use powdr_ast::{
+    analyzed::{
+        type_from_definition, Analyzed, Expression, FunctionValueDefinition, PolynomialReference,
+        PolynomialType, Reference, Symbol, SymbolKind,
+    },
+    parsed::{
+        asm::{
+            parse_absolute_path, ASMModule, AbsoluteSymbolPath, MachineStatement, Module,
+            ModuleStatement, SymbolDefinition, SymbolValue,
+        },
+        display::format_type_scheme_around_name,
+        visitor::AllChildren,
+        TypeDeclaration,
+    },
+};
+use powdr_linker::LinkerParams;
+use powdr_number::GoldilocksField;
+use powdr_parser_util::{Error, SourceRef};
+
+/// A symbol that can be the target of go-to-definition and hover requests.
+pub struct Definition {
+    pub source: SourceRef,
+    /// A short description of the symbol, e.g. its declaration with the inferred type.
+    pub description: String,
+}
+
+/// The result of analyzing a powdr-asm or powdr-pil document.
+pub struct DocumentAnalysis {
+    file_name: String,
+    /// The text that was analyzed.
+    pub text: String,
+    pub errors: Vec<Error>,
+    /// All known symbols by absolute name. Machines are keyed by their
+    /// absolute path, which starts with `::`.
+    definitions: BTreeMap<String, Definition>,
+    /// Byte ranges in the document that refer to an entry of `definitions`.
+    references: Vec<(Range<usize>, String)>,
+}
+
+impl DocumentAnalysis {
+    /// Analyzes a document. Files ending in `.pil` are treated as powdr-pil,
+    /// everything else as powdr-asm. Modules and the standard library are loaded from disk.
+    pub fn new(file_name: &str, text: String) -> Self {
+        let mut analysis = Self {
+            file_name: file_name.to_string(),
+            text,
+            errors: vec![],
+            definitions: Default::default(),
+            references: vec![],
+        };
+        let analyzed = if is_pil(file_name) {
+            analysis.analyze_pil()
+        } else {
+            analysis.analyze_asm()
+        };
+        match analyzed {
+            Ok(Some(analyzed)) => analysis.index_analyzed(&analyzed),
+            Ok(None) => {}
+            Err(errors) => analysis.errors.extend(errors),
+        }
+        analysis
+            .references
+            .sort_by_key(|(range, _)| (range.start, range.end));
+        analysis
+    }
+
+    fn analyze_pil(&self) -> Result<Option<Analyzed<GoldilocksField>>, Vec<Error>> {
+        let (parsed, errors) = powdr_parser::parse_with_recovery(Some(&self.file_name), &self.text);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        catch_panic(|| powdr_pil_analyzer::analyze_ast(parsed)).map(Some)
+    }
+
+    fn analyze_asm(&mut self) -> Result<Option<Analyzed<GoldilocksField>>, Vec<Error>> {
+        let (parsed, errors) =
+            powdr_parser::parse_asm_with_recovery(Some(&self.file_name), &self.text);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let resolved = catch_panic(|| {
+            powdr_importer::load_dependencies_and_resolve(
+                Some(self.file_name.clone().into()),
+                parsed,
+            )
+            .map_err(|e| vec![e])
+        })?;
+        self.index_machines(&resolved.main, &AbsoluteSymbolPath::default());
+
+        catch_panic(|| {
+            let analyzed = powdr_analysis::analyze(resolved).map_err(errors_without_source)?;
+            // Without a main machine, the machines to instantiate are not known,
+            // so we can only check the machines themselves.
+            let user_machines = analyzed
+                .machines()
+                .filter(|(path, _)| path.parts().next() != Some("std"))
+                .count();
+            if user_machines > 1
+                && analyzed
+                    .get_machine(&parse_absolute_path("::Main"))
+                    .is_none()
+            {
+                return Ok(None);
+            }
+            let compiled = powdr_asm_to_pil::compile::<GoldilocksField>(analyzed);
+            let graph = powdr_airgen::compile(compiled);
+            let linked = powdr_linker::link(graph, LinkerParams::default())
+                .map_err(errors_without_source)?;
+            powdr_pil_analyzer::analyze_ast(linked).map(Some)
+        })
+    }
+
+    /// Records all machines and the machine types referenced in this document.
+    fn index_machines(&mut self, module: &ASMModule, path: &AbsoluteSymbolPath) {
+        for statement in &module.statements {
+            let ModuleStatement::SymbolDefinition(SymbolDefinition { name, value, .. }) = statement
+            else {
+                continue;
+            };
+            let path = path.with_part(name);
+            match value {
+                SymbolValue::Machine(machine) => {
+                    self.definitions.insert(
+                        path.to_string(),
+                        Definition {
+                            source: machine.source.clone(),
+                            description: format!(
+                                "machine {name}{}{}",
+                                machine.params, machine.properties
+                            ),
+                        },
+                    );
+                    if !self.is_in_document(&machine.source) {
+                        continue;
+                    }
+                    let params = machine
+                        .params
+                        .0
+                        .iter()
+                        .filter_map(|p| Some((&p.source, p.ty.as_ref()?)));
+                    let submachines = machine.statements.iter().filter_map(|s| match s {
+                        MachineStatement::Submachine(source, ty, _, _) => Some((source, ty)),
+                        _ => None,
+                    });
+                    for (source, ty) in params.chain(submachines) {
+                        self.references
+                            .push((source.start..source.end, ty.to_string()));
+                    }
+                }
+                SymbolValue::Module(Module::Local(module)) => self.index_machines(module, &path),
+                SymbolValue::Module(Module::External(_)) | SymbolValue::Import(_) => {}
+            }
+        }
+    }
+
+    /// Records all symbols and the references to them in this document.
+    fn index_analyzed(&mut self, analyzed: &Analyzed<GoldilocksField>) {
+        #[allow(clippy::iter_over_hash_type)]
+        // This is deterministic because the definitions are inserted into a BTreeMap
+        // and the references are sorted afterwards.
+        for (name, (symbol, value)) in &analyzed.definitions {
+            self.definitions.insert(
+                name.clone(),
+                Definition {
+                    source: symbol.source.clone(),
+                    description: describe_symbol(symbol, value),
+                },
+            );
+            let Some(value) = value else { continue };
+            self.index_references(value.all_children());
+        }
+        #[allow(clippy::iter_over_hash_type)]
+        // This is deterministic because the definitions are inserted into a BTreeMap.
+        for (name, (symbol, _)) in &analyzed.intermediate_columns {
+            self.definitions.insert(
+                name.clone(),
+                Definition {
+                    source: symbol.source.clone(),
+                    description: describe_symbol(symbol, &None),
+                },
+            );
+        }
+        for trait_impl in &analyzed.trait_impls {
+            for function in &trait_impl.functions {
+                self.index_references(function.all_children());
+            }
+        }
+    }
+
+    fn index_references<'a>(&mut self, expressions: impl Iterator<Item = &'a Expression>) {
+        for e in expressions {
+            if let Expression::Reference(
+                source,
+                Reference::Poly(PolynomialReference { name, .. }),
+            ) = e
+            {
+                if self.is_in_document(source) {
+                    self.references
+                        .push((source.start..source.end, name.clone()));
+                }
+            }
+        }
+    }
+
+    /// Returns the definition of the symbol at the given byte offset in the document.
+    /// References found during the analysis take precedence. Otherwise, the path
+    /// around the offset is resolved relative to the namespace of the closest
+    /// preceding symbol definition.
+    pub fn definition_at(&self, offset: usize) -> Option<&Definition> {
+        self.references
+            .iter()
+            .filter(|(range, _)| range.start <= offset && offset <= range.end)
+            .min_by_key(|(range, _)| range.len())
+            .and_then(|(_, name)| self.definitions.get(name))
+            .or_else(|| self.definition_by_path(offset))
+    }
+
+    fn definition_by_path(&self, offset: usize) -> Option<&Definition> {
+        let path = path_at(&self.text, offset);
+        let path = path.strip_prefix("::").unwrap_or(path);
+        if path.is_empty() {
+            return None;
+        }
+        let mut namespace = self
+            .definitions
+            .iter()
+            .filter(|(name, d)| {
+                !name.starts_with("::")
+                    && self.is_in_document(&d.source)
+                    && d.source.start <= offset
+            })
+            .max_by_key(|(_, d)| d.source.start)
+            .and_then(|(name, _)| name.rsplit_once("::"))
+            .map_or("", |(namespace, _)| namespace);
+        loop {
+            let name = if namespace.is_empty() {
+                path.to_string()
+            } else {
+                format!("{namespace}::{path}")
+            };
+            if let Some(definition) = self.definitions.get(&name) {
+                return Some(definition);
+            }
+            if namespace.is_empty() {
+                return None;
+            }
+            namespace = namespace.rsplit_once("::").map_or("", |(parent, _)| parent);
+        }
+    }
+
+    /// Returns true if the source reference points into the analyzed document.
+    pub fn is_in_document(&self, source: &SourceRef) -> bool {
+        source
+            .file_name
+            .as_ref()
+            .is_none_or(|f| f.as_ref() == self.file_name)
+    }
+}
+
+fn is_pil(file_name: &str) -> bool {
+    file_name.ends_with(".pil")
+}
+
+/// Only parses the document, which is much faster than a full analysis.
+pub fn syntax_errors(file_name: &str, text: &str) -> Vec<Error> {
+    if is_pil(file_name) {
+        powdr_parser::parse_with_recovery(Some(file_name), text).1
+    } else {
+        powdr_parser::parse_asm_with_recovery(Some(file_name), text).1
+    }
+}
+
+/// Returns the symbol path (identifiers separated by `::`) around the byte offset.
+pub fn path_at(text: &str, offset: usize) -> &str {
+    let is_path_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == ':';
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_path_char(*c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let end = text[offset..]
+        .find(|c| !is_path_char(c))
+        .map_or(text.len(), |i| offset + i);
+    &text[start..end]
+}
+
+fn describe_symbol(symbol: &Symbol, value: &Option<FunctionValueDefinition>) -> String {
+    let name = &symbol.absolute_name;
+    let length = symbol
+        .length
+        .map(|length| format!("[{length}]"))
+        .unwrap_or_default();
+    match (&symbol.kind, value) {
+        (SymbolKind::Poly(PolynomialType::Intermediate), _) => format!("col {name}{length}"),
+        (SymbolKind::Poly(kind), _) => format!("col {kind} {name}{length}"),
+        (_, Some(FunctionValueDefinition::TypeDeclaration(TypeDeclaration::Enum(decl)))) => {
+            decl.to_string()
+        }
+        (_, Some(FunctionValueDefinition::TypeDeclaration(TypeDeclaration::Struct(decl)))) => {
+            decl.to_string()
+        }
+        (_, Some(FunctionValueDefinition::TraitDeclaration(decl))) => decl.to_string(),
+        _ => format!(
+            "let{}",
+            format_type_scheme_around_name(name, &type_from_definition(symbol, value))
+        ),
+    }
+}
+
+fn errors_without_source(errors: Vec<String>) -> Vec<Error> {
+    errors
+        .into_iter()
+        .map(|e| SourceRef::default().with_error(e))
+        .collect()
+}
+
+/// Runs `f`, turning panics into errors, since not all stages of the pipeline
+/// report errors gracefully.
+fn catch_panic<R>(f: impl FnOnce() -> Result<R, Vec<Error>>) -> Result<R, Vec<Error>> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_string());
+        Err(errors_without_source(vec![format!(
+            "Internal error: {message}"
+        )]))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn description_at(analysis: &DocumentAnalysis, needle: &str) -> String {
+        let offset = analysis.text.find(needle).unwrap();
+        analysis.definition_at(offset).unwrap().description.clone()
+    }
+
+    #[test]
+    fn pil_hover_and_definition() {
+        let text = r#"namespace N(8);
+    let double: int -> int = |x| x * 2;
+    let four = double(2);
+    col witness w;
+    w = w';
+"#;
+        let analysis = DocumentAnalysis::new("test.pil", text.to_string());
+        assert!(analysis.errors.is_empty());
+        assert_eq!(
+            description_at(&analysis, "double(2)"),
+            "let N::double: int -> int"
+        );
+        assert_eq!(description_at(&analysis, "four"), "let N::four: int");
+        // References in identities are resolved by name.
+        let offset = text.rfind("w'").unwrap();
+        let definition = analysis.definition_at(offset).unwrap();
+        assert_eq!(definition.description, "col witness N::w");
+        assert!(text[definition.source.start..].starts_with("col witness w"));
+    }
+
+    #[test]
+    fn pil_errors() {
+        let analysis = DocumentAnalysis::new("test.pil", "let x: int = y;".to_string());
+        assert_eq!(analysis.errors.len(), 1);
+        assert!(analysis.errors[0].message().contains("y"));
+        let analysis = DocumentAnalysis::new("test.pil", "let x: int = ;".to_string());
+        assert_eq!(analysis.errors.len(), 1);
+        assert_eq!(analysis.errors[0].source_ref().start, 13);
+    }
+
+    #[test]
+    fn machine_definition() {
+        let text = r#"machine Sub with degree: 8 {
+    col witness y;
+}
+
+machine Main with degree: 8 {
+    Sub sub;
+    col witness x;
+}
+"#;
+        let analysis = DocumentAnalysis::new("test.asm", text.to_string());
+        let offset = text.find("Sub sub").unwrap();
+        let definition = analysis.definition_at(offset).unwrap();
+        assert_eq!(definition.description, "machine Sub with degree: 8");
+        assert_eq!(definition.source.start, 0);
+        assert_eq!(definition.source.end, text.find("\n\n").unwrap());
+    }
+}
@@ -0,0 +1,143 @@
+use lazy_static::lazy_static;
+use powdr_ast::parsed::{
+    asm::{ASMModule, ASMProgram, Module, ModuleStatement, SymbolDefinition, SymbolValue},
+    SymbolCategory,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Module,
+    Machine,
+    Symbol(SymbolCategory),
+}
+
+/// A named item in the standard library.
+#[derive(Clone, PartialEq, Eq)]
+pub struct StdItem {
+    /// The absolute path of the item, starting with `std`.
+    pub path: Vec<String>,
+    pub kind: ItemKind,
+}
+
+impl StdItem {
+    pub fn name(&self) -> &str {
+        self.path.last().unwrap()
+    }
+}
+
+lazy_static! {
+    static ref STD_ITEMS: Vec<StdItem> = load_std_items();
+}
+
+/// Returns the items in the standard library that complete the path `prefix`,
+/// which is the text in front of the cursor, e.g. `std::math::f`.
+pub fn complete_std_path(prefix: &str) -> Vec<&'static StdItem> {
+    let prefix = prefix.strip_prefix("::").unwrap_or(prefix);
+    let Some((parent, partial)) = prefix.rsplit_once("::") else {
+        return vec![];
+    };
+    let parent = parent.split("::").collect::<Vec<_>>();
+    if parent[0] != "std" {
+        return vec![];
+    }
+    STD_ITEMS
+        .iter()
+        .filter(|item| {
+            item.path.len() == parent.len() + 1
+                && item.path.iter().zip(&parent).all(|(a, b)| a == b)
+                && item.name().starts_with(partial)
+        })
+        .collect()
+}
+
+/// Loads the standard library through the importer, which adds it
+/// to every program.
+fn load_std_items() -> Vec<StdItem> {
+    let program = match powdr_importer::load_dependencies_and_resolve(None, ASMProgram::default()) {
+        Ok(program) => program,
+        Err(e) => {
+            log::error!("Could not load the standard library: {}", e.message());
+            return vec![];
+        }
+    };
+    let mut items = vec![];
+    collect_items(&program.main, &mut vec![], &mut items);
+    items.retain(|item| item.path[0] == "std");
+    items
+}
+
+fn collect_items(module: &ASMModule, path: &mut Vec<String>, items: &mut Vec<StdItem>) {
+    for statement in &module.statements {
+        match statement {
+            ModuleStatement::SymbolDefinition(SymbolDefinition { name, value, .. }) => {
+                path.push(name.clone());
+                match value {
+                    SymbolValue::Machine(_) => items.push(StdItem {
+                        path: path.clone(),
+                        kind: ItemKind::Machine,
+                    }),
+                    SymbolValue::Module(Module::Local(m)) => {
+                        items.push(StdItem {
+                            path: path.clone(),
+                            kind: ItemKind::Module,
+                        });
+                        collect_items(m, path, items);
+                    }
+                    // Imports and external modules are not offered, the targets
+                    // of imports are offered at their original location.
+                    SymbolValue::Import(_) | SymbolValue::Module(Module::External(_)) => {}
+                }
+                path.pop();
+            }
+            ModuleStatement::PilStatement(s) => {
+                for (name, sub_name, category) in s.symbol_definition_names_and_contained() {
+                    items.push(StdItem {
+                        path: path
+                            .iter()
+                            .chain(std::iter::once(name))
+                            .chain(sub_name)
+                            .cloned()
+                            .collect(),
+                        kind: ItemKind::Symbol(category),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn complete(prefix: &str) -> Vec<&str> {
+        complete_std_path(prefix)
+            .into_iter()
+            .map(|item| item.name())
+            .collect()
+    }
+
+    #[test]
+    fn std_modules() {
+        let items = complete("std::");
+        assert!(items.contains(&"math"));
+        assert!(items.contains(&"machines"));
+        assert!(!items.contains(&"std"));
+        assert_eq!(complete("::std::mat"), vec!["math"]);
+    }
+
+    #[test]
+    fn std_symbols() {
+        let items = complete_std_path("std::utils::unwrap_or");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, vec!["std", "utils", "unwrap_or_else"]);
+        assert!(items[0].kind == ItemKind::Symbol(SymbolCategory::Value));
+        assert!(complete("std::prelude::Option::").contains(&"Some"));
+    }
+
+    #[test]
+    fn not_std() {
+        assert!(complete("math::").is_empty());
+        assert!(complete("std").is_empty());
+    }
+}
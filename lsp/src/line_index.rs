@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+use lsp_types::Position;
+
+/// Converts between byte offsets, as used in source references,
+/// and LSP positions, which count lines and UTF-16 code units.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// Byte offsets of the first character of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn range(&self, range: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    /// Returns the byte offset of a position. Positions beyond the end of
+    /// a line are mapped to the end of that line.
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut character = 0;
+        for (i, c) in self.text[line_start..].char_indices() {
+            if character >= position.character as usize || c == '\n' {
+                return line_start + i;
+            }
+            character += c.len_utf16();
+        }
+        self.text.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let text = "let x = 1;\nlet y = \"ä𝔽\";\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.position(0), Position::new(0, 0));
+        assert_eq!(index.position(4), Position::new(0, 4));
+        assert_eq!(index.position(11), Position::new(1, 0));
+        let f = text.find('𝔽').unwrap();
+        assert_eq!(index.position(f), Position::new(1, 10));
+        assert_eq!(index.position(f + '𝔽'.len_utf8()), Position::new(1, 12));
+        assert_eq!(index.position(text.len()), Position::new(2, 0));
+    }
+
+    #[test]
+    fn offsets() {
+        let text = "let x = 1;\nlet y = \"ä𝔽\";\n";
+        let index = LineIndex::new(text);
+        for offset in [0, 4, 10, 11, 20, text.find('𝔽').unwrap(), text.len()] {
+            assert_eq!(index.offset(index.position(offset)), offset);
+        }
+        assert_eq!(index.offset(Position::new(0, 100)), 10);
+        assert_eq!(index.offset(Position::new(5, 0)), text.len());
+    }
+}
@@ -0,0 +1,364 @@
+//! A language server for powdr-asm and powdr-pil, communicating via stdin and stdout.
+
+mod analysis;
+mod completion;
+mod line_index;
+
+use std::collections::HashMap;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as _},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, Url,
+};
+use powdr_ast::parsed::SymbolCategory;
+use powdr_parser_util::{Error, SourceRef};
+
+use analysis::{Definition, DocumentAnalysis};
+use completion::ItemKind;
+use line_index::LineIndex;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Sync + Send>>;
+
+fn main() -> Result<()> {
+    env_logger::init();
+
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![":".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })?;
+    connection.initialize(capabilities)?;
+    log::info!("powdr language server initialized");
+
+    Server {
+        connection,
+        documents: Default::default(),
+    }
+    .run()?;
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Document {
+    /// The current text of the document. It can differ from the analyzed text
+    /// until the document is saved.
+    text: String,
+    analysis: DocumentAnalysis,
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn run(mut self) -> Result<()> {
+        let receiver = self.connection.receiver.clone();
+        for message in receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Result<()> {
+        let response = match request.method.as_str() {
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(request, Self::definition),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, Self::hover),
+            Completion::METHOD => self.respond::<Completion>(request, Self::completion),
+            method => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request: {method}"),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    /// Answers a request using `handler`, or with an error if its parameters are invalid.
+    fn respond<R: lsp_types::request::Request>(
+        &self,
+        request: Request,
+        handler: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        let id = request.id.clone();
+        match request.extract::<R::Params>(R::METHOD) {
+            Ok((id, params)) => Response::new_ok(id, handler(self, params)),
+            Err(e) => Response::new_err(
+                id,
+                ErrorCode::InvalidParams as i32,
+                format!("Invalid request: {e:?}"),
+            ),
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = extract::<DidOpenTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                self.update(params.text_document.uri, params.text_document.text, true)
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(mut params) = extract::<DidChangeTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                // We request full synchronization, so the last change contains the whole text.
+                match params.content_changes.pop() {
+                    Some(change) => self.update(params.text_document.uri, change.text, false),
+                    None => Ok(()),
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let Some(params) = extract::<DidSaveTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                let text = params
+                    .text
+                    .or_else(|| Some(self.documents.get(&uri)?.text.clone()));
+                match text {
+                    Some(text) => self.update(uri, text, true),
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = extract::<DidCloseTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                self.publish_diagnostics(params.text_document.uri, vec![])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Updates the text of a document and publishes its diagnostics.
+    /// Since the full analysis also loads and analyzes all dependencies,
+    /// it is only run if `analyze` is set or the document is new.
+    /// Otherwise, only syntax errors are reported.
+    fn update(&mut self, uri: Url, text: String, analyze: bool) -> Result<()> {
+        let file_name = file_name(&uri);
+        let diagnostics = if analyze || !self.documents.contains_key(&uri) {
+            log::info!("Analyzing {file_name}");
+            let analysis = DocumentAnalysis::new(&file_name, text.clone());
+            let diagnostics = diagnostics(&analysis.errors, &file_name, &text);
+            self.documents
+                .insert(uri.clone(), Document { text, analysis });
+            diagnostics
+        } else {
+            let errors = analysis::syntax_errors(&file_name, &text);
+            let diagnostics = diagnostics(&errors, &file_name, &text);
+            self.documents.get_mut(&uri).unwrap().text = text;
+            diagnostics
+        };
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let document = self.documents.get(&position.text_document.uri)?;
+        let definition = definition_at(document, &position)?;
+        let location = if document.analysis.is_in_document(&definition.source) {
+            let range = LineIndex::new(&document.analysis.text)
+                .range(definition.source.start..definition.source.end);
+            Location::new(position.text_document.uri, range)
+        } else {
+            location_in_other_file(&definition.source)?
+        };
+        Some(GotoDefinitionResponse::Scalar(location))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let document = self.documents.get(&position.text_document.uri)?;
+        let definition = definition_at(document, &position)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```powdr\n{}\n```", definition.description),
+            }),
+            range: None,
+        })
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let document = self.documents.get(&position.text_document.uri)?;
+        // Completion does not need the analysis, so we can use the current text.
+        let offset = LineIndex::new(&document.text).offset(position.position);
+        let prefix = analysis::path_at(&document.text[..offset], offset);
+        let items = completion::complete_std_path(prefix)
+            .into_iter()
+            .map(|item| CompletionItem {
+                label: item.name().to_string(),
+                kind: Some(completion_item_kind(item.kind)),
+                detail: Some(item.path.join("::")),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+/// Returns the parameters of a notification. Since notifications cannot be
+/// answered, invalid parameters are only logged.
+fn extract<P: serde::de::DeserializeOwned>(notification: Notification) -> Option<P> {
+    let method = notification.method.clone();
+    notification
+        .extract(&method)
+        .map_err(|e| log::error!("Invalid notification: {e:?}"))
+        .ok()
+}
+
+/// Returns the definition at a position in the analyzed text of the document.
+fn definition_at<'a>(
+    document: &'a Document,
+    position: &TextDocumentPositionParams,
+) -> Option<&'a Definition> {
+    let offset = LineIndex::new(&document.analysis.text).offset(position.position);
+    document.analysis.definition_at(offset)
+}
+
+/// Returns the file name used in source references for the document.
+fn file_name(uri: &Url) -> String {
+    uri.to_file_path()
+        .ok()
+        .and_then(|path| path.to_str().map(|p| p.to_string()))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+fn location_in_other_file(source: &SourceRef) -> Option<Location> {
+    let path = std::fs::canonicalize(source.file_name.as_deref()?).ok()?;
+    let range = LineIndex::new(source.file_contents.as_deref()?).range(source.start..source.end);
+    Some(Location::new(Url::from_file_path(path).ok()?, range))
+}
+
+fn diagnostics(errors: &[Error], file_name: &str, text: &str) -> Vec<Diagnostic> {
+    errors
+        .iter()
+        .map(|e| diagnostic(e, file_name, text))
+        .collect()
+}
+
+fn diagnostic(error: &Error, file_name: &str, text: &str) -> Diagnostic {
+    let source = error.source_ref();
+    let (range, message) = match &source.file_name {
+        // The error is located in another file, e.g. an imported module,
+        // so we report it at the start of the document.
+        Some(other_file) if other_file.as_ref() != file_name => {
+            let location = match source.file_contents.as_deref() {
+                Some(contents) => {
+                    let position = LineIndex::new(contents).position(source.start);
+                    format!(
+                        "{other_file}:{}:{}",
+                        position.line + 1,
+                        position.character + 1
+                    )
+                }
+                None => other_file.to_string(),
+            };
+            (
+                Default::default(),
+                format!("{location}: {}", error.message()),
+            )
+        }
+        _ => (
+            LineIndex::new(text).range(source.start..source.end),
+            error.message().to_string(),
+        ),
+    };
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("powdr".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn completion_item_kind(kind: ItemKind) -> CompletionItemKind {
+    match kind {
+        ItemKind::Module => CompletionItemKind::MODULE,
+        ItemKind::Machine => CompletionItemKind::CLASS,
+        ItemKind::Symbol(SymbolCategory::Value) => CompletionItemKind::VALUE,
+        ItemKind::Symbol(SymbolCategory::Type) => CompletionItemKind::ENUM,
+        ItemKind::Symbol(SymbolCategory::TypeConstructor) => CompletionItemKind::ENUM_MEMBER,
+        ItemKind::Symbol(SymbolCategory::TraitDeclaration) => CompletionItemKind::INTERFACE,
+        ItemKind::Symbol(SymbolCategory::Struct) => CompletionItemKind::STRUCT,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn invalid_params() {
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            connection,
+            documents: Default::default(),
+        };
+        let request = Request::new(1.into(), HoverRequest::METHOD.to_string(), "no params");
+        server.handle_request(request).unwrap();
+        let Message::Response(response) = client.receiver.recv().unwrap() else {
+            panic!("Expected a response");
+        };
+        assert_eq!(response.id, 1.into());
+        assert_eq!(
+            response.error.unwrap().code,
+            ErrorCode::InvalidParams as i32
+        );
+
+        // Invalid notifications are ignored.
+        let notification = Notification::new(DidOpenTextDocument::METHOD.to_string(), "no params");
+        server.handle_notification(notification).unwrap();
+        assert!(client.receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,537 @@
+//! Canonical formatting of powdr-asm and powdr-pil source files.
+//!
+//! The items of a file (statements, machines, modules, ...) are printed
+//! using their `Display` implementations. Comments are not part of the AST,
+//! so they are collected from the source and re-attached to the item
+//! that follows them, or to the item they trail on the same line.
+//! Inside items that contain comments, only the expressions without
+//! comments are formatted, the rest of the item is kept as it is.
+
+use std::ops::Range;
+
+use powdr_ast::{
+    indent,
+    parsed::{
+        asm::{
+            ASMModule, MachineStatement, Module, ModuleStatement, SymbolDefinition, SymbolValue,
+        },
+        visitor::Children,
+        Expression, PilStatement, SourceReference,
+    },
+};
+use powdr_parser_util::{Error, SourceRef};
+
+use crate::{parse_module_with_recovery, parse_with_recovery, ParserContext, EXPRESSION_PARSER};
+
+/// Formats a PIL file. Fails if the file contains syntax errors.
+pub fn format_pil(file_name: Option<&str>, input: &str) -> Result<String, Vec<Error>> {
+    let file = parse_file(parse_with_recovery(file_name, input))?;
+    let items = file
+        .0
+        .iter()
+        .map(|statement| {
+            let indentation = match statement {
+                PilStatement::Namespace(..) => 0,
+                _ => 1,
+            };
+            Item::new(
+                input,
+                statement.source_reference(),
+                indentation,
+                text_content(statement, statement.children()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let output = Formatter::new(input).format(&items);
+    ensure_unchanged(file_name, &file, parse_with_recovery(file_name, &output))?;
+    Ok(output)
+}
+
+/// Formats an asm file. Fails if the file contains syntax errors.
+pub fn format_asm(file_name: Option<&str>, input: &str) -> Result<String, Vec<Error>> {
+    let module = parse_file(parse_module_with_recovery(file_name, input))?;
+    let items = module_items(input, &module);
+    let output = Formatter::new(input).format(&items);
+    ensure_unchanged(
+        file_name,
+        &module,
+        parse_module_with_recovery(file_name, &output),
+    )?;
+    Ok(output)
+}
+
+fn parse_file<T>((ast, errors): (T, Vec<Error>)) -> Result<T, Vec<Error>> {
+    if errors.is_empty() {
+        Ok(ast)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that the formatted source still describes the same program.
+fn ensure_unchanged<T: PartialEq>(
+    file_name: Option<&str>,
+    original: &T,
+    (formatted, errors): (T, Vec<Error>),
+) -> Result<(), Vec<Error>> {
+    if errors.is_empty() && formatted == *original {
+        Ok(())
+    } else {
+        let source = SourceRef {
+            file_name: file_name.map(|f| f.into()),
+            ..SourceRef::unknown()
+        };
+        Err(vec![source.with_error(
+            "Formatting would change the meaning of the file, leaving it as it is.".to_string(),
+        )])
+    }
+}
+
+/// An item of a source file, which is formatted on its own lines.
+struct Item<'a> {
+    /// The range of the item in the source, including a terminating semicolon.
+    range: Range<usize>,
+    /// The indentation relative to the enclosing block.
+    indentation: usize,
+    content: Content<'a>,
+}
+
+enum Content<'a> {
+    /// The formatted item and its expressions, which are formatted
+    /// on their own if the item contains comments.
+    Text(String, Vec<&'a Expression>),
+    /// An item whose body consists of other items, e.g. a machine.
+    /// The body is terminated by a closing brace.
+    Block {
+        header: String,
+        items: Vec<Item<'a>>,
+    },
+}
+
+impl<'a> Item<'a> {
+    fn new(text: &str, source: &SourceRef, indentation: usize, content: Content<'a>) -> Self {
+        // Most source references end before the terminating semicolon.
+        let end = match text[source.end..].trim_start().strip_prefix(';') {
+            Some(rest) => text.len() - rest.len(),
+            None => source.end,
+        };
+        Self {
+            range: source.start..end,
+            indentation,
+            content,
+        }
+    }
+}
+
+fn text_content<'a>(
+    item: &impl ToString,
+    expressions: impl Iterator<Item = &'a Expression>,
+) -> Content<'a> {
+    Content::Text(item.to_string(), expressions.collect())
+}
+
+fn module_items<'a>(text: &str, module: &'a ASMModule) -> Vec<Item<'a>> {
+    module
+        .statements
+        .iter()
+        .map(|statement| match statement {
+            ModuleStatement::SymbolDefinition(definition) => {
+                symbol_definition_item(text, definition)
+            }
+            ModuleStatement::PilStatement(s) => {
+                Item::new(text, s.source_reference(), 0, text_content(s, s.children()))
+            }
+        })
+        .collect()
+}
+
+fn symbol_definition_item<'a>(text: &str, definition: &'a SymbolDefinition) -> Item<'a> {
+    let SymbolDefinition {
+        source,
+        name,
+        value,
+    } = definition;
+    let content = match value {
+        SymbolValue::Machine(machine) => Content::Block {
+            header: format!("machine {name}{}{} {{", machine.params, machine.properties),
+            items: machine
+                .statements
+                .iter()
+                .map(|s| machine_statement_item(text, s))
+                .collect(),
+        },
+        SymbolValue::Module(Module::Local(module)) => Content::Block {
+            header: format!("mod {name} {{"),
+            items: module_items(text, module),
+        },
+        SymbolValue::Module(Module::External(_)) | SymbolValue::Import(_) => {
+            Content::Text(definition.to_string(), vec![])
+        }
+    };
+    Item::new(text, source, 0, content)
+}
+
+fn machine_statement_item<'a>(text: &str, statement: &'a MachineStatement) -> Item<'a> {
+    let content = match statement {
+        MachineStatement::FunctionDeclaration(_, name, params, statements) => Content::Block {
+            header: format!("function {name}{} {{", params.prepend_space_if_non_empty()),
+            items: statements
+                .iter()
+                .map(|s| Item::new(text, s.source_reference(), 0, text_content(s, s.children())))
+                .collect(),
+        },
+        MachineStatement::Pil(_, s) => text_content(statement, s.children()),
+        MachineStatement::Submachine(_, _, _, args) => text_content(statement, args.iter()),
+        MachineStatement::InstructionDeclaration(_, _, instruction) => {
+            text_content(statement, instruction.children())
+        }
+        MachineStatement::LinkDeclaration(_, link) => text_content(statement, link.children()),
+        MachineStatement::RegisterDeclaration(..) | MachineStatement::OperationDeclaration(..) => {
+            Content::Text(statement.to_string(), vec![])
+        }
+    };
+    Item::new(text, statement.source_reference(), 0, content)
+}
+
+struct Formatter<'a> {
+    text: &'a str,
+    comments: Vec<Range<usize>>,
+    /// The index of the first comment that has not been printed yet.
+    next_comment: usize,
+    output: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            comments: comments(text),
+            next_comment: 0,
+            output: String::new(),
+        }
+    }
+
+    fn format(mut self, items: &[Item]) -> String {
+        self.format_items(items, self.text.len(), 0);
+        self.output
+    }
+
+    /// Formats `items` and all comments before `end`.
+    fn format_items(&mut self, items: &[Item], end: usize, indentation: usize) {
+        let mut previous_end = None;
+        for item in items {
+            let indentation = indentation + item.indentation;
+            self.format_comments(item.range.start, indentation, &mut previous_end);
+            self.separate(previous_end, item.range.start);
+            match &item.content {
+                Content::Text(text, expressions) => {
+                    if self.has_comment_before(item.range.end) {
+                        // We cannot tell where the comments belong in the formatted item,
+                        // so we only format its expressions that do not contain comments.
+                        let partially_formatted =
+                            self.format_partially(item.range.clone(), expressions.iter().copied());
+                        let partially_formatted =
+                            self.reindent(item.range.start, &partially_formatted, indentation);
+                        self.output.push_str(&partially_formatted);
+                        while self.has_comment_before(item.range.end) {
+                            self.next_comment += 1;
+                        }
+                    } else {
+                        self.output.push_str(&indent(text, indentation));
+                    }
+                }
+                Content::Block { header, items } => {
+                    self.output.push_str(&indent(header, indentation));
+                    self.output.push('\n');
+                    // The range of a block ends after its closing brace.
+                    self.format_items(items, item.range.end - 1, indentation + 1);
+                    self.output.push_str(&indent("}", indentation));
+                }
+            }
+            previous_end = Some(self.format_trailing_comment(item.range.end));
+            self.output.push('\n');
+        }
+        self.format_comments(end, indentation, &mut previous_end);
+    }
+
+    /// Formats all comments before `end`, each on its own lines.
+    fn format_comments(
+        &mut self,
+        end: usize,
+        indentation: usize,
+        previous_end: &mut Option<usize>,
+    ) {
+        while self.has_comment_before(end) {
+            let comment = self.comments[self.next_comment].clone();
+            self.separate(*previous_end, comment.start);
+            let comment_text =
+                self.reindent(comment.start, &self.text[comment.clone()], indentation);
+            self.output.push_str(&comment_text);
+            self.output.push('\n');
+            *previous_end = Some(comment.end);
+            self.next_comment += 1;
+        }
+    }
+
+    /// Appends a comment that follows on the same line as an item ending at `end`
+    /// and returns the end of the printed text.
+    fn format_trailing_comment(&mut self, end: usize) -> usize {
+        match self.comments.get(self.next_comment) {
+            Some(comment)
+                if self.text[end..comment.start].trim().is_empty()
+                    && !self.text[end..comment.end].contains('\n') =>
+            {
+                self.output.push(' ');
+                self.output.push_str(&self.text[comment.clone()]);
+                self.next_comment += 1;
+                comment.end
+            }
+            _ => end,
+        }
+    }
+
+    fn has_comment_before(&self, end: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.start < end)
+    }
+
+    fn contains_comment(&self, range: &Range<usize>) -> bool {
+        self.comments
+            .iter()
+            .any(|comment| comment.start < range.end && range.start < comment.end)
+    }
+
+    /// Returns the source in `range`, where the given expressions are formatted
+    /// if they do not contain comments. Otherwise, their sub-expressions are
+    /// formatted in the same way.
+    fn format_partially<'b>(
+        &self,
+        range: Range<usize>,
+        expressions: impl Iterator<Item = &'b Expression>,
+    ) -> String {
+        let mut expressions = expressions.collect::<Vec<_>>();
+        expressions.sort_by_key(|e| e.source_reference().start);
+        let mut output = String::new();
+        let mut position = range.start;
+        for expression in expressions {
+            let source = expression.source_reference();
+            if source.start < position || source.start >= source.end || source.end > range.end {
+                // Not a proper part of the source in `range`.
+                continue;
+            }
+            let expression_range = source.start..source.end;
+            output.push_str(&self.text[position..expression_range.start]);
+            if self.contains_comment(&expression_range) {
+                output.push_str(&self.format_partially(expression_range, expression.children()));
+            } else {
+                output.push_str(&self.format_expression(expression_range, expression));
+            }
+            position = source.end;
+        }
+        output.push_str(&self.text[position..range.end]);
+        output
+    }
+
+    /// Formats an expression that does not contain comments.
+    /// The source is kept if the source reference does not exactly
+    /// cover the expression or if formatting would change it.
+    fn format_expression(&self, range: Range<usize>, expression: &Expression) -> String {
+        let original = &self.text[range.clone()];
+        let formatted = expression.to_string();
+        if !parses_to(original, expression) || !parses_to(&formatted, expression) {
+            return original.to_string();
+        }
+        // Subsequent lines are indented relative to the line the expression starts on.
+        let line_start = self.text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.text[line_start..range.start];
+        let line_indentation = &line[..line.len() - line.trim_start().len()];
+        formatted.replace('\n', &format!("\n{line_indentation}"))
+    }
+
+    /// Keeps a single blank line between two items if there was
+    /// at least one in the source.
+    fn separate(&mut self, previous_end: Option<usize>, start: usize) {
+        if let Some(previous_end) = previous_end {
+            if self.text[previous_end..start].matches('\n').count() >= 2 {
+                self.output.push('\n');
+            }
+        }
+    }
+
+    /// Returns `text`, which starts at `start` in the source, moved to the given indentation.
+    /// The indentation of subsequent lines relative to the first line is kept.
+    fn reindent(&self, start: usize, text: &str, indentation: usize) -> String {
+        let line_start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let column = start - line_start;
+        let target = indentation * 4;
+        text.split('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                let content = line.trim();
+                if i == 0 {
+                    format!("{}{content}", " ".repeat(target))
+                } else if content.is_empty() {
+                    String::new()
+                } else {
+                    let whitespace = line.len() - line.trim_start().len();
+                    let whitespace = (whitespace + target).saturating_sub(column);
+                    format!("{}{content}", " ".repeat(whitespace))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Returns true if `text` is parsed as the expression `expected`.
+fn parses_to(text: &str, expected: &Expression) -> bool {
+    let ctx = ParserContext::new(None, text);
+    EXPRESSION_PARSER
+        .parse(&ctx, text)
+        .is_ok_and(|expression| expression == *expected)
+        && ctx.errors.borrow().is_empty()
+}
+
+/// Returns the ranges of all comments in the source.
+/// Line comments do not include the terminating newline.
+fn comments(text: &str) -> Vec<Range<usize>> {
+    let mut comments = vec![];
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => {
+                i += 1;
+                while i < bytes.len() && !matches!(bytes[i], b'"' | b'\n') {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            (b'/', Some(b'/')) => {
+                let end = text[i..].find(['\n', '\r']).map_or(text.len(), |e| i + e);
+                comments.push(i..end);
+                i = end;
+            }
+            (b'/', Some(b'*')) => {
+                let end = text[i + 2..]
+                    .find("*/")
+                    .map_or(text.len(), |e| i + 2 + e + 2);
+                comments.push(i..end);
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    comments
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn assert_formatted(
+        format: fn(Option<&str>, &str) -> Result<String, Vec<Error>>,
+        input: &str,
+        expected: &str,
+    ) {
+        let formatted = format(None, input).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(None, &formatted).unwrap(), expected);
+    }
+
+    #[test]
+    fn pil_comments() {
+        let input = r#"// The main namespace.
+namespace main(8);
+/// The only witness column.
+pol commit x; // trailing
+
+
+    // Blank lines are collapsed.
+let f = |a| {
+        // inside
+        a   + 1
+    };
+x   =  x';
+/* end */
+"#;
+        let expected = r#"// The main namespace.
+namespace main(8);
+    /// The only witness column.
+    pol commit x; // trailing
+
+    // Blank lines are collapsed.
+    let f = |a| {
+            // inside
+            a + 1
+        };
+    x = x';
+/* end */
+"#;
+        assert_formatted(format_pil, input, expected);
+    }
+
+    #[test]
+    fn asm_comments() {
+        let input = r#"
+// The machine.
+machine Main with degree: 8 {
+  // Registers
+  reg pc[@pc];
+  reg X[<=]; /* input */
+
+  pol commit x;
+  function main {
+      // Start.
+      A <=X=   1;
+    return;
+  }
+  // Before the end.
+}
+
+
+use std::utils::unwrap_or_else;
+mod inner {
+    // Nothing here.
+}
+"#;
+        let expected = r#"// The machine.
+machine Main with degree: 8 {
+    // Registers
+    reg pc[@pc];
+    reg X[<=]; /* input */
+
+    pol commit x;
+    function main {
+        // Start.
+        A <=X= 1;
+        return;
+    }
+    // Before the end.
+}
+
+use std::utils::unwrap_or_else;
+mod inner {
+    // Nothing here.
+}
+"#;
+        assert_formatted(format_asm, input, expected);
+    }
+
+    #[test]
+    fn comment_markers_in_strings() {
+        let input = "let s = \"// /*\"; // comment\n";
+        let expected = "    let s = \"// /*\"; // comment\n";
+        assert_formatted(format_pil, input, expected);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            format_pil(None, "let x = ;\nlet y = ;").unwrap_err().len(),
+            2
+        );
+    }
+}
@@ -4,11 +4,15 @@ use lalrpop_util::*;
 use powdr_ast::parsed::{
     asm::ASMProgram,
     types::{Type, TypeBounds, TypeScheme},
-    Expression, SourceReference,
+    Expression, FieldAccess, NamespacedPolynomialReference, Part, SourceReference, SymbolPath,
 };
 use powdr_parser_util::{handle_parse_error, Error, SourceRef};
 
-use std::sync::Arc;
+use std::{cell::RefCell, sync::Arc};
+
+mod format;
+
+pub use format::{format_asm, format_pil};
 
 lalrpop_mod!(
     #[allow(clippy::all)]
@@ -20,6 +24,8 @@ lalrpop_mod!(
 pub struct ParserContext {
     file_name: Option<Arc<str>>,
     file_contents: Option<Arc<str>>,
+    /// Syntax errors the parser recovered from.
+    errors: RefCell<Vec<Error>>,
 }
 
 impl ParserContext {
@@ -27,7 +33,32 @@ impl ParserContext {
         Self {
             file_name: file_name.map(|s| s.into()),
             file_contents: Some(input.into()),
+            errors: Default::default(),
+        }
+    }
+
+    /// Records a syntax error the parser recovered from - used in the grammar.
+    pub fn report_error(&self, error: ErrorRecovery<usize, lexer::Token, Error>) {
+        let error = handle_parse_error(
+            error.error,
+            self.file_name.as_deref(),
+            self.file_contents.as_deref().unwrap_or_default(),
+        );
+        self.errors.borrow_mut().push(error);
+    }
+
+    /// Returns the errors the parser recovered from, followed by the error
+    /// it could not recover from, if any.
+    fn into_errors<'a>(
+        self,
+        result: Result<(), ParseError<usize, lexer::Token<'a>, Error>>,
+        input: &'a str,
+    ) -> Vec<Error> {
+        let mut errors = self.errors.into_inner();
+        if let Err(err) = result {
+            errors.push(handle_parse_error(err, self.file_name.as_deref(), input));
         }
+        errors
     }
 
     pub fn source_ref(&self, start: usize, end: usize) -> SourceRef {
@@ -49,6 +80,26 @@ impl ParserContext {
         *expr.source_reference_mut() = self.source_ref(start, end);
         Box::new(expr)
     }
+
+    /// Creates the expression for `object.field`. If `object` is a plain identifier,
+    /// this is a reference to the namespaced symbol `object.field` and it is only
+    /// turned into a field access once it is known that `object` is a value.
+    pub fn field_access(
+        &self,
+        object: Box<Expression>,
+        field: String,
+        start: usize,
+        end: usize,
+    ) -> Box<Expression> {
+        match object.try_to_identifier() {
+            Some(namespace) => {
+                let path = [namespace.clone(), field].into_iter().map(Part::Named);
+                let reference: NamespacedPolynomialReference = SymbolPath::from_parts(path).into();
+                self.to_expr_with_source_ref(reference, start, end)
+            }
+            None => self.to_expr_with_source_ref(FieldAccess { object, field }, start, end),
+        }
+    }
 }
 
 lazy_static::lazy_static! {
@@ -56,13 +107,25 @@ lazy_static::lazy_static! {
     static ref ASM_MODULE_PARSER: powdr::ASMModuleParser = powdr::ASMModuleParser::new();
     static ref TYPE_PARSER: powdr::TypeExprParser = powdr::TypeExprParser::new();
     static ref TYPE_VAR_BOUNDS_PARSER: powdr::TypeVarBoundsParser = powdr::TypeVarBoundsParser::new();
+    static ref EXPRESSION_PARSER: powdr::ExpressionParser = powdr::ExpressionParser::new();
 }
 
 pub fn parse(file_name: Option<&str>, input: &str) -> Result<powdr_ast::parsed::PILFile, Error> {
+    first_error(parse_with_recovery(file_name, input))
+}
+
+/// Parses a PIL file and continues after syntax errors.
+/// Returns the statements that could be parsed and all errors.
+pub fn parse_with_recovery(
+    file_name: Option<&str>,
+    input: &str,
+) -> (powdr_ast::parsed::PILFile, Vec<Error>) {
     let ctx = ParserContext::new(file_name, input);
-    PIL_FILE_PARSER
-        .parse(&ctx, input)
-        .map_err(|err| handle_parse_error(err, file_name, input))
+    let (file, result) = match PIL_FILE_PARSER.parse(&ctx, input) {
+        Ok(file) => (file, Ok(())),
+        Err(err) => (Default::default(), Err(err)),
+    };
+    (file, ctx.into_errors(result, input))
 }
 
 pub fn parse_asm(
@@ -72,14 +135,40 @@ pub fn parse_asm(
     parse_module(file_name, input).map(|main| ASMProgram { main })
 }
 
+/// Parses an asm file and continues after syntax errors.
+/// Returns the statements that could be parsed and all errors.
+pub fn parse_asm_with_recovery(
+    file_name: Option<&str>,
+    input: &str,
+) -> (powdr_ast::parsed::asm::ASMProgram, Vec<Error>) {
+    let (main, errors) = parse_module_with_recovery(file_name, input);
+    (ASMProgram { main }, errors)
+}
+
 pub fn parse_module(
     file_name: Option<&str>,
     input: &str,
 ) -> Result<powdr_ast::parsed::asm::ASMModule, Error> {
+    first_error(parse_module_with_recovery(file_name, input))
+}
+
+pub fn parse_module_with_recovery(
+    file_name: Option<&str>,
+    input: &str,
+) -> (powdr_ast::parsed::asm::ASMModule, Vec<Error>) {
     let ctx = ParserContext::new(file_name, input);
-    ASM_MODULE_PARSER
-        .parse(&ctx, input)
-        .map_err(|err| handle_parse_error(err, file_name, input))
+    let (module, result) = match ASM_MODULE_PARSER.parse(&ctx, input) {
+        Ok(module) => (module, Ok(())),
+        Err(err) => (Default::default(), Err(err)),
+    };
+    (module, ctx.into_errors(result, input))
+}
+
+fn first_error<T>((ast, errors): (T, Vec<Error>)) -> Result<T, Error> {
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(ast),
+    }
 }
 
 pub fn parse_type(input: &str) -> Result<Type<powdr_ast::parsed::Expression>, Error> {
@@ -134,7 +223,10 @@ pub fn unescape_string(s: &str) -> String {
 #[cfg(test)]
 mod test {
     use super::*;
-    use powdr_ast::parsed::{PILFile, PilStatement, PolynomialName};
+    use powdr_ast::parsed::{
+        asm::{ModuleStatement, SymbolDefinition, SymbolValue},
+        PILFile, PilStatement, PolynomialName,
+    };
     use powdr_parser_util::UnwrapErrToStderr;
     use pretty_assertions::assert_eq;
     use similar::TextDiff;
@@ -289,6 +381,30 @@ mod test {
 
     use crate::parse;
 
+    #[test]
+    /// Test that formatting the test files succeeds and that formatting the result does not change it
+    fn format_idempotent() {
+        let formatters: [(&str, fn(Option<&str>, &str) -> Result<String, Vec<Error>>); 2] =
+            [("asm", format_asm), ("pil", format_pil)];
+        for (ext, format) in formatters {
+            let basedir = std::path::Path::new("../test_data/").to_owned();
+            for (file, orig_string) in find_files_with_ext(basedir, ext.into()) {
+                let format_file = |input: &str| {
+                    format(Some(&file), input).unwrap_or_else(|errors| {
+                        errors.iter().for_each(|e| e.output_to_stderr());
+                        panic!("could not format file: {file}");
+                    })
+                };
+                let formatted = format_file(&orig_string);
+                assert_eq!(
+                    formatted,
+                    format_file(&formatted),
+                    "not idempotent for file: {file}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn reparse() {
         let input = r#"
@@ -695,4 +811,78 @@ namespace N(2);
         let printed = format!("{}", parse(Some("input"), input).unwrap_err_to_stderr());
         assert_eq!(expected.trim(), printed.trim());
     }
+
+    #[test]
+    fn recover_from_errors() {
+        let input = r#"
+    let x: int = ;
+    let y: int = 2;
+    let z = (1 +;
+    let w = 3;"#;
+        let (parsed, errors) = parse_with_recovery(Some("input"), input);
+        assert_eq!(parsed.to_string(), "    let y: int = 2;\n    let w = 3;\n");
+        assert_eq!(errors.len(), 2);
+        let source_ref = errors[0].source_ref();
+        assert_eq!(&input[source_ref.start..source_ref.end], ";");
+        assert!(errors[0].message().contains("Expected one of"));
+        assert_eq!(errors[1].source_ref().start, input.find("+;").unwrap() + 1);
+        // Only the first error is returned without recovery.
+        let error = parse(Some("input"), input).unwrap_err();
+        assert_eq!(error.source_ref().start, source_ref.start);
+    }
+
+    #[test]
+    fn recover_from_error_at_end_of_input() {
+        let input = "let x = 1;\nlet y = ";
+        let (parsed, errors) = parse_with_recovery(Some("input"), input);
+        assert_eq!(parsed.to_string(), "    let x = 1;\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message().starts_with("Unrecognized EOF"));
+    }
+
+    #[test]
+    fn recover_from_errors_in_machine() {
+        let input = r#"
+machine Main {
+    reg pc[@pc];
+    col witness x
+    col witness y;
+    x = ;
+    reg A;
+}
+
+machine Other {
+"#;
+        let (parsed, errors) = parse_asm_with_recovery(Some("input"), input);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[0].source_ref().start,
+            input.find("col witness y").unwrap()
+        );
+        assert_eq!(parsed.main.statements.len(), 1);
+        let ModuleStatement::SymbolDefinition(SymbolDefinition {
+            name,
+            value: SymbolValue::Machine(machine),
+            ..
+        }) = &parsed.main.statements[0]
+        else {
+            panic!()
+        };
+        assert_eq!(name, "Main");
+        assert_eq!(machine.statements.len(), 2);
+    }
+
+    #[test]
+    fn field_access() {
+        let input = r#"
+    let x = f(a).b + (p + q).c[2].d;
+    let y = A{ b: 1 }.b;
+    let z = a.b;"#;
+        let expected = r#"
+    let x = f(a).b + (p + q).c[2].d;
+    let y = A{ b: 1 }.b;
+    let z = a::b;"#;
+        let printed = format!("{}", parse(Some("input"), input).unwrap_err_to_stderr());
+        assert_eq!(expected.trim(), printed.trim());
+    }
 }
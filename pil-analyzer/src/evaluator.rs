@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{self, Display},
     sync::Arc,
 };
@@ -17,14 +17,16 @@ use powdr_ast::{
         display::quote,
         types::{ArrayType, Type, TypeScheme},
         ArrayLiteral, BinaryOperation, BinaryOperator, BlockExpression, EnumDeclaration,
-        FunctionCall, IfExpression, IndexAccess, LambdaExpression, LetStatementInsideBlock,
-        MatchArm, MatchExpression, Number, Pattern, StatementInsideBlock, UnaryOperation,
-        UnaryOperator,
+        FieldAccess, FunctionCall, IfExpression, IndexAccess, LambdaExpression,
+        LetStatementInsideBlock, MatchArm, MatchExpression, NamedExpression, Number, Pattern,
+        StatementInsideBlock, StructExpression, UnaryOperation, UnaryOperator,
     },
 };
 use powdr_number::{BigInt, BigUint, FieldElement, LargeInt};
 use powdr_parser_util::SourceRef;
 
+use crate::traits_resolver::resolve_trait_function;
+
 /// Evaluates an expression given a hash map of definitions.
 pub fn evaluate_expression<'a, T: FieldElement>(
     expr: &'a Expression,
@@ -147,6 +149,7 @@ pub enum Value<'a, T> {
     Closure(Closure<'a, T>),
     TypeConstructor(TypeConstructorValue<'a>),
     Enum(EnumValue<'a, T>),
+    Struct(StructValue<'a, T>),
     BuiltinFunction(BuiltinFunction),
     Expression(AlgebraicExpression<T>),
 }
@@ -224,6 +227,7 @@ impl<T: FieldElement> Value<'_, T> {
             Value::Closure(c) => c.type_formatted(),
             Value::TypeConstructor(tc) => tc.type_formatted(),
             Value::Enum(enum_val) => enum_val.type_formatted(),
+            Value::Struct(struct_val) => struct_val.type_formatted(),
             Value::BuiltinFunction(b) => format!("builtin_{b:?}"),
             Value::Expression(_) => "expr".to_string(),
         }
@@ -344,6 +348,34 @@ impl<T: Display> Display for EnumValue<'_, T> {
     }
 }
 
+/// A value of a struct type, i.e. the values of its fields.
+#[derive(Clone, Debug)]
+pub struct StructValue<'a, T> {
+    /// The absolute name of the struct.
+    pub name: &'a str,
+    pub fields: BTreeMap<&'a str, Arc<Value<'a, T>>>,
+}
+
+impl<T: Display> StructValue<'_, T> {
+    pub fn type_formatted(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+impl<T: Display> Display for StructValue<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}"))
+                .format(", ")
+        )
+    }
+}
+
 /// An enum type constructor value, i.e. the value arising from referencing an
 /// enum variant that takes data.
 #[derive(Clone, Debug)]
@@ -495,6 +527,7 @@ impl<T: Display> Display for Value<'_, T> {
             Value::Closure(closure) => write!(f, "{closure}"),
             Value::TypeConstructor(tc) => write!(f, "{tc}"),
             Value::Enum(enum_value) => write!(f, "{enum_value}"),
+            Value::Struct(struct_value) => write!(f, "{struct_value}"),
             Value::BuiltinFunction(b) => write!(f, "{b:?}"),
             Value::Expression(e) => write!(f, "{e}"),
         }
@@ -596,15 +629,20 @@ impl<'a> Definitions<'a> {
                 }
                 Some(FunctionValueDefinition::TraitFunction(_, _)) => {
                     let type_args = type_args.as_ref().unwrap();
-                    let Expression::LambdaExpression(_, lambda) =
-                        solved_impls.resolve_trait_function(&name, type_args)
-                    else {
+                    let resolved = resolve_trait_function(solved_impls, &name, type_args)
+                        .ok_or_else(|| {
+                            EvalError::SymbolNotFound(format!(
+                                "Could not find an implementation for the trait function {name}::<{}>",
+                                type_args.iter().format(", ")
+                            ))
+                        })?;
+                    let Expression::LambdaExpression(_, lambda) = resolved.function else {
                         unreachable!()
                     };
                     let closure = Closure {
                         lambda,
                         environment: vec![],
-                        type_args: HashMap::new(),
+                        type_args: resolved.type_vars,
                     };
                     Value::Closure(closure).into()
                 }
@@ -927,6 +965,10 @@ impl<'a, 'b, T: FieldElement, S: SymbolLookup<'a, T>> Evaluator<'a, 'b, T, S> {
                 self.op_stack.push(Operation::Expand(index));
                 self.expand(array)?;
             }
+            Expression::FieldAccess(_, FieldAccess { object, .. }) => {
+                self.op_stack.push(Operation::Combine(expr));
+                self.expand(object)?;
+            }
             Expression::FunctionCall(
                 _,
                 FunctionCall {
@@ -976,8 +1018,14 @@ impl<'a, 'b, T: FieldElement, S: SymbolLookup<'a, T>> Evaluator<'a, 'b, T, S> {
             Expression::FreeInput(_, _) => Err(EvalError::Unsupported(
                 "Cannot evaluate free input.".to_string(),
             ))?,
-            Expression::StructExpression(_, _) => {
-                unimplemented!("Struct expressions are not yet supported.")
+            Expression::StructExpression(_, StructExpression { fields, .. }) => {
+                self.op_stack.push(Operation::Combine(expr));
+                self.op_stack.extend(
+                    fields
+                        .iter()
+                        .rev()
+                        .map(|f| Operation::Expand(f.body.as_ref())),
+                );
             }
         };
         Ok(())
@@ -1107,6 +1155,42 @@ impl<'a, 'b, T: FieldElement, S: SymbolLookup<'a, T>> Evaluator<'a, 'b, T, S> {
                     )))?,
                 }
             }
+            Expression::FieldAccess(_, FieldAccess { field, .. }) => {
+                let object = self.value_stack.pop().unwrap();
+                let Value::Struct(struct_value) = object.as_ref() else {
+                    return Err(EvalError::TypeError(format!(
+                        "Expected struct to access field {field} but got {object}: {}",
+                        object.type_formatted()
+                    )));
+                };
+                struct_value
+                    .fields
+                    .get(field.as_str())
+                    .ok_or_else(|| {
+                        EvalError::TypeError(format!(
+                            "Struct {} has no field named {field}",
+                            struct_value.name
+                        ))
+                    })?
+                    .clone()
+            }
+            Expression::StructExpression(_, StructExpression { name, fields }) => {
+                let Reference::Poly(name) = name else {
+                    unreachable!()
+                };
+                let values = self
+                    .value_stack
+                    .split_off(self.value_stack.len() - fields.len());
+                Value::Struct(StructValue {
+                    name: &name.name,
+                    fields: fields
+                        .iter()
+                        .map(|NamedExpression { name, .. }| name.as_str())
+                        .zip(values)
+                        .collect(),
+                })
+                .into()
+            }
             Expression::FunctionCall(_, FunctionCall { arguments, .. }) => {
                 let arguments = self
                     .value_stack
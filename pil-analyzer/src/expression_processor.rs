@@ -2,10 +2,13 @@ use core::panic;
 use powdr_ast::{
     analyzed::{Expression, PolynomialReference, Reference},
     parsed::{
-        self, asm::SymbolPath, types::Type, ArrayExpression, ArrayLiteral, BinaryOperation,
-        BlockExpression, IfExpression, LambdaExpression, LetStatementInsideBlock, MatchArm,
-        MatchExpression, NamedExpression, NamespacedPolynomialReference, Number, Pattern,
-        SourceReference, StatementInsideBlock, StructExpression, SymbolCategory, UnaryOperation,
+        self,
+        asm::{Part, SymbolPath},
+        types::Type,
+        ArrayExpression, ArrayLiteral, BinaryOperation, BlockExpression, IfExpression,
+        LambdaExpression, LetStatementInsideBlock, MatchArm, MatchExpression, NamedExpression,
+        NamespacedPolynomialReference, Number, Pattern, SourceReference, StatementInsideBlock,
+        StructExpression, SymbolCategory, UnaryOperation,
     },
 };
 
@@ -71,6 +74,16 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
         use parsed::Expression as PExpression;
         Ok(match expr {
             PExpression::Reference(src, poly) => {
+                if let Some((object, field)) = self.try_split_field_access(&poly.path) {
+                    let object = PExpression::Reference(src.clone(), object.into());
+                    return self.process_expression(PExpression::FieldAccess(
+                        src,
+                        parsed::FieldAccess {
+                            object: Box::new(object),
+                            field,
+                        },
+                    ));
+                }
                 let reference = self
                     .process_reference(poly)
                     .map_err(|e| src.with_error(e))?;
@@ -124,6 +137,13 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
                     index: Box::new(self.process_expression(*index_access.index)?),
                 },
             ),
+            PExpression::FieldAccess(src, field_access) => Expression::FieldAccess(
+                src,
+                parsed::FieldAccess {
+                    object: Box::new(self.process_expression(*field_access.object)?),
+                    field: field_access.field,
+                },
+            ),
             PExpression::FunctionCall(src, c) => Expression::FunctionCall(
                 src,
                 parsed::FunctionCall {
@@ -304,6 +324,26 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
         ))
     }
 
+    /// The parser reads `a.b` as the path `a::b`. If `a` is a local variable or if
+    /// `a::b` does not resolve but `a` does, this is an access to the field `b` of
+    /// the value `a` and the function returns `a` and `b`.
+    fn try_split_field_access(&self, path: &SymbolPath) -> Option<(SymbolPath, String)> {
+        let mut object = path.clone();
+        let Part::Named(field) = object.pop()? else {
+            return None;
+        };
+        let is_local = match object.parts().next()? {
+            Part::Named(name) => self.local_variables.contains_key(name),
+            Part::Super => false,
+        };
+        let is_value = || {
+            self.driver.try_resolve_ref(path).is_none()
+                && (self.driver.try_resolve_ref(&object).is_some()
+                    || self.try_split_field_access(&object).is_some())
+        };
+        (is_local || is_value()).then_some((object, field))
+    }
+
     fn process_reference(
         &mut self,
         reference: NamespacedPolynomialReference,
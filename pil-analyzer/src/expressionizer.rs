@@ -15,7 +15,8 @@ use powdr_ast::parsed::{
     types::Type,
     visitor::{AllChildren, ExpressionVisitable},
     ArrayLiteral, BinaryOperation, BlockExpression, FunctionCall, FunctionKind, IndexAccess,
-    LambdaExpression, LetStatementInsideBlock, Number, Pattern, TypedExpression, UnaryOperation,
+    LambdaExpression, LetStatementInsideBlock, NamedExpression, Number, Pattern, StructExpression,
+    TypedExpression, UnaryOperation,
 };
 use powdr_number::{BigUint, FieldElement};
 use powdr_parser_util::SourceRef;
@@ -224,6 +225,24 @@ impl Expressionizer<'_> {
                     .into(),
                 }
             }
+            Value::Struct(struct_value) => StructExpression {
+                name: Reference::Poly(PolynomialReference {
+                    name: struct_value.name.to_string(),
+                    // We do not know the type args here.
+                    type_args: None,
+                }),
+                fields: struct_value
+                    .fields
+                    .iter()
+                    .map(|(name, value)| -> Result<_, EvalError> {
+                        Ok(NamedExpression {
+                            name: name.to_string(),
+                            body: Box::new(self.try_value_to_expression(value)?),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+            }
+            .into(),
             Value::BuiltinFunction(_) => {
                 return Err(EvalError::TypeError(
                     "Converting builtin functions to expressions not supported.".to_string(),
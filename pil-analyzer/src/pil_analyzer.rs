@@ -11,11 +11,11 @@ use itertools::Itertools;
 use powdr_ast::parsed::asm::{
     parse_absolute_path, AbsoluteSymbolPath, ModuleStatement, SymbolPath,
 };
-use powdr_ast::parsed::types::Type;
+use powdr_ast::parsed::types::{Type, TypeScheme};
 use powdr_ast::parsed::visitor::{AllChildren, Children};
 use powdr_ast::parsed::{
     self, Expression as ParsedExpression, FunctionKind, LambdaExpression, PILFile, PilStatement,
-    SourceReference, SymbolCategory, TraitImplementation, TypedExpression,
+    SourceReference, SymbolCategory, TraitImplementation, TypeDeclaration, TypedExpression,
 };
 use powdr_number::{FieldElement, GoldilocksField};
 
@@ -27,7 +27,7 @@ use powdr_ast::analyzed::{
 use powdr_parser::{parse, parse_module, parse_type};
 use powdr_parser_util::Error;
 
-use crate::traits_resolver::TraitsResolver;
+use crate::traits_resolver::{check_overlapping_impls, TraitsResolver};
 use crate::type_builtins::constr_function_statement_type;
 use crate::type_inference::infer_types;
 use crate::{side_effect_checker, AnalysisDriver};
@@ -45,7 +45,10 @@ pub fn analyze_ast<T: FieldElement>(pil_file: PILFile) -> Result<Analyzed<T>, Ve
 }
 
 pub fn analyze_string<T: FieldElement>(contents: &str) -> Result<Analyzed<T>, Vec<Error>> {
-    let pil_file = powdr_parser::parse(Some("input"), contents).map_err(|e| vec![e])?;
+    let (pil_file, errors) = powdr_parser::parse_with_recovery(Some("input"), contents);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
     analyze(vec![pil_file])
 }
 
@@ -276,7 +279,10 @@ impl PILAnalyzer {
         // by the statement processor already).
         // For Arrays, we also collect the inner expressions and expect them to be field elements.
 
-        for trait_impl in self.trait_impls.iter_mut() {
+        // Functions of generic trait implementations are checked like generic definitions,
+        // so we collect them under a name that cannot clash with a symbol.
+        let mut generic_impl_functions = vec![];
+        for (index, trait_impl) in self.trait_impls.iter_mut().enumerate() {
             let (_, def) = self
                 .definitions
                 .get(&trait_impl.name.to_string())
@@ -291,17 +297,36 @@ impl PILAnalyzer {
                 .map(|named_expr| trait_impl.type_of_function(trait_decl, &named_expr.name))
                 .collect();
 
+            let type_vars = &trait_impl.type_scheme.vars;
             for (named_expr, specialized_type) in
                 trait_impl.functions.iter_mut().zip(specialized_types)
             {
-                expressions.push((
-                    Arc::get_mut(&mut named_expr.body).unwrap(),
-                    specialized_type.into(),
-                ));
+                let body = Arc::get_mut(&mut named_expr.body).unwrap();
+                if type_vars.is_empty() {
+                    expressions.push((body, specialized_type.into()));
+                } else {
+                    let name = format!("{}::{} (impl #{index})", trait_impl.name, named_expr.name);
+                    let type_scheme = TypeScheme {
+                        vars: type_vars.clone(),
+                        ty: specialized_type,
+                    };
+                    generic_impl_functions.push((name, (Some(type_scheme), Some(body))));
+                }
             }
         }
 
-        let definitions = self
+        let struct_declarations = self
+            .definitions
+            .iter()
+            .filter_map(|(name, (_, value))| match value {
+                Some(FunctionValueDefinition::TypeDeclaration(TypeDeclaration::Struct(
+                    struct_decl,
+                ))) => Some((name.clone(), struct_decl.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let mut definitions: HashMap<_, _> = self
             .definitions
             .iter_mut()
             .filter(|(_name, (_symbol, value))| {
@@ -347,12 +372,13 @@ impl PILAnalyzer {
                 Some((name.clone(), (type_scheme, expr)))
             })
             .collect();
+        definitions.extend(generic_impl_functions);
         for expr in &mut self.proof_items {
             // At statement level, we allow Constr, Constr[], (int -> ()) or ().
             expressions.push((expr, constr_function_statement_type()));
         }
 
-        let inferred_types = infer_types(definitions, &mut expressions)?;
+        let inferred_types = infer_types(definitions, struct_declarations, &mut expressions)?;
         // Store the inferred types.
         for (name, ty) in inferred_types {
             let Some(FunctionValueDefinition::Expression(TypedExpression {
@@ -370,6 +396,11 @@ impl PILAnalyzer {
     /// Creates and returns a map for every referenced trait function with concrete type to the
     /// corresponding trait implementation function.
     fn resolve_trait_impls(&mut self) -> Result<SolvedTraitImpls, Vec<Error>> {
+        let errors = check_overlapping_impls(&self.trait_impls);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
         let all_traits = self
             .definitions
             .iter()
@@ -714,9 +714,6 @@ where
         trait_impl: parsed::TraitImplementation<parsed::Expression>,
     ) -> Result<TraitImplementation<Expression>, Error> {
         let type_vars: HashSet<_> = trait_impl.type_scheme.vars.vars().collect();
-        if !type_vars.is_empty() {
-            unimplemented!("Generic impls are not supported yet.");
-        }
         let functions = trait_impl
             .functions
             .into_iter()
@@ -1,14 +1,17 @@
+use itertools::Itertools;
 use powdr_ast::{
-    analyzed::{Expression, PolynomialReference, SolvedTraitImpls},
+    analyzed::{
+        Expression, PolynomialReference, ResolvedTraitFunction, SolvedTraitImpls,
+        TraitImplCandidate,
+    },
     parsed::{
+        display::format_type_args,
         types::{TupleType, Type},
         TraitImplementation,
     },
 };
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-};
+use powdr_parser_util::Error;
+use std::collections::{HashMap, HashSet};
 
 use crate::type_unifier::Unifier;
 
@@ -17,8 +20,6 @@ use crate::type_unifier::Unifier;
 pub struct TraitsResolver<'a> {
     /// All trait names, even if they have no implementation.
     traits: HashSet<&'a str>,
-    /// List of implementations for all traits and their index in the list of trait impls.
-    trait_impls: HashMap<String, Vec<(&'a TraitImplementation<Expression>, usize)>>,
     /// Index data structure that we are building up here.
     solved_impls: SolvedTraitImpls,
 }
@@ -28,22 +29,29 @@ impl<'a> TraitsResolver<'a> {
         traits: HashSet<&'a str>,
         trait_impls: &'a [TraitImplementation<Expression>],
     ) -> Self {
-        let mut impls_by_trait: HashMap<String, Vec<_>> = HashMap::new();
-        for (i, impl_) in trait_impls.iter().enumerate() {
-            impls_by_trait
-                .entry(impl_.name.to_string())
-                .or_default()
-                .push((impl_, i));
+        let mut solved_impls = SolvedTraitImpls::default();
+        for (index, impl_) in trait_impls.iter().enumerate() {
+            for function in &impl_.functions {
+                solved_impls.add_candidate(
+                    format!("{}::{}", impl_.name, function.name),
+                    TraitImplCandidate {
+                        index,
+                        type_scheme: impl_.type_scheme.clone(),
+                        function: function.body.clone(),
+                    },
+                );
+            }
         }
         Self {
             traits,
-            trait_impls: impls_by_trait,
-            solved_impls: Default::default(),
+            solved_impls,
         }
     }
 
     /// Resolves a trait function reference for a given polynomial reference.
     /// If successful, it stores the resolved implementation to be returned via `solved_impls()`.
+    /// References with generic type arguments are skipped, they are resolved
+    /// at evaluation time.
     pub fn resolve_trait_function_reference(
         &mut self,
         reference: &PolynomialReference,
@@ -62,23 +70,33 @@ impl<'a> TraitsResolver<'a> {
         }
 
         // Now we need to find out if this is a trait function at all or just a generic function.
-        let Some((trait_decl_name, trait_fn_name)) = reference.name.rsplit_once("::") else {
+        let Some((trait_decl_name, _)) = reference.name.rsplit_once("::") else {
             return Ok(());
         };
         if !self.traits.contains(trait_decl_name) {
             // Not a trait function.
             return Ok(());
         }
-        let Some(trait_impls) = self.trait_impls.get(trait_decl_name) else {
+        if self.solved_impls.candidates(&reference.name).is_empty() {
             return Err(format!(
                 "Could not find an implementation for the trait function {reference} (trait is not implemented at all)"
             ));
-        };
+        }
+        if !type_args.iter().all(|t| t.is_concrete_type()) {
+            // Inside a generic function, the implementation depends on the instantiation.
+            return Ok(());
+        }
 
-        match find_trait_implementation(trait_fn_name, type_args, trait_impls) {
-            Some((expr, index)) => {
-                self.solved_impls
-                    .insert(reference.name.clone(), type_args.clone(), index, expr);
+        match find_trait_implementation(self.solved_impls.candidates(&reference.name), type_args) {
+            Some((candidate, type_vars)) => {
+                let (index, function) = (candidate.index, candidate.function.clone());
+                self.solved_impls.insert(
+                    reference.name.clone(),
+                    type_args.clone(),
+                    index,
+                    function,
+                    type_vars,
+                );
                 Ok(())
             }
             None => Err(format!(
@@ -94,25 +112,134 @@ impl<'a> TraitsResolver<'a> {
     }
 }
 
-fn find_trait_implementation(
-    function: &str,
+/// Checks that no two implementations of the same trait apply to the same type
+/// arguments, since the implementation to use would not be well-defined.
+pub fn check_overlapping_impls(trait_impls: &[TraitImplementation<Expression>]) -> Vec<Error> {
+    trait_impls
+        .iter()
+        .tuple_combinations()
+        .filter(|(a, b)| a.name == b.name && impls_overlap(a, b))
+        .map(|(a, b)| {
+            b.source_ref.with_error(format!(
+                "Conflicting implementations of trait {}: {} overlaps with {}",
+                b.name,
+                impl_header(b),
+                impl_header(a)
+            ))
+        })
+        .collect()
+}
+
+/// Returns true if there are type arguments that match both implementations
+/// and satisfy the bounds of their type variables.
+fn impls_overlap(a: &TraitImplementation<Expression>, b: &TraitImplementation<Expression>) -> bool {
+    // Rename the type variables of `b` so that they are distinct from those of `a`.
+    let rename = |var: &String| format!("{var}'");
+    let renaming = b
+        .type_scheme
+        .vars
+        .vars()
+        .map(|var| (var.clone(), Type::TypeVar(rename(var))))
+        .collect();
+    let mut b_type = b.type_scheme.ty.clone();
+    b_type.substitute_type_vars(&renaming);
+
+    let mut unifier = Unifier::default();
+    if unifier
+        .unify_types(a.type_scheme.ty.clone(), b_type)
+        .is_err()
+    {
+        return false;
+    }
+    let a_bounds = a
+        .type_scheme
+        .vars
+        .bounds()
+        .map(|(var, bounds)| (var.clone(), bounds));
+    let b_bounds = b
+        .type_scheme
+        .vars
+        .bounds()
+        .map(|(var, bounds)| (rename(var), bounds));
+    a_bounds.chain(b_bounds).all(|(var, bounds)| {
+        bounds.iter().all(|bound| {
+            unifier
+                .ensure_bound(&Type::TypeVar(var.clone()), bound.clone())
+                .is_ok()
+        })
+    })
+}
+
+/// Formats the header of a trait implementation, e.g. `impl<T: Add> Add<Ext<T>>`.
+fn impl_header(trait_impl: &TraitImplementation<Expression>) -> String {
+    let Type::Tuple(TupleType { items }) = &trait_impl.type_scheme.ty else {
+        panic!("Type from trait scheme is not a tuple.")
+    };
+    format!(
+        "impl{} {}{}",
+        trait_impl.type_scheme.type_vars_to_string(),
+        trait_impl.name,
+        format_type_args(items)
+    )
+}
+
+/// Returns the implementation of a trait function for concrete type arguments.
+/// In contrast to `SolvedTraitImpls::try_resolve_trait_function`, this also works
+/// for type arguments that only occur at evaluation time, i.e. for references
+/// inside generic functions.
+pub fn resolve_trait_function<'a>(
+    solved_impls: &'a SolvedTraitImpls,
+    trait_function_name: &str,
     type_args: &[Type],
-    implementations: &[(&TraitImplementation<Expression>, usize)],
-) -> Option<(Arc<Expression>, usize)> {
+) -> Option<ResolvedTraitFunction<'a>> {
+    solved_impls
+        .try_resolve_trait_function(trait_function_name, type_args)
+        .or_else(|| {
+            find_trait_implementation(solved_impls.candidates(trait_function_name), type_args).map(
+                |(candidate, type_vars)| ResolvedTraitFunction {
+                    index: candidate.index,
+                    function: candidate.function.as_ref(),
+                    type_vars,
+                },
+            )
+        })
+}
+
+/// Finds the implementation whose type matches the concrete type arguments
+/// and whose type variables satisfy their bounds. There is at most one such
+/// implementation, since overlapping implementations are rejected during analysis.
+/// Returns the implementation and the values of its type variables.
+fn find_trait_implementation<'a>(
+    candidates: &'a [TraitImplCandidate],
+    type_args: &[Type],
+) -> Option<(&'a TraitImplCandidate, HashMap<String, Type>)> {
     let tuple_args = Type::Tuple(TupleType {
         items: type_args.to_vec(),
     });
     assert!(tuple_args.is_concrete_type());
 
-    implementations.iter().find_map(|(impl_, index)| {
-        Unifier::default()
-            .unify_types(tuple_args.clone(), impl_.type_scheme.ty.clone())
-            .is_ok()
-            .then(|| {
-                (
-                    impl_.function_by_name(function).unwrap().body.clone(),
-                    *index,
-                )
+    candidates.iter().find_map(|candidate| {
+        let scheme = &candidate.type_scheme;
+        let mut unifier = Unifier::default();
+        unifier
+            .unify_types(tuple_args.clone(), scheme.ty.clone())
+            .ok()?;
+        for (var, bounds) in scheme.vars.bounds() {
+            for bound in bounds {
+                unifier
+                    .ensure_bound(&Type::TypeVar(var.clone()), bound.clone())
+                    .ok()?;
+            }
+        }
+        let type_vars = scheme
+            .vars
+            .vars()
+            .map(|var| {
+                let mut ty = Type::TypeVar(var.clone());
+                unifier.substitute(&mut ty);
+                (var.clone(), ty)
             })
+            .collect();
+        Some((candidate, type_vars))
     })
 }
@@ -7,9 +7,10 @@ use powdr_ast::{
         display::format_type_scheme_around_name,
         types::{ArrayType, FunctionType, TupleType, Type, TypeBounds, TypeScheme},
         visitor::ExpressionVisitable,
-        ArrayLiteral, BinaryOperation, BlockExpression, FunctionCall, FunctionKind, IndexAccess,
-        LambdaExpression, LetStatementInsideBlock, MatchArm, MatchExpression, Number, Pattern,
-        SourceReference, StatementInsideBlock, UnaryOperation,
+        ArrayLiteral, BinaryOperation, BlockExpression, FieldAccess, FunctionCall, FunctionKind,
+        IndexAccess, LambdaExpression, LetStatementInsideBlock, MatchArm, MatchExpression,
+        NamedExpression, Number, Pattern, SourceReference, StatementInsideBlock, StructDeclaration,
+        StructExpression, UnaryOperation,
     },
 };
 use powdr_parser_util::{Error, SourceRef};
@@ -26,13 +27,15 @@ use crate::{
 /// Infers types on all definitions and checks type-correctness for isolated
 /// expressions (from identities and arrays) where the expected type is given.
 /// The parameter `statement_type` is the expected type for expressions at statement level.
+/// The parameter `struct_declarations` contains the declarations of all structs by their absolute name.
 /// Sets the generic arguments for references and the literal types in all expressions.
 /// Returns the types for symbols without explicit type.
 pub fn infer_types(
     definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
+    struct_declarations: HashMap<String, StructDeclaration>,
     expressions: &mut [(&mut Expression, ExpectedType)],
 ) -> Result<Vec<(String, Type)>, Vec<Error>> {
-    TypeChecker::new().infer_types(definitions, expressions)
+    TypeChecker::new(struct_declarations).infer_types(definitions, expressions)
 }
 
 /// A type to expect with a bit of flexibility.
@@ -67,21 +70,28 @@ struct TypeChecker {
     /// Contains the unmodified type scheme for symbols with generic types and newly
     /// created type variables for symbols without declared type.
     declared_types: HashMap<String, (SourceRef, TypeScheme)>,
+    /// Declarations of all structs, used for struct expressions and field accesses.
+    struct_declarations: HashMap<String, StructDeclaration>,
     /// Current mapping of declared type vars to type. Reset before checking each definition.
     declared_type_vars: HashMap<String, Type>,
     unifier: Unifier,
     /// Keeps track of the kind of lambda we are currently type-checking.
     lambda_kind: FunctionKind,
+    /// Expected types of the parameters of the lambda expression that is checked next, if known.
+    /// They make the types of the parameters available inside the body, e.g. to access struct fields.
+    lambda_param_hints: Option<Vec<Type>>,
 }
 
 impl TypeChecker {
-    pub fn new() -> Self {
+    pub fn new(struct_declarations: HashMap<String, StructDeclaration>) -> Self {
         Self {
             local_var_types: Default::default(),
             declared_types: Default::default(),
+            struct_declarations,
             declared_type_vars: Default::default(),
             unifier: Default::default(),
             lambda_kind: FunctionKind::Constr,
+            lambda_param_hints: None,
         }
     }
 
@@ -155,6 +165,9 @@ impl TypeChecker {
                     .vars()
                     .map(|v| (v.clone(), self.unifier.new_type_var()))
                     .collect();
+                let mut declared_ty = declared_type.ty.clone();
+                declared_ty.substitute_type_vars(&self.declared_type_vars);
+                self.set_lambda_param_hints(&declared_ty, value);
                 self.infer_type_of_expression(value).map(|ty| {
                     inferred_types.insert(name.to_string(), ty);
                 })?;
@@ -409,6 +422,13 @@ impl TypeChecker {
             Expression::Reference(
                 source_ref,
                 Reference::Poly(PolynomialReference { name, type_args }),
+            )
+            | Expression::StructExpression(
+                source_ref,
+                StructExpression {
+                    name: Reference::Poly(PolynomialReference { name, type_args }),
+                    ..
+                },
             ) => {
                 for ty in type_args.as_mut().unwrap() {
                     if !self.update_local_type(ty, type_var_mapping) {
@@ -528,22 +548,7 @@ impl TypeChecker {
                 let (ty, args) = self
                     .unifier
                     .instantiate_scheme(self.declared_types[name].1.clone());
-                if let Some(requested_type_args) = type_args {
-                    if requested_type_args.len() != args.len() {
-                        return Err(source_ref.with_error(format!(
-                            "Expected {} type arguments for symbol {name}, but got {}: {}",
-                            args.len(),
-                            requested_type_args.len(),
-                            requested_type_args.iter().join(", ")
-                        )));
-                    }
-                    for (requested, inferred) in requested_type_args.iter_mut().zip(&args) {
-                        requested.substitute_type_vars(&self.declared_type_vars);
-                        self.unifier
-                            .unify_types(requested.clone(), inferred.clone())
-                            .map_err(|err| source_ref.with_error(err))?;
-                    }
-                }
+                self.unify_requested_type_args(source_ref, name, type_args, &args)?;
                 *type_args = Some(args);
                 type_for_reference(&ty)
             }
@@ -588,12 +593,20 @@ impl TypeChecker {
                     param_types,
                 },
             ) => {
+                let param_type_hints = self.lambda_param_hints.take();
                 let old_len = self.local_var_types.len();
                 let result = params
                     .iter()
                     .map(|p| self.infer_type_of_pattern(p))
                     .collect::<Result<Vec<_>, _>>()
                     .and_then(|param_types| {
+                        for (param_type, hint) in param_types
+                            .iter()
+                            .zip(param_type_hints.into_iter().flatten())
+                        {
+                            // A mismatch is reported when the type of the whole lambda is checked.
+                            let _ = self.unifier.unify_types(param_type.clone(), hint);
+                        }
                         let old_lambda_kind = self.lambda_kind;
                         self.lambda_kind = *kind;
                         let body_type = self.infer_type_of_expression(body);
@@ -658,6 +671,11 @@ impl TypeChecker {
                 self.expect_type(&Type::Int, index)?;
                 result
             }
+            Expression::FieldAccess(source_ref, FieldAccess { object, field }) => {
+                let object_type = self.infer_type_of_expression(object)?;
+                self.type_of_field(object_type, field)
+                    .map_err(|err| source_ref.with_error(err))?
+            }
             Expression::FunctionCall(
                 source_ref,
                 FunctionCall {
@@ -733,12 +751,101 @@ impl TypeChecker {
                 self.local_var_types.truncate(original_var_count);
                 result?
             }
-            Expression::StructExpression(_sr, _struct_expr) => {
-                unimplemented!("Struct expressions are not yet supported")
+            Expression::StructExpression(
+                source_ref,
+                StructExpression {
+                    name: Reference::Poly(PolynomialReference { name, type_args }),
+                    fields,
+                },
+            ) => {
+                // The fields have already been checked against the declaration.
+                let struct_decl = self
+                    .struct_declarations
+                    .get(name.as_str())
+                    .ok_or_else(|| source_ref.with_error(format!("Struct '{name}' not found.")))?
+                    .clone();
+                let (ty, args) = self.unifier.instantiate_scheme(struct_decl.type_scheme());
+                self.unify_requested_type_args(source_ref, name, type_args, &args)?;
+                let type_var_mapping: HashMap<_, _> = struct_decl
+                    .type_vars
+                    .vars()
+                    .cloned()
+                    .zip(args.clone())
+                    .collect();
+                for NamedExpression { name, body } in fields {
+                    let mut field_type = struct_decl.type_of_field(name).unwrap().ty;
+                    field_type.substitute_type_vars(&type_var_mapping);
+                    self.expect_type(&field_type, body)?;
+                }
+                *type_args = Some(args);
+                ty
+            }
+            Expression::StructExpression(_, StructExpression { name, .. }) => {
+                unreachable!("Struct name not resolved: {name}")
             }
         })
     }
 
+    /// Unifies the explicitly requested type arguments (if any) of a reference
+    /// with the type arguments `args` the type scheme of the symbol has been instantiated with.
+    fn unify_requested_type_args(
+        &mut self,
+        source_ref: &SourceRef,
+        name: &str,
+        type_args: &mut Option<Vec<Type>>,
+        args: &[Type],
+    ) -> Result<(), Error> {
+        let Some(requested_type_args) = type_args else {
+            return Ok(());
+        };
+        if requested_type_args.len() != args.len() {
+            return Err(source_ref.with_error(format!(
+                "Expected {} type arguments for symbol {name}, but got {}: {}",
+                args.len(),
+                requested_type_args.len(),
+                requested_type_args.iter().join(", ")
+            )));
+        }
+        for (requested, inferred) in requested_type_args.iter_mut().zip(args) {
+            requested.substitute_type_vars(&self.declared_type_vars);
+            self.unifier
+                .unify_types(requested.clone(), inferred.clone())
+                .map_err(|err| source_ref.with_error(err))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the type of the field `field` of a value of type `object_type`,
+    /// which has to be a struct type already known at this point.
+    fn type_of_field(&self, object_type: Type, field: &str) -> Result<Type, String> {
+        let object_type = self.type_into_substituted(object_type);
+        let Type::NamedType(name, args) = &object_type else {
+            return Err(if matches!(object_type, Type::TypeVar(_)) {
+                format!("Type of the value needs to be known to access its field '{field}'.")
+            } else {
+                format!("Expected a struct to access field '{field}', but got type {object_type}.")
+            });
+        };
+        let struct_decl = self
+            .struct_declarations
+            .get(&name.to_string())
+            .ok_or_else(|| {
+                format!("Expected a struct to access field '{field}', but got type {object_type}.")
+            })?;
+        let mut field_type = struct_decl
+            .type_of_field(field)
+            .ok_or_else(|| format!("Struct '{name}' has no field named '{field}'"))?
+            .ty;
+        let type_var_mapping: HashMap<_, _> = struct_decl
+            .type_vars
+            .vars()
+            .cloned()
+            .zip(args.iter().flatten().cloned())
+            .collect();
+        field_type.substitute_type_vars(&type_var_mapping);
+        Ok(field_type)
+    }
+
     /// Returns the type expected at statement level, given the current function context.
     fn statement_type(&self) -> ExpectedType {
         if self.lambda_kind == FunctionKind::Constr {
@@ -790,6 +897,7 @@ impl TypeChecker {
     /// because we can create better error messages.
     fn expect_type(&mut self, expected_type: &Type, expr: &mut Expression) -> Result<(), Error> {
         update_type_if_literal(expr, expected_type);
+        self.set_lambda_param_hints(expected_type, expr);
 
         let inferred_type = self.infer_type_of_expression(expr)?;
         self.unifier
@@ -803,6 +911,21 @@ impl TypeChecker {
             })
     }
 
+    /// If `expr` is a lambda expression and `expected_type` is a function type,
+    /// makes the expected parameter types available to the lambda.
+    fn set_lambda_param_hints(&mut self, expected_type: &Type, expr: &Expression) {
+        let Expression::LambdaExpression(_, LambdaExpression { params, .. }) = expr else {
+            return;
+        };
+        self.lambda_param_hints = match self.type_into_substituted(expected_type.clone()) {
+            Type::Function(FunctionType {
+                params: expected_params,
+                ..
+            }) if expected_params.len() == params.len() => Some(expected_params),
+            _ => None,
+        };
+    }
+
     /// Type-checks a pattern and adds local variables.
     fn expect_type_of_pattern(
         &mut self,
@@ -386,6 +386,48 @@ pub fn match_enum() {
     );
 }
 
+#[test]
+fn structs() {
+    let src = r#"
+        struct Point<T> { x: T, y: T }
+        let shift: Point<int>, int -> Point<int> = |p, d| Point{ y: p.y + d, x: p.x + d };
+        let p = shift(Point{ x: 1, y: 2 }, 10);
+        let t = (p, p.x, shift(p, 1).y);
+    "#;
+    assert_eq!(
+        parse_and_evaluate_symbol(src, "t"),
+        "(Point{ x: 11, y: 12 }, 11, 13)".to_string()
+    );
+}
+
+#[test]
+fn generic_trait_impl() {
+    let src = r#"
+        trait Add<T> {
+            add: T, T -> T,
+        }
+        impl Add<int> {
+            add: |a, b| a + b,
+        }
+        struct Ext<T> { a: T, b: T }
+        impl<T> Add<Ext<T>> {
+            add: |x, y| Ext{ a: Add::add(x.a, y.a), b: Add::add(x.b, y.b) },
+        }
+        let<T> double: T -> T = |x| Add::add(x, x);
+        let e: Ext<int> = Ext{ a: 1, b: 2 };
+        let r: Ext<int> = Add::add(e, Ext{ a: 3, b: 4 });
+        let d: Ext<Ext<int>> = double(Ext{ a: e, b: r });
+    "#;
+    assert_eq!(
+        parse_and_evaluate_symbol(src, "r"),
+        "Ext{ a: 4, b: 6 }".to_string()
+    );
+    assert_eq!(
+        parse_and_evaluate_symbol(src, "d"),
+        "Ext{ a: Ext{ a: 2, b: 4 }, b: Ext{ a: 8, b: 12 } }".to_string()
+    );
+}
+
 #[test]
 pub fn gigantic_stack() {
     let src = r#"
@@ -748,6 +748,106 @@ fn trait_wrong_impl() {
     type_check(input, &[]);
 }
 
+#[test]
+fn generic_trait_impl() {
+    let input = "
+    trait Add<T> {
+        add: T, T -> T,
+    }
+    struct Ext<T> {
+        a: T,
+        b: T,
+    }
+    impl<T: Add> Add<Ext<T>> {
+        add: |x, y| Ext { a: x.a + y.a, b: x.b + y.b },
+    }
+    let r: Ext<int> = Add::add(Ext { a: 1, b: 2 }, Ext { a: 3, b: 4 });
+    let<T> double: Ext<T> -> Ext<T> = |x| Add::add(x, x);
+    let s: Ext<fe> = double(Ext { a: 1, b: 2 });
+    ";
+    type_check(input, &[("double", "T", "Ext<T> -> Ext<T>")]);
+}
+
+#[test]
+#[should_panic = "Inferred type scheme for symbol Add::add (impl #0) does not match the declared type."]
+fn generic_trait_impl_missing_bound() {
+    let input = "
+    trait Add<T> {
+        add: T, T -> T,
+    }
+    struct Ext<T> {
+        a: T,
+        b: T,
+    }
+    impl<T> Add<Ext<T>> {
+        add: |x, y| Ext { a: x.a + y.a, b: x.b + y.b },
+    }
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Could not find a matching implementation for the trait function Add::add::<Ext<(int, int)>>"]
+fn generic_trait_impl_bound_not_satisfied() {
+    let input = "
+    trait Add<T> {
+        add: T, T -> T,
+    }
+    struct Ext<T> {
+        a: T,
+        b: T,
+    }
+    impl<T: Add> Add<Ext<T>> {
+        add: |x, y| Ext { a: x.a + y.a, b: x.b + y.b },
+    }
+    let r: Ext<(int, int)> = Add::add(Ext { a: (1, 2), b: (3, 4) }, Ext { a: (5, 6), b: (7, 8) });
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Conflicting implementations of trait Add: impl Add<Ext<int>> overlaps with impl<T: Add> Add<Ext<T>>"]
+fn overlapping_trait_impls() {
+    let input = "
+    trait Add<T> {
+        add: T, T -> T,
+    }
+    struct Ext<T> {
+        a: T,
+        b: T,
+    }
+    impl<T: Add> Add<Ext<T>> {
+        add: |x, y| Ext { a: x.a + y.a, b: x.b + y.b },
+    }
+    impl Add<Ext<int>> {
+        add: |x, y| Ext { a: x.a + y.a, b: x.b - y.b },
+    }
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+fn trait_impls_disjoint_by_bounds() {
+    let input = "
+    trait Pick<T> {
+        pick: T -> T,
+    }
+    struct Ext<T> {
+        a: T,
+        b: T,
+    }
+    impl<T: Add> Pick<Ext<T>> {
+        pick: |x| Ext { a: x.a + x.b, b: x.b },
+    }
+    impl Pick<Ext<bool>> {
+        pick: |x| Ext { a: x.b, b: x.a },
+    }
+    let r: Ext<int> = Pick::pick(Ext { a: 1, b: 2 });
+    let s: Ext<bool> = Pick::pick(Ext { a: true, b: false });
+    ";
+    type_check(input, &[]);
+}
+
 #[test]
 fn prover_functions() {
     let input = "
@@ -824,6 +924,60 @@ fn test_struct_repeated_fields_decl() {
     type_check(input, &[]);
 }
 
+#[test]
+fn struct_field_access() {
+    let input = "
+    struct Point<T> { x: T, y: T }
+    struct Line { a: Point<int>, b: Point<int> }
+    let<T: Add + FromLiteral> p: T -> Point<T> = |i| Point{ x: i, y: i + 1 };
+    let l = Line{ a: p(1), b: p(2) };
+    let len = l.b.x - l.a.x;
+    let y = { let q = p(3_fe); q.y };
+    let sum: Point<int> -> int = |q| q.x + q.y;
+    ";
+    type_check(
+        input,
+        &[
+            ("p", "T: Add + FromLiteral", "T -> Point<T>"),
+            ("l", "", "Line"),
+            ("len", "", "int"),
+            ("y", "", "fe"),
+            ("sum", "", "Point<int> -> int"),
+        ],
+    );
+}
+
+#[test]
+#[should_panic = "Struct 'Point' has no field named 'z'"]
+fn struct_access_wrong_field() {
+    let input = "
+    struct Point { x: int, y: int }
+    let p = Point{ x: 1, y: 2 };
+    let z = p.z;
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Type of the value needs to be known to access its field 'x'."]
+fn field_access_unknown_type() {
+    let input = "
+    struct Point { x: int, y: int }
+    let f = |p| p.x;
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Expected a struct to access field 'x', but got type int."]
+fn field_access_on_non_struct() {
+    let input = "
+    let a: int = 1;
+    let f = (a + 1).x;
+    ";
+    type_check(input, &[]);
+}
+
 #[test]
 #[should_panic(expected = "Expected symbol of kind Struct but got Type: A")]
 fn enum_used_as_struct() {
@@ -17,6 +17,7 @@ use powdr_ast::parsed::visitor::{AllChildren, Children, ExpressionVisitable};
 use powdr_ast::parsed::Number;
 use powdr_number::{BigUint, FieldElement};
 
+pub mod lint;
 pub mod referenced_symbols;
 
 use referenced_symbols::{ReferencedSymbols, SymbolReference};
@@ -109,17 +110,28 @@ fn remove_unreferenced_definitions<T: FieldElement>(pil_file: &mut Analyzed<T>)
             .then_some(SymbolReference::from("std::prelude::set_hint"));
             if let Some(FunctionValueDefinition::TraitFunction(..)) = value {
                 let type_args = n.type_args.unwrap();
-                // If this is not concrete at some point in the future,
-                // we need to substitute type variables while traversing the dependency graph.
-                // Currently, when we encounter a generic function, we just ignore the type arguments.
-                // This works well because we do not have bounds for user-defined traits yet
-                // and also no generic trait impls.
-                assert!(type_args.iter().all(|t| t.is_concrete_type()));
-                let impl_index = pil_file
-                    .solved_impls
-                    .resolve_trait_impl_index(&n.name, type_args);
-                impls_to_retain.insert(impl_index);
-                Box::new(pil_file.trait_impls[impl_index].symbols())
+                // When we encounter a generic function, we just ignore the type arguments,
+                // so references inside generic functions and impls can have type variables
+                // in their type arguments. Since we do not substitute them while traversing
+                // the dependency graph, we retain all implementations they could resolve to.
+                let impl_indices = if type_args.iter().all(|t| t.is_concrete_type()) {
+                    vec![pil_file
+                        .solved_impls
+                        .resolve_trait_impl_index(&n.name, type_args)]
+                } else {
+                    pil_file
+                        .solved_impls
+                        .candidates(&n.name)
+                        .iter()
+                        .map(|c| c.index)
+                        .collect()
+                };
+                impls_to_retain.extend(impl_indices.iter().copied());
+                Box::new(
+                    impl_indices
+                        .into_iter()
+                        .flat_map(|i| pil_file.trait_impls[i].symbols()),
+                )
             } else {
                 Box::new(
                     value
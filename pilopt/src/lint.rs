@@ -0,0 +1,235 @@
+//! Soundness checks on PIL, meant to catch witness columns that are constrained
+//! by prover hints only.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+use powdr_ast::analyzed::{
+    AlgebraicExpression, Analyzed, Identity, LookupIdentity, PhantomLookupIdentity, PolyID,
+    PolynomialType, SelectedExpressions,
+};
+use powdr_ast::parsed::{visitor::AllChildren, SourceReference};
+use powdr_number::FieldElement;
+use powdr_parser_util::SourceRef;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// A witness column that is not referenced by any identity.
+    UnconstrainedWitnessColumn { name: String },
+    /// A witness column that is only referenced on the left-hand side of lookups,
+    /// none of which constrains it to the values of a fixed column.
+    OnlyInLookupLhs { name: String },
+    /// An identity whose degree is higher than the maximum degree supported by the backend.
+    DegreeTooHigh {
+        identity_id: u64,
+        degree: usize,
+        max_degree: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    /// The location of the column declaration or the identity.
+    pub source: SourceRef,
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LintKind::UnconstrainedWitnessColumn { name } => {
+                write!(f, "Witness column {name} is not constrained by any identity")
+            }
+            LintKind::OnlyInLookupLhs { name } => write!(
+                f,
+                "Witness column {name} is only used on the left-hand side of lookups and not range-constrained"
+            ),
+            LintKind::DegreeTooHigh {
+                identity_id,
+                degree,
+                max_degree,
+            } => write!(
+                f,
+                "Identity {identity_id} has degree {degree}, but the maximum degree is {max_degree}"
+            ),
+        }?;
+        write!(f, " at {}", format_location(&self.source))
+    }
+}
+
+/// Formats the source reference as `<file>:<line>`, if the file contents are known.
+fn format_location(source: &SourceRef) -> String {
+    let file_name = source.file_name.as_deref().unwrap_or("input");
+    match &source.file_contents {
+        Some(contents) if source.start <= contents.len() => {
+            let line = contents[..source.start].matches('\n').count() + 1;
+            format!("{file_name}:{line}")
+        }
+        _ => format!("{source:?}"),
+    }
+}
+
+/// How a witness column is referenced by the identities.
+#[derive(Default)]
+struct ColumnUsage {
+    /// Referenced by an identity other than on the left-hand side of a lookup.
+    constrained: bool,
+    /// Referenced on the left-hand side of a lookup.
+    in_lookup_lhs: bool,
+    /// Directly looked up in a fixed column.
+    range_constrained: bool,
+}
+
+/// Checks the PIL for underconstrained witness columns and, if `max_degree` is given,
+/// identities of a higher degree.
+/// This is meant to run on the PIL before optimization, since the optimizer removes
+/// unreferenced columns.
+pub fn lint<T: FieldElement>(pil: &Analyzed<T>, max_degree: Option<usize>) -> Vec<LintWarning> {
+    let intermediates: BTreeMap<PolyID, &AlgebraicExpression<T>> = pil
+        .intermediate_polys_in_source_order()
+        .flat_map(|(symbol, definitions)| {
+            symbol
+                .array_elements()
+                .map(|(_, poly_id)| poly_id)
+                .zip(definitions)
+        })
+        .collect();
+    let mut collector = ColumnCollector {
+        intermediates,
+        cache: Default::default(),
+    };
+
+    let mut usage: BTreeMap<PolyID, ColumnUsage> = BTreeMap::new();
+    for identity in &pil.identities {
+        match identity {
+            Identity::Lookup(LookupIdentity { left, right, .. })
+            | Identity::PhantomLookup(PhantomLookupIdentity { left, right, .. }) => {
+                for column in collector.witness_columns(left.all_children()) {
+                    usage.entry(column).or_default().in_lookup_lhs = true;
+                }
+                for column in collector.witness_columns(right.all_children()) {
+                    usage.entry(column).or_default().constrained = true;
+                }
+                for column in range_constrained_columns(left, right) {
+                    usage.entry(column).or_default().range_constrained = true;
+                }
+                if let Identity::PhantomLookup(identity) = identity {
+                    for column in collector.witness_columns(identity.multiplicity.all_children()) {
+                        usage.entry(column).or_default().constrained = true;
+                    }
+                }
+            }
+            _ => {
+                for column in collector.witness_columns(identity.all_children()) {
+                    usage.entry(column).or_default().constrained = true;
+                }
+            }
+        }
+    }
+
+    let column_warnings = pil
+        .committed_polys_in_source_order()
+        .flat_map(|(symbol, _)| {
+            symbol
+                .array_elements()
+                .map(move |(name, poly_id)| (name, poly_id, &symbol.source))
+        })
+        .filter_map(|(name, poly_id, source)| {
+            let kind = match usage.get(&poly_id) {
+                None => LintKind::UnconstrainedWitnessColumn { name },
+                Some(usage)
+                    if usage.in_lookup_lhs && !usage.constrained && !usage.range_constrained =>
+                {
+                    LintKind::OnlyInLookupLhs { name }
+                }
+                Some(_) => return None,
+            };
+            Some(LintWarning {
+                kind,
+                source: source.clone(),
+            })
+        });
+
+    let intermediate_definitions = pil.intermediate_definitions();
+    let degree_warnings = max_degree.into_iter().flat_map(|max_degree| {
+        pil.identities.iter().filter_map(move |identity| {
+            let degree = identity.degree(&intermediate_definitions);
+            (degree > max_degree).then(|| LintWarning {
+                kind: LintKind::DegreeTooHigh {
+                    identity_id: identity.id(),
+                    degree,
+                    max_degree,
+                },
+                source: identity.source_reference().clone(),
+            })
+        })
+    });
+
+    column_warnings.chain(degree_warnings).collect()
+}
+
+/// Returns the witness columns that are directly looked up in a fixed column,
+/// i.e. the left-hand side expression is a column reference and the right-hand side
+/// expression at the same position is a fixed column reference.
+fn range_constrained_columns<T>(
+    left: &SelectedExpressions<T>,
+    right: &SelectedExpressions<T>,
+) -> Vec<PolyID> {
+    left.expressions
+        .iter()
+        .zip(&right.expressions)
+        .filter_map(|(l, r)| match (l, r) {
+            (AlgebraicExpression::Reference(l), AlgebraicExpression::Reference(r))
+                if l.poly_id.ptype == PolynomialType::Committed
+                    && r.poly_id.ptype == PolynomialType::Constant =>
+            {
+                Some(l.poly_id)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collects the witness columns referenced by expressions, including those referenced
+/// through intermediate columns.
+struct ColumnCollector<'a, T> {
+    intermediates: BTreeMap<PolyID, &'a AlgebraicExpression<T>>,
+    /// The witness columns referenced by each intermediate column.
+    cache: BTreeMap<PolyID, BTreeSet<PolyID>>,
+}
+
+impl<'a, T> ColumnCollector<'a, T> {
+    fn witness_columns<'b>(
+        &mut self,
+        expressions: impl Iterator<Item = &'b AlgebraicExpression<T>>,
+    ) -> BTreeSet<PolyID>
+    where
+        T: 'b,
+    {
+        let mut columns = BTreeSet::new();
+        for e in expressions {
+            if let AlgebraicExpression::Reference(reference) = e {
+                match reference.poly_id.ptype {
+                    PolynomialType::Committed => {
+                        columns.insert(reference.poly_id);
+                    }
+                    PolynomialType::Intermediate => {
+                        columns.extend(self.intermediate_witness_columns(reference.poly_id));
+                    }
+                    PolynomialType::Constant => {}
+                }
+            }
+        }
+        columns
+    }
+
+    fn intermediate_witness_columns(&mut self, poly_id: PolyID) -> BTreeSet<PolyID> {
+        if let Some(columns) = self.cache.get(&poly_id) {
+            return columns.clone();
+        }
+        let definition = self.intermediates[&poly_id];
+        let columns = self.witness_columns(definition.all_children());
+        self.cache.insert(poly_id, columns.clone());
+        columns
+    }
+}
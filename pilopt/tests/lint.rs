@@ -0,0 +1,81 @@
+use powdr_number::GoldilocksField;
+use powdr_pil_analyzer::analyze_string;
+
+use powdr_pilopt::lint::{lint, LintKind};
+use pretty_assertions::assert_eq;
+
+fn lint_kinds(input: &str, max_degree: Option<usize>) -> Vec<LintKind> {
+    let pil = analyze_string::<GoldilocksField>(input).unwrap();
+    lint(&pil, max_degree)
+        .into_iter()
+        .map(|warning| warning.kind)
+        .collect()
+}
+
+#[test]
+fn unconstrained_witness_column() {
+    let input = r#"namespace N(65536);
+    col witness X;
+    col witness Y;
+    col witness unused;
+    col inter = Y * 2;
+    X = inter + 1;
+"#;
+    assert_eq!(
+        lint_kinds(input, None),
+        vec![LintKind::UnconstrainedWitnessColumn {
+            name: "N::unused".to_string()
+        }]
+    );
+}
+
+#[test]
+fn only_in_lookup_lhs() {
+    let input = r#"namespace N(65536);
+    col fixed BYTE(i) { i & 0xff };
+    col witness ranged;
+    col witness hint;
+    col witness A;
+    col witness B;
+    [ranged] in [BYTE];
+    [hint] in [A];
+    A = B;
+"#;
+    assert_eq!(
+        lint_kinds(input, None),
+        vec![LintKind::OnlyInLookupLhs {
+            name: "N::hint".to_string()
+        }]
+    );
+}
+
+#[test]
+fn degree_too_high() {
+    let input = r#"namespace N(65536);
+    col witness X;
+    col inter = X * X;
+    X * inter = 1;
+    X * X = 1;
+"#;
+    assert_eq!(lint_kinds(input, Some(3)), vec![]);
+    assert_eq!(
+        lint_kinds(input, Some(2)),
+        vec![LintKind::DegreeTooHigh {
+            identity_id: 0,
+            degree: 3,
+            max_degree: 2,
+        }]
+    );
+}
+
+#[test]
+fn warning_location() {
+    let input = "namespace N(65536);\n    col witness X;\n    col witness unused;\n    X = 1;\n";
+    let pil = analyze_string::<GoldilocksField>(input).unwrap();
+    let warnings = lint(&pil, None);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "Witness column N::unused is not constrained by any identity at input:3"
+    );
+}
@@ -15,9 +15,16 @@ fn jit_witgen_benchmark(c: &mut Criterion) {
     pipeline.compute_optimized_pil().unwrap();
     pipeline.compute_fixed_cols().unwrap();
 
-    group.bench_function("jit_witgen_benchmark", |b| {
-        b.iter(|| pipeline.clone().compute_witness().unwrap())
-    });
+    // Compares running the derived code compiled to machine code and interpreted.
+    for (name, use_interpreter) in [
+        ("jit_witgen_benchmark", false),
+        ("jit_witgen_benchmark_interpreter", true),
+    ] {
+        let pipeline = pipeline.clone().with_jit_interpreter(use_interpreter);
+        group.bench_function(name, |b| {
+            b.iter(|| pipeline.clone().compute_witness().unwrap())
+        });
+    }
     group.finish();
 }
 
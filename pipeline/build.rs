@@ -2,6 +2,7 @@ use std::env;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::process::Command;
 
 use walkdir::WalkDir;
 
@@ -11,6 +12,29 @@ fn main() {
     build_reparse_test("asm", "asm");
     build_reparse_test("pil", "pil");
     build_reparse_test("asm", "std");
+    build_id();
+}
+
+/// Exports `POWDR_BUILD_ID`, the git revision powdr is built from, so that artifacts
+/// computed by one build are not reused by a build with different code.
+/// It is empty if the crate is not built from a git checkout, in which case
+/// the crate version identifies the code.
+#[allow(clippy::print_stdout)]
+fn build_id() {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8(output.stdout).unwrap().trim().to_string())
+    };
+    let revision = git(&["rev-parse", "HEAD"]).unwrap_or_default();
+    // The reflog of HEAD changes with every commit and checkout.
+    if let Some(reflog) = git(&["rev-parse", "--git-path", "logs/HEAD"]) {
+        println!("cargo:rerun-if-changed={reflog}");
+    }
+    println!("cargo:rustc-env=POWDR_BUILD_ID={revision}");
 }
 
 fn build_book_tests(kind: &str) {
@@ -0,0 +1,178 @@
+//! An on-disk store for the optimized PIL and the fixed columns, so that they can be
+//! reused across process invocations.
+//!
+//! Artifacts are stored in a sub-directory of the cache directory whose name is the hash
+//! of everything that influences them: The source code (for ASM, the module tree after
+//! resolving all imports, for PIL, the file and all files it includes), the linker
+//! parameters, the field, the version of powdr and the git revision it was built from,
+//! since any change to e.g. the optimizer changes the artifacts. Builds with uncommitted
+//! changes share the artifacts of their revision, so the cache should be cleared when
+//! working on powdr itself.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use powdr_ast::{analyzed::Analyzed, parsed::PilStatement};
+use powdr_linker::LinkerParams;
+use powdr_number::{FieldElement, ReadWrite};
+use powdr_schemas::SerializedAnalyzed;
+use sha2::{Digest, Sha256};
+
+use crate::pipeline::VariablySizedColumns;
+
+const OPTIMIZED_PIL_FILE_NAME: &str = "optimized.pilo";
+const FIXED_COLS_FILE_NAME: &str = "constants.bin";
+
+/// Returns the key under which the artifacts computed from `source` are stored.
+pub(crate) fn cache_key<T: FieldElement>(source: &str, linker_params: &LinkerParams) -> String {
+    let mut hasher = Sha256::default();
+    for part in [
+        env!("CARGO_PKG_VERSION"),
+        env!("POWDR_BUILD_ID"),
+        std::any::type_name::<T>(),
+        &T::modulus().to_string(),
+        &linker_params.mode.to_string(),
+        &linker_params.degree_mode.to_string(),
+        source,
+    ] {
+        // Hash the length as well, so that the boundaries between the parts are unambiguous.
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Returns the contents of the given PIL file, followed by the contents of all
+/// files it (transitively) includes.
+pub(crate) fn pil_file_source(path: &Path) -> Result<String, String> {
+    let mut source = String::new();
+    let mut processed = vec![];
+    append_pil_file_source(path, &mut processed, &mut source)?;
+    Ok(source)
+}
+
+fn append_pil_file_source(
+    path: &Path,
+    processed: &mut Vec<PathBuf>,
+    source: &mut String,
+) -> Result<(), String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("File {} not found: {e}", path.display()))?;
+    if processed.contains(&path) {
+        return Ok(());
+    }
+    processed.push(path.clone());
+
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Error reading {}: {e}", path.display()))?;
+    let ast = powdr_parser::parse(Some(path.to_str().unwrap()), &contents)
+        .map_err(|e| e.message().to_string())?;
+    source.push_str(&contents);
+    for statement in ast.0 {
+        if let PilStatement::Include(_, include) = statement {
+            append_pil_file_source(&path.parent().unwrap().join(include), processed, source)?;
+        }
+    }
+    Ok(())
+}
+
+fn artifact_path(cache_dir: &Path, key: &str, file_name: &str) -> PathBuf {
+    cache_dir.join(key).join(file_name)
+}
+
+/// Returns the optimized PIL stored under the given key, if any.
+pub(crate) fn read_optimized_pil<T: FieldElement>(
+    cache_dir: &Path,
+    key: &str,
+) -> Option<Analyzed<T>> {
+    let path = artifact_path(cache_dir, key, OPTIMIZED_PIL_FILE_NAME);
+    if !path.exists() {
+        return None;
+    }
+    SerializedAnalyzed::deserialize_from(path.clone())
+        .and_then(|serialized| serialized.try_into())
+        .map_err(|e| log::warn!("Ignoring cached PIL in {}: {e}", path.display()))
+        .ok()
+}
+
+/// Stores the optimized PIL under the given key.
+pub(crate) fn write_optimized_pil<T: FieldElement>(cache_dir: &Path, key: &str, pil: &Analyzed<T>) {
+    store(cache_dir, key, OPTIMIZED_PIL_FILE_NAME, |path| {
+        SerializedAnalyzed::try_from(pil)?.serialize_to(path.to_path_buf())
+    })
+}
+
+/// Returns the fixed columns stored under the given key, if any.
+pub(crate) fn read_fixed_cols<T: FieldElement>(
+    cache_dir: &Path,
+    key: &str,
+) -> Option<VariablySizedColumns<T>> {
+    let path = artifact_path(cache_dir, key, FIXED_COLS_FILE_NAME);
+    let file = fs::File::open(&path).ok()?;
+    serde_cbor::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| log::warn!("Ignoring cached fixed columns in {}: {e}", path.display()))
+        .ok()
+}
+
+/// Stores the fixed columns under the given key.
+pub(crate) fn write_fixed_cols<T: FieldElement>(
+    cache_dir: &Path,
+    key: &str,
+    fixed_cols: &VariablySizedColumns<T>,
+) {
+    store(cache_dir, key, FIXED_COLS_FILE_NAME, |path| {
+        fixed_cols.write(path).map_err(|e| e.to_string())
+    })
+}
+
+/// Writes an artifact using `write`.
+/// Failures are logged but otherwise ignored, since the cache is only an optimization.
+fn store(
+    cache_dir: &Path,
+    key: &str,
+    file_name: &str,
+    write: impl FnOnce(&Path) -> Result<(), String>,
+) {
+    let target = artifact_path(cache_dir, key, file_name);
+    // Write to a temporary file first and then rename, so that concurrent processes
+    // never observe a partially written artifact.
+    let tmp_target = target.with_extension(format!("{}.tmp", std::process::id()));
+    let result = fs::create_dir_all(target.parent().unwrap())
+        .map_err(|e| e.to_string())
+        .and_then(|_| write(&tmp_target))
+        .and_then(|_| fs::rename(&tmp_target, &target).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => log::debug!("Stored {} in the artifact cache", target.display()),
+        Err(e) => {
+            log::warn!(
+                "Could not store {} in the artifact cache: {e}",
+                target.display()
+            );
+            let _ = fs::remove_file(&tmp_target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use powdr_number::{BabyBearField, GoldilocksField};
+
+    use super::*;
+
+    #[test]
+    fn cache_key_depends_on_all_inputs() {
+        let params = LinkerParams::default();
+        let key = cache_key::<GoldilocksField>("source", &params);
+        assert_eq!(key, cache_key::<GoldilocksField>("source", &params));
+        assert_ne!(key, cache_key::<GoldilocksField>("source2", &params));
+        assert_ne!(key, cache_key::<BabyBearField>("source", &params));
+        let bus_params = LinkerParams {
+            mode: powdr_linker::LinkerMode::Bus,
+            ..params
+        };
+        assert_ne!(key, cache_key::<GoldilocksField>("source", &bus_params));
+    }
+}
@@ -1,5 +1,6 @@
 //! The main powdr lib, used to compile from assembly to PIL
 
+mod artifact_cache;
 pub mod pipeline;
 pub mod test_runner;
 pub mod test_util;
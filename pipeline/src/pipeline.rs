@@ -23,7 +23,8 @@ use powdr_backend::{Backend, BackendOptions, BackendType, Proof};
 use powdr_executor::{
     constant_evaluator::{self, VariablySizedColumn},
     witgen::{
-        chain_callbacks, extract_publics, unused_query_callback, QueryCallback, WitgenCallback,
+        chain_callbacks, extract_publics, start_chrome_trace, unused_query_callback,
+        write_chrome_trace, MachineWitnessProvider, QueryCallback, WitgenCallback,
         WitgenCallbackContext, WitnessGenerator,
     },
 };
@@ -32,8 +33,8 @@ use powdr_number::{write_polys_csv_file, CsvRenderMode, FieldElement, ReadWrite}
 use powdr_schemas::SerializedAnalyzed;
 
 use crate::{
-    dict_data_to_query_callback, handle_simple_queries_callback, inputs_to_query_callback,
-    serde_data_to_query_callback,
+    artifact_cache, dict_data_to_query_callback, handle_simple_queries_callback,
+    inputs_to_query_callback, serde_data_to_query_callback,
     util::{FixedPolySet, WitnessPolySet},
 };
 use std::collections::BTreeMap;
@@ -74,6 +75,8 @@ pub struct Artifacts<T: FieldElement> {
     optimized_pil: Option<Arc<Analyzed<T>>>,
     /// Fully evaluated fixed columns.
     fixed_cols: Option<Arc<VariablySizedColumns<T>>>,
+    /// The key of the optimized PIL and fixed columns in the artifact cache.
+    artifact_cache_key: Option<String>,
     /// Generated witnesses.
     witness: Option<Arc<Columns<T>>>,
     /// Instantiated backend.
@@ -94,6 +97,10 @@ impl<R: io::Read> AsIoRead for Option<R> {
     }
 }
 
+/// Creates a fresh witness provider for a machine, for each run of witness generation.
+pub type MachineWitnessProviderFactory<T> =
+    Arc<dyn Fn() -> Box<dyn MachineWitnessProvider<T>> + Send + Sync>;
+
 /// Optional Arguments for various stages of the pipeline.
 #[derive(Default, Clone)]
 struct Arguments<T: FieldElement> {
@@ -101,6 +108,8 @@ struct Arguments<T: FieldElement> {
     external_witness_values: Vec<(String, Vec<T>)>,
     /// Callback for queries for witness generation.
     query_callback: Option<Arc<dyn QueryCallback<T>>>,
+    /// Witness providers for machines, by namespace.
+    machine_witness_providers: BTreeMap<String, MachineWitnessProviderFactory<T>>,
     /// Backend to use for proving. If None, proving will fail.
     backend: Option<BackendType>,
     /// Backend options
@@ -113,6 +122,12 @@ struct Arguments<T: FieldElement> {
     export_witness_csv: bool,
     /// Whether to export all columns (witness and constants) to a CSV file.
     export_all_columns_csv: bool,
+    /// The optional file to write a Chrome trace of witness generation to.
+    witgen_trace_file: Option<PathBuf>,
+    /// Whether to interpret the code derived for witness generation instead of compiling it.
+    use_jit_interpreter: bool,
+    /// The optional directory in which the optimized PIL and fixed columns are cached.
+    artifact_cache_dir: Option<PathBuf>,
     /// The optional setup file to use for proving.
     setup_file: Option<PathBuf>,
     /// The optional proving key file to use for proving.
@@ -169,6 +184,7 @@ impl<T: FieldElement> Clone for Artifacts<T> {
             analyzed_pil: self.analyzed_pil.clone(),
             optimized_pil: self.optimized_pil.clone(),
             fixed_cols: self.fixed_cols.clone(),
+            artifact_cache_key: self.artifact_cache_key.clone(),
             witness: self.witness.clone(),
             proof: self.proof.clone(),
             // Backend is not cloneable, so we clear it instead
@@ -316,6 +332,28 @@ impl<T: FieldElement> Pipeline<T> {
         self
     }
 
+    /// Writes a trace of witness generation in the Chrome trace event format to the given
+    /// file, which can be viewed in Perfetto or chrome://tracing.
+    pub fn with_witgen_trace_file(mut self, witgen_trace_file: Option<PathBuf>) -> Self {
+        self.arguments.witgen_trace_file = witgen_trace_file;
+        self
+    }
+
+    /// Interprets the code derived for witness generation instead of compiling it to
+    /// machine code, which does not need a Rust toolchain but runs slower.
+    pub fn with_jit_interpreter(mut self, use_jit_interpreter: bool) -> Self {
+        self.arguments.use_jit_interpreter = use_jit_interpreter;
+        self
+    }
+
+    /// Sets the directory in which the optimized PIL and fixed columns are cached
+    /// across runs, keyed by a hash of the source, the linker parameters and the field.
+    /// If `None`, caching is disabled.
+    pub fn with_artifact_cache_dir(mut self, artifact_cache_dir: Option<PathBuf>) -> Self {
+        self.arguments.artifact_cache_dir = artifact_cache_dir;
+        self
+    }
+
     pub fn add_query_callback(mut self, query_callback: Arc<dyn QueryCallback<T>>) -> Self {
         let query_callback = match self.arguments.query_callback {
             Some(old_callback) => Arc::new(chain_callbacks(old_callback, query_callback)),
@@ -325,6 +363,23 @@ impl<T: FieldElement> Pipeline<T> {
         self
     }
 
+    /// Generates the witness of the machine in the given namespace using a provider
+    /// created by `factory`, instead of the built-in witness generation.
+    pub fn with_machine_witness_provider(
+        mut self,
+        namespace: &str,
+        factory: MachineWitnessProviderFactory<T>,
+    ) -> Self {
+        assert!(
+            self.arguments
+                .machine_witness_providers
+                .insert(namespace.to_string(), factory)
+                .is_none(),
+            "Duplicate witness provider for machine: {namespace}"
+        );
+        self
+    }
+
     /// Adds data to the initial memory given by the prover.
     /// This is a more efficient method of passing bytes from the host
     /// to the guest.
@@ -739,15 +794,18 @@ impl<T: FieldElement> Pipeline<T> {
                 let path = path.clone();
                 let path_str = path.as_ref().map(|p| p.to_str().unwrap());
 
-                let parsed_asm =
-                    powdr_parser::parse_asm(path_str, asm_string).unwrap_or_else(|err| {
-                        eprintln!(
-                            "Error parsing .asm file:{}",
-                            path_str.map(|p| format!(" {p}")).unwrap_or_default()
-                        );
+                let (parsed_asm, errors) =
+                    powdr_parser::parse_asm_with_recovery(path_str, asm_string);
+                if !errors.is_empty() {
+                    eprintln!(
+                        "Error parsing .asm file:{}",
+                        path_str.map(|p| format!(" {p}")).unwrap_or_default()
+                    );
+                    for err in errors {
                         err.output_to_stderr();
-                        panic!();
-                    });
+                    }
+                    panic!();
+                }
 
                 (path.clone(), parsed_asm)
             });
@@ -958,16 +1016,61 @@ impl<T: FieldElement> Pipeline<T> {
         Ok(self.artifact.analyzed_pil.as_ref().unwrap())
     }
 
+    /// Returns the key of the optimized PIL and fixed columns in the artifact cache,
+    /// or None if the cache is disabled or the pipeline was not created from source.
+    fn compute_artifact_cache_key(&mut self) -> Result<Option<String>, Vec<String>> {
+        if self.arguments.artifact_cache_dir.is_none() {
+            return Ok(None);
+        }
+        if self.artifact.artifact_cache_key.is_none() {
+            let source =
+                if self.artifact.asm_string.is_some() || self.artifact.asm_file_path.is_some() {
+                    // Hashing the resolved module tree also covers all imported modules.
+                    self.compute_resolved_module_tree()?.to_string()
+                } else if let Some(ref pil_string) = self.artifact.pil_string {
+                    pil_string.clone()
+                } else if let Some(ref pil_file) = self.artifact.pil_file_path {
+                    artifact_cache::pil_file_source(pil_file).map_err(|e| vec![e])?
+                } else {
+                    return Ok(None);
+                };
+            self.artifact.artifact_cache_key = Some(artifact_cache::cache_key::<T>(
+                &source,
+                &self.arguments.linker_params,
+            ));
+        }
+        Ok(self.artifact.artifact_cache_key.clone())
+    }
+
     pub fn compute_optimized_pil(&mut self) -> Result<Arc<Analyzed<T>>, Vec<String>> {
         if let Some(ref optimized_pil) = self.artifact.optimized_pil {
             return Ok(optimized_pil.clone());
         }
 
-        self.compute_analyzed_pil()?;
-        let analyzed_pil = self.artifact.analyzed_pil.take().unwrap();
+        let cache_key = self.compute_artifact_cache_key()?;
+        let cached = cache_key.as_ref().and_then(|key| {
+            let cache_dir = self.arguments.artifact_cache_dir.as_ref().unwrap();
+            artifact_cache::read_optimized_pil(cache_dir, key)
+        });
 
-        self.log("Optimizing pil...");
-        let optimized = powdr_pilopt::optimize(analyzed_pil);
+        let optimized = match cached {
+            Some(optimized) => {
+                self.log("Using cached optimized pil.");
+                optimized
+            }
+            None => {
+                self.compute_analyzed_pil()?;
+                let analyzed_pil = self.artifact.analyzed_pil.take().unwrap();
+
+                self.log("Optimizing pil...");
+                let optimized = powdr_pilopt::optimize(analyzed_pil);
+                if let Some(key) = &cache_key {
+                    let cache_dir = self.arguments.artifact_cache_dir.as_ref().unwrap();
+                    artifact_cache::write_optimized_pil(cache_dir, key, &optimized);
+                }
+                optimized
+            }
+        };
         self.maybe_write_pil(&optimized, "_opt")?;
         self.maybe_write_pil_object(&optimized, "_opt")?;
 
@@ -987,13 +1090,32 @@ impl<T: FieldElement> Pipeline<T> {
 
         let pil = self.compute_optimized_pil()?;
 
-        self.log("Evaluating fixed columns...");
-        let start = Instant::now();
-        let fixed_cols = constant_evaluator::generate(&pil);
-        self.log(&format!(
-            "Fixed column generation took {}s",
-            start.elapsed().as_secs_f32()
-        ));
+        let cache_key = self.compute_artifact_cache_key()?;
+        let cached = cache_key.as_ref().and_then(|key| {
+            let cache_dir = self.arguments.artifact_cache_dir.as_ref().unwrap();
+            artifact_cache::read_fixed_cols(cache_dir, key)
+        });
+
+        let fixed_cols = match cached {
+            Some(fixed_cols) => {
+                self.log("Using cached fixed columns.");
+                fixed_cols
+            }
+            None => {
+                self.log("Evaluating fixed columns...");
+                let start = Instant::now();
+                let fixed_cols = constant_evaluator::generate(&pil);
+                self.log(&format!(
+                    "Fixed column generation took {}s",
+                    start.elapsed().as_secs_f32()
+                ));
+                if let Some(key) = &cache_key {
+                    let cache_dir = self.arguments.artifact_cache_dir.as_ref().unwrap();
+                    artifact_cache::write_fixed_cols(cache_dir, key, &fixed_cols);
+                }
+                fixed_cols
+            }
+        };
         self.maybe_write_constants(&fixed_cols)?;
 
         self.artifact.fixed_cols = Some(Arc::new(fixed_cols));
@@ -1049,9 +1171,29 @@ impl<T: FieldElement> Pipeline<T> {
                 .query_callback
                 .clone()
                 .unwrap_or_else(|| Arc::new(unused_query_callback()));
-            let witness = WitnessGenerator::new(&pil, &fixed_cols, query_callback.borrow())
-                .with_external_witness_values(&external_witness_values)
-                .generate();
+            if self.arguments.witgen_trace_file.is_some() {
+                start_chrome_trace();
+            }
+            let witness = self
+                .arguments
+                .machine_witness_providers
+                .iter()
+                .fold(
+                    WitnessGenerator::new(&pil, &fixed_cols, query_callback.borrow())
+                        .with_external_witness_values(&external_witness_values)
+                        .with_jit_interpreter(self.arguments.use_jit_interpreter),
+                    |witgen, (namespace, factory)| {
+                        witgen.with_machine_witness_provider(namespace, factory())
+                    },
+                )
+                .try_generate()
+                .map_err(|e| vec![format!("Witness generation failed: {e}")])?;
+            if let Some(path) = &self.arguments.witgen_trace_file {
+                fs::File::create(path)
+                    .and_then(|file| write_chrome_trace(BufWriter::new(file)))
+                    .map_err(|e| vec![format!("Error writing witgen trace: {e}")])?;
+                self.log(&format!("Wrote witgen trace to {}", path.display()));
+            }
 
             self.log(&format!(
                 "Witness generation took {}s",
@@ -1083,7 +1225,8 @@ impl<T: FieldElement> Pipeline<T> {
         let ctx = WitgenCallbackContext::new(
             self.compute_fixed_cols()?,
             self.arguments.query_callback.as_ref().cloned(),
-        );
+        )
+        .with_jit_interpreter(self.arguments.use_jit_interpreter);
         Ok(WitgenCallback::new(Arc::new(
             move |pil, current_witness, challenges, stage| {
                 ctx.next_stage_witness(pil, current_witness, challenges, stage)
@@ -1147,7 +1290,10 @@ impl<T: FieldElement> Pipeline<T> {
                 vkey_app.as_io_read(),
                 self.arguments.backend_options.clone(),
             )
-            .unwrap();
+            .map_err(|e| match e {
+                powdr_backend::Error::BackendError(e) => vec![e],
+                e => vec![e.to_string()],
+            })?;
         self.log(&format!("Setup took {}s", start.elapsed().as_secs_f32()));
 
         self.artifact.backend = Some(backend);
@@ -1235,7 +1381,7 @@ impl<T: FieldElement> Pipeline<T> {
             .export_verification_key(&mut writer)
             .map_err(|e| match e {
                 powdr_backend::Error::BackendError(e) => vec![e],
-                _ => panic!(),
+                e => vec![e.to_string()],
             })
     }
 
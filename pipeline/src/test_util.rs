@@ -634,8 +634,33 @@ pub fn test_stwo(file_name: &str, inputs: Vec<Mersenne31Field>) {
         .iter()
         .map(|(_name, v)| v.expect("all publics should be known since we created a proof"))
         .collect();
+    pipeline.verify(&proof, &[publics.clone()]).unwrap();
+
+    // Export the verification key and verify the proof with a new pipeline that only
+    // uses the verification key, without a setup.
+    let vkey_file_path = export_stwo_verification_key(&mut pipeline);
+    let mut pipeline = Pipeline::default()
+        .with_tmp_output()
+        .from_file(resolve_test_file(file_name))
+        .with_backend(backend, None)
+        .with_vkey_file(Some(vkey_file_path));
     pipeline.verify(&proof, &[publics]).unwrap();
 }
+
+/// Exports the verification key of a stwo pipeline to its output directory and returns its path.
+#[cfg(feature = "stwo")]
+fn export_stwo_verification_key(pipeline: &mut Pipeline<Mersenne31Field>) -> PathBuf {
+    let vkey_file_path = pipeline
+        .output_dir()
+        .as_ref()
+        .unwrap()
+        .join("verification_key.bin");
+    powdr_number::buffered_write_file(&vkey_file_path, |writer| {
+        pipeline.export_verification_key(writer).unwrap()
+    })
+    .unwrap();
+    vkey_file_path
+}
 #[cfg(feature = "stwo")]
 pub fn assert_proofs_fail_for_invalid_witnesses_stwo(
     file_name: &str,
@@ -664,6 +689,11 @@ pub fn test_stwo_pipeline(pipeline: Pipeline<Mersenne31Field>) {
         .iter()
         .map(|(_name, v)| v.expect("all publics should be known since we created a proof"))
         .collect();
+    pipeline.verify(&proof, &[publics.clone()]).unwrap();
+
+    // Verify the proof again, using the exported verification key
+    let vkey_file_path = export_stwo_verification_key(&mut pipeline);
+    let mut pipeline = pipeline.with_vkey_file(Some(vkey_file_path));
     pipeline.verify(&proof, &[publics]).unwrap();
 }
 
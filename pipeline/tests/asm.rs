@@ -45,6 +45,15 @@ fn challenges_asm() {
     test_stwo_pipeline(pipeline);
 }
 
+#[test]
+fn challenges_three_stages_asm() {
+    let f = "asm/challenges_three_stages.asm";
+    let pipeline = make_simple_prepared_pipeline::<GoldilocksField>(f, LinkerMode::Bus);
+    test_mock_backend(pipeline);
+    let pipeline = make_simple_prepared_pipeline::<Mersenne31Field>(f, LinkerMode::Bus);
+    test_stwo_pipeline(pipeline);
+}
+
 #[test]
 fn simple_sum_asm() {
     let f = "asm/simple_sum.asm";
@@ -194,6 +203,28 @@ fn block_to_block_with_bus_monolithic() {
     test_plonky3_pipeline(pipeline);
 }
 
+#[cfg(feature = "plonky3")]
+#[test]
+fn block_to_block_with_bus_composite_memory_budget() {
+    use powdr_backend::BackendType;
+
+    let f = "asm/block_to_block_with_bus.asm";
+    // With a memory budget, the machines are proven concurrently.
+    let mut pipeline = make_simple_prepared_pipeline::<GoldilocksField>(f, LinkerMode::Bus)
+        .with_backend(
+            BackendType::Plonky3Composite,
+            Some("memory_budget=1G".to_string()),
+        );
+    let proof = pipeline.compute_proof().cloned().unwrap();
+    let publics = pipeline
+        .publics()
+        .unwrap()
+        .into_iter()
+        .map(|(_, v)| v.unwrap())
+        .collect();
+    pipeline.verify(&proof, &[publics]).unwrap();
+}
+
 #[test]
 fn block_to_block_with_bus_different_sizes() {
     let f = "asm/block_to_block_with_bus_different_sizes.asm";
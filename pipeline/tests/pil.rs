@@ -1,4 +1,9 @@
-use powdr_linker::LinkerMode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use powdr_ast::analyzed::DegreeRange;
+use powdr_executor::witgen::{LookupCell, MachineWitnessProvider};
+use powdr_linker::{DegreeMode, LinkerMode, LinkerParams};
 use powdr_number::GoldilocksField;
 use powdr_pipeline::{
     test_util::{
@@ -141,6 +146,238 @@ fn fibonacci_invalid_witness() {
     assert_proofs_fail_for_invalid_witnesses_pilcom(f, &witness);
 }
 
+/// Returns a witness for `pil/fibonacci.pil` with the given values of `x` and `y`.
+fn fibonacci_witness(x: [u64; 4], y: [u64; 4]) -> Vec<(String, Vec<GoldilocksField>)> {
+    vec![
+        (
+            "Fibonacci::x".to_string(),
+            x.map(GoldilocksField::from).to_vec(),
+        ),
+        (
+            "Fibonacci::y".to_string(),
+            y.map(GoldilocksField::from).to_vec(),
+        ),
+    ]
+}
+
+#[test]
+fn fibonacci_invalid_witness_mock_report() {
+    let f = "pil/fibonacci.pil";
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    // The following constraint should fail in row 1:
+    //     (1-ISLAST) * (x' - y) = 0;
+    let witness = fibonacci_witness([1, 1, 10, 3], [1, 2, 3, 13]);
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .from_file(resolve_test_file(f))
+        .set_witness(witness)
+        .with_backend(powdr_backend::BackendType::Mock, None);
+    assert!(pipeline.compute_proof().is_err());
+
+    let report_path = pipeline
+        .output_dir()
+        .as_ref()
+        .unwrap()
+        .join("mock_report.json");
+    let report: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report_path).unwrap()).unwrap();
+    let failures = report["polynomial_constraints"].as_array().unwrap();
+    let failure = failures.iter().find(|failure| failure["row"] == 1).unwrap();
+    assert_eq!(failure["machine"], "Fibonacci");
+    assert_eq!(failure["columns"]["Fibonacci::x"]["next"], "10");
+    assert_eq!(failure["columns"]["Fibonacci::y"]["current"], "2");
+    assert!(report["connections"].as_array().unwrap().is_empty());
+    assert!(report["bus_interactions"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn fibonacci_invalid_witness_mock_shrink() {
+    let f = "pil/fibonacci.pil";
+    use powdr_number::read_polys_csv_file;
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    // The following constraint should fail in row 1:
+    //     (1-ISLAST) * (x' - y) = 0;
+    let witness = fibonacci_witness([1, 1, 10, 3], [1, 2, 3, 13]);
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .from_file(resolve_test_file(f))
+        .set_witness(witness)
+        .with_backend(powdr_backend::BackendType::Mock, Some("shrink".to_string()));
+    assert!(pipeline.compute_proof().is_err());
+
+    // The reproducer only contains the two rows needed to evaluate the failing constraint.
+    let output_dir = pipeline.output_dir().as_ref().unwrap();
+    let witness = read_polys_csv_file::<GoldilocksField>(
+        std::fs::File::open(output_dir.join("mock_repro_witness.csv")).unwrap(),
+    );
+    let x = &witness
+        .iter()
+        .find(|(name, _)| name == "Fibonacci::x")
+        .unwrap()
+        .1;
+    assert_eq!(x, &[1, 10].map(GoldilocksField::from));
+
+    // It fails in isolation.
+    assert!(Pipeline::<GoldilocksField>::default()
+        .from_file(output_dir.join("mock_repro.pil"))
+        .set_witness(witness)
+        .with_backend(powdr_backend::BackendType::Mock, None)
+        .compute_proof()
+        .is_err());
+}
+
+#[test]
+fn mock_shrink_requires_output_dir() {
+    let f = "pil/fibonacci.pil";
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    let witness = fibonacci_witness([1, 1, 10, 3], [1, 2, 3, 13]);
+    let errors = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file(f))
+        .set_witness(witness)
+        .with_backend(powdr_backend::BackendType::Mock, Some("shrink".to_string()))
+        .compute_proof()
+        .unwrap_err();
+    assert!(errors[0].contains("requires an output directory"));
+}
+
+#[test]
+fn artifact_cache() {
+    let f = "pil/fibonacci.pil";
+    use powdr_executor::constant_evaluator::{get_uniquely_sized_cloned, VariablySizedColumn};
+    use powdr_number::ReadWrite;
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    let cache_dir = mktemp::Temp::new_dir().unwrap();
+    let run = || {
+        let mut pipeline = Pipeline::<GoldilocksField>::default()
+            .from_file(resolve_test_file(f))
+            .with_artifact_cache_dir(Some(cache_dir.to_path_buf()));
+        let fixed_cols = pipeline.compute_fixed_cols().unwrap();
+        (
+            pipeline.optimized_pil().unwrap().to_string(),
+            get_uniquely_sized_cloned(&fixed_cols).unwrap(),
+        )
+    };
+
+    let (pil, fixed_cols) = run();
+    let entries = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].join("optimized.pilo").exists());
+    assert!(entries[0].join("constants.bin").exists());
+
+    // Change the cached fixed columns, so that we can tell whether
+    // the second run reads them from the cache.
+    let mut modified_fixed_cols = fixed_cols.clone();
+    modified_fixed_cols[0].1[0] += GoldilocksField::from(1);
+    modified_fixed_cols
+        .iter()
+        .map(|(name, values)| (name.clone(), VariablySizedColumn::from(values.clone())))
+        .collect::<Vec<_>>()
+        .write(&entries[0].join("constants.bin"))
+        .unwrap();
+
+    // The second run reads both artifacts from the cache.
+    let (cached_pil, cached_fixed_cols) = run();
+    assert_eq!(pil, cached_pil);
+    assert_eq!(modified_fixed_cols, cached_fixed_cols);
+
+    // A different linker mode results in a different cache entry.
+    Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file(f))
+        .with_linker_params(powdr_linker::LinkerParams {
+            mode: LinkerMode::Bus,
+            ..Default::default()
+        })
+        .with_artifact_cache_dir(Some(cache_dir.to_path_buf()))
+        .compute_optimized_pil()
+        .unwrap();
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn fibonacci_mock_fuzz() {
+    let f = "pil/fibonacci.pil";
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    let witness = fibonacci_witness([1, 1, 2, 3], [1, 2, 3, 5]);
+    // All columns are fully constrained, so no mutation is accepted.
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .from_file(resolve_test_file(f))
+        .set_witness(witness)
+        .with_backend(
+            powdr_backend::BackendType::Mock,
+            Some("fuzz=100".to_string()),
+        );
+    pipeline.compute_proof().unwrap();
+}
+
+#[test]
+fn mock_invalid_fuzz_option() {
+    let f = "pil/fibonacci.pil";
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    let witness = fibonacci_witness([1, 1, 2, 3], [1, 2, 3, 5]);
+    let errors = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file(f))
+        .set_witness(witness)
+        .with_backend(
+            powdr_backend::BackendType::Mock,
+            Some("fuzz=many".to_string()),
+        )
+        .compute_proof()
+        .unwrap_err();
+    assert_eq!(errors, vec!["Invalid fuzz option: fuzz=many".to_string()]);
+}
+
+#[test]
+fn underconstrained_mock_fuzz() {
+    // Since x is 1 in all rows, the second constraint does not constrain y.
+    let pil = r#"
+namespace Main(4);
+    col witness x, y;
+    x' = x;
+    (x - 1) * y = 0;
+"#;
+    let witness = vec![
+        ("Main::x".to_string(), vec![GoldilocksField::from(1); 4]),
+        ("Main::y".to_string(), vec![GoldilocksField::from(0); 4]),
+    ];
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .from_pil_string(pil.to_string())
+        .set_witness(witness)
+        .with_backend(
+            powdr_backend::BackendType::Mock,
+            Some("fuzz=100".to_string()),
+        );
+    assert!(pipeline.compute_proof().is_err());
+
+    let report_path = pipeline
+        .output_dir()
+        .as_ref()
+        .unwrap()
+        .join("mock_fuzz.json");
+    let accepted: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(report_path).unwrap()).unwrap();
+    let accepted = accepted.as_array().unwrap();
+    assert!(!accepted.is_empty());
+    // Only mutations of y are accepted.
+    assert!(accepted.iter().all(|mutation| {
+        mutation["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|cell| cell["column"] == "Main::y")
+    }));
+}
+
 #[test]
 fn constant_in_identity() {
     let f = "pil/constant_in_identity.pil";
@@ -270,6 +507,45 @@ fn stwo_fibonacci() {
     test_stwo(f, Default::default());
 }
 
+#[test]
+fn stwo_fibonacci_with_public() {
+    let f = "pil/fibonacci_with_public.pil";
+    test_stwo(f, Default::default());
+}
+
+#[cfg(feature = "stwo")]
+#[test]
+fn stwo_fibonacci_with_wrong_public() {
+    use powdr_number::Mersenne31Field;
+    use powdr_pipeline::test_util::resolve_test_file;
+
+    let f = "pil/fibonacci_with_public.pil";
+    let mut pipeline = Pipeline::<Mersenne31Field>::default()
+        .from_file(resolve_test_file(f))
+        .with_backend(powdr_backend::BackendType::Stwo, None);
+    let proof = pipeline.compute_proof().cloned().unwrap();
+    // The public value is y(3) = 5.
+    pipeline
+        .verify(&proof, &[vec![Mersenne31Field::from(5)]])
+        .unwrap();
+    assert!(pipeline
+        .verify(&proof, &[vec![Mersenne31Field::from(4)]])
+        .is_err());
+}
+
+#[cfg(feature = "stwo")]
+#[test]
+fn stwo_no_verification_key_for_variable_sizes() {
+    use powdr_number::Mersenne31Field;
+    let pil = "namespace Main(4..8); pol commit x; x * (x - 1) = 0;";
+    let errors = Pipeline::<Mersenne31Field>::default()
+        .from_pil_string(pil.to_string())
+        .with_backend(powdr_backend::BackendType::Stwo, None)
+        .export_verification_key(Vec::new())
+        .unwrap_err();
+    assert!(errors[0].contains("the size of the following machines is not fixed: Main"));
+}
+
 #[test]
 fn stwo_fixed_columns() {
     let f = "pil/fixed_columns.pil";
@@ -394,6 +670,106 @@ fn vm_to_block_dynamic_length() {
     regular_test_gl(f, Default::default());
 }
 
+/// Generates the witness of the Add machine by hand, recording the calls to it.
+#[derive(Default)]
+struct AddWitnessProvider {
+    calls: Vec<[GoldilocksField; 3]>,
+}
+
+impl MachineWitnessProvider<GoldilocksField> for AddWitnessProvider {
+    fn can_process_call(&self, _identity_id: u64, known_arguments: &[bool]) -> bool {
+        known_arguments[0] && known_arguments[1]
+    }
+
+    fn process_call(
+        &mut self,
+        _identity_id: u64,
+        arguments: &mut [LookupCell<'_, GoldilocksField>],
+    ) -> Result<(), String> {
+        let (a, b) = match (&arguments[0], &arguments[1]) {
+            (LookupCell::Input(a), LookupCell::Input(b)) => (**a, **b),
+            _ => return Err("Inputs need to be known".to_string()),
+        };
+        let c = a + b;
+        match &mut arguments[2] {
+            LookupCell::Input(value) if **value != c => {
+                return Err(format!("Expected {a} + {b} = {c}, got {value}"))
+            }
+            LookupCell::Input(_) => {}
+            LookupCell::Output(value) => **value = c,
+        }
+        self.calls.push([a, b, c]);
+        Ok(())
+    }
+
+    fn take_witness(&mut self, degree_range: DegreeRange) -> HashMap<String, Vec<GoldilocksField>> {
+        let size = degree_range.fit(self.calls.len().next_power_of_two() as u64) as usize;
+        let column = |f: fn(&[GoldilocksField; 3]) -> GoldilocksField| {
+            self.calls
+                .iter()
+                .map(f)
+                .chain(std::iter::repeat(0.into()))
+                .take(size)
+                .collect::<Vec<_>>()
+        };
+        [
+            ("Add::sel", column(|_| 1.into())),
+            ("Add::a", column(|call| call[0])),
+            ("Add::b", column(|call| call[1])),
+            ("Add::c", column(|call| call[2])),
+        ]
+        .into_iter()
+        .map(|(name, values)| (name.to_string(), values))
+        .collect()
+    }
+}
+
+#[test]
+fn external_machine_witgen() {
+    use powdr_pipeline::test_util::resolve_test_file;
+    let f = "pil/external_machine_witgen.pil";
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .with_linker_params(LinkerParams {
+            mode: LinkerMode::Bus,
+            degree_mode: DegreeMode::Vadcop,
+        })
+        .from_file(resolve_test_file(f))
+        .with_machine_witness_provider("Add", Arc::new(|| Box::new(AddWitnessProvider::default())));
+    let witness = pipeline.compute_witness().unwrap();
+    let column = |name: &str| &witness.iter().find(|(n, _)| n == name).unwrap().1;
+    assert_eq!(
+        column("main::C").iter().step_by(2).collect::<Vec<_>>(),
+        [0, 8, 16, 24]
+            .map(GoldilocksField::from)
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        column("Add::c"),
+        &[0, 8, 16, 24, 0, 0, 0, 0]
+            .map(GoldilocksField::from)
+            .to_vec()
+    );
+    test_mock_backend(pipeline);
+}
+
+#[test]
+fn external_machine_witgen_unknown_namespace() {
+    use powdr_pipeline::test_util::resolve_test_file;
+    let f = "pil/external_machine_witgen.pil";
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .with_linker_params(LinkerParams {
+            mode: LinkerMode::Bus,
+            degree_mode: DegreeMode::Vadcop,
+        })
+        .from_file(resolve_test_file(f))
+        .with_machine_witness_provider("Mul", Arc::new(|| Box::new(AddWitnessProvider::default())));
+    let err = pipeline.compute_witness().unwrap_err();
+    assert!(err[0].contains("witness providers of namespaces: Mul"));
+}
+
 #[test]
 fn serialize_deserialize_optimized_pil() {
     let f = "pil/fibonacci.pil";
@@ -7,6 +7,7 @@ mod folder;
 mod params;
 mod proof;
 mod prover;
+mod recursion;
 mod symbolic_builder;
 mod traits;
 mod verifier;
@@ -16,5 +17,6 @@ pub use folder::*;
 pub use params::*;
 pub use proof::*;
 pub use prover::*;
+pub use recursion::*;
 pub use traits::*;
 pub use verifier::*;
@@ -4,7 +4,7 @@
 //! (But using Poseidon2 instead of Poseidon)
 
 use crate::{
-    params::{Challenger, FieldElementMap, Plonky3Field},
+    params::{Challenger, FieldElementMap, FriParameters, Plonky3Field},
     poseidon2::goldilocks::{Permutation, PERM, WIDTH},
 };
 use p3_challenger::DuplexChallenger;
@@ -43,10 +43,6 @@ type ChallengeMmcs = ExtensionMmcs<Goldilocks, FriChallenge, ValMmcs>;
 type Dft = Radix2DitParallel<Goldilocks>;
 type MyPcs = TwoAdicFriPcs<Goldilocks, Dft, ValMmcs, ChallengeMmcs>;
 
-const FRI_LOG_BLOWUP: usize = 1;
-const FRI_NUM_QUERIES: usize = 100;
-const FRI_PROOF_OF_WORK_BITS: usize = 16;
-
 impl FieldElementMap for GoldilocksField {
     type Config = StarkConfig<MyPcs, FriChallenge, FriChallenger>;
 
@@ -62,7 +58,7 @@ impl FieldElementMap for GoldilocksField {
         FriChallenger::new(PERM.clone())
     }
 
-    fn get_config() -> Self::Config {
+    fn get_config(fri_parameters: &FriParameters) -> Self::Config {
         let hash = Hash::new(PERM.clone());
 
         let compress = Compress::new(PERM.clone());
@@ -74,9 +70,9 @@ impl FieldElementMap for GoldilocksField {
         let dft = Dft::default();
 
         let fri_config = FriConfig {
-            log_blowup: FRI_LOG_BLOWUP,
-            num_queries: FRI_NUM_QUERIES,
-            proof_of_work_bits: FRI_PROOF_OF_WORK_BITS,
+            log_blowup: fri_parameters.log_blowup,
+            num_queries: fri_parameters.num_queries,
+            proof_of_work_bits: fri_parameters.proof_of_work_bits,
             mmcs: challenge_mmcs,
         };
 
@@ -84,11 +80,4 @@ impl FieldElementMap for GoldilocksField {
 
         Self::Config::new(pcs)
     }
-
-    fn degree_bound() -> usize {
-        // Currently, Plonky3 can't compute evaluations other than those already computed for the
-        // FRI commitment. This introduces the following dependency between the blowup factor and
-        // the degree bound:
-        (1 << FRI_LOG_BLOWUP) + 1
-    }
 }
@@ -4,7 +4,7 @@
 
 use lazy_static::lazy_static;
 
-use crate::params::{Challenger, FieldElementMap, Plonky3Field};
+use crate::params::{Challenger, FieldElementMap, FriParameters, Plonky3Field};
 use p3_challenger::DuplexChallenger;
 use p3_commit::ExtensionMmcs;
 use p3_dft::Radix2DitParallel;
@@ -49,10 +49,6 @@ type ChallengeMmcs = ExtensionMmcs<KoalaBear, FriChallenge, ValMmcs>;
 type Dft = Radix2DitParallel<KoalaBear>;
 type MyPcs = TwoAdicFriPcs<KoalaBear, Dft, ValMmcs, ChallengeMmcs>;
 
-const FRI_LOG_BLOWUP: usize = 1;
-const FRI_NUM_QUERIES: usize = 100;
-const FRI_PROOF_OF_WORK_BITS: usize = 16;
-
 lazy_static! {
     static ref ROUNDS: (usize, usize) = poseidon2_round_numbers_128::<KoalaBear>(WIDTH, D);
     static ref ROUNDS_F: usize = ROUNDS.0;
@@ -81,7 +77,7 @@ impl FieldElementMap for KoalaBearField {
         FriChallenger::new(PERM_BB.clone())
     }
 
-    fn get_config() -> Self::Config {
+    fn get_config(fri_parameters: &FriParameters) -> Self::Config {
         let hash = Hash::new(PERM_BB.clone());
 
         let compress = Compress::new(PERM_BB.clone());
@@ -93,9 +89,9 @@ impl FieldElementMap for KoalaBearField {
         let dft = Dft::default();
 
         let fri_config = FriConfig {
-            log_blowup: FRI_LOG_BLOWUP,
-            num_queries: FRI_NUM_QUERIES,
-            proof_of_work_bits: FRI_PROOF_OF_WORK_BITS,
+            log_blowup: fri_parameters.log_blowup,
+            num_queries: fri_parameters.num_queries,
+            proof_of_work_bits: fri_parameters.proof_of_work_bits,
             mmcs: challenge_mmcs,
         };
 
@@ -103,11 +99,4 @@ impl FieldElementMap for KoalaBearField {
 
         Self::Config::new(pcs)
     }
-
-    fn degree_bound() -> usize {
-        // Currently, Plonky3 can't compute evaluations other than those already computed for the
-        // FRI commitment. This introduces the following dependency between the blowup factor and
-        // the degree bound:
-        (1 << FRI_LOG_BLOWUP) + 1
-    }
 }
@@ -6,7 +6,7 @@ use core::marker::PhantomData;
 
 use lazy_static::lazy_static;
 
-use crate::params::{poseidon2, Challenger, FieldElementMap, Plonky3Field};
+use crate::params::{poseidon2, Challenger, FieldElementMap, FriParameters, Plonky3Field};
 use p3_challenger::DuplexChallenger;
 use p3_circle::CirclePcs;
 use p3_commit::ExtensionMmcs;
@@ -48,10 +48,6 @@ type ValMmcs = MerkleTreeMmcs<
 type ChallengeMmcs = ExtensionMmcs<Mersenne31, FriChallenge, ValMmcs>;
 type Pcs = CirclePcs<Mersenne31, ValMmcs, ChallengeMmcs>;
 
-const FRI_LOG_BLOWUP: usize = 1;
-const FRI_NUM_QUERIES: usize = 100;
-const FRI_PROOF_OF_WORK_BITS: usize = 16;
-
 lazy_static! {
     static ref ROUNDS: (usize, usize) = poseidon2_round_numbers_128::<Mersenne31>(WIDTH, D);
     static ref ROUNDS_F: usize = ROUNDS.0;
@@ -80,7 +76,7 @@ impl FieldElementMap for Mersenne31Field {
         FriChallenger::new(PERM_M31.clone())
     }
 
-    fn get_config() -> Self::Config {
+    fn get_config(fri_parameters: &FriParameters) -> Self::Config {
         let hash = Hash::new(PERM_M31.clone());
 
         let compress = Compress::new(PERM_M31.clone());
@@ -90,9 +86,9 @@ impl FieldElementMap for Mersenne31Field {
         let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
 
         let fri_config = FriConfig {
-            log_blowup: FRI_LOG_BLOWUP,
-            num_queries: FRI_NUM_QUERIES,
-            proof_of_work_bits: FRI_PROOF_OF_WORK_BITS,
+            log_blowup: fri_parameters.log_blowup,
+            num_queries: fri_parameters.num_queries,
+            proof_of_work_bits: fri_parameters.proof_of_work_bits,
             mmcs: challenge_mmcs,
         };
 
@@ -104,11 +100,4 @@ impl FieldElementMap for Mersenne31Field {
 
         Self::Config::new(pcs)
     }
-
-    fn degree_bound() -> usize {
-        // Currently, Plonky3 can't compute evaluations other than those already computed for the
-        // FRI commitment. This introduces the following dependency between the blowup factor and
-        // the degree bound:
-        (1 << FRI_LOG_BLOWUP) + 1
-    }
 }
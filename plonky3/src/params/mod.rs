@@ -6,6 +6,7 @@ pub mod poseidon2;
 
 use p3_uni_stark::StarkGenericConfig;
 use powdr_number::FieldElement;
+use serde::{Deserialize, Serialize};
 
 use p3_commit::PolynomialSpace;
 
@@ -18,6 +19,51 @@ pub type Challenger<T> = <<T as FieldElementMap>::Config as StarkGenericConfig>:
 pub type ProverData<F> = <Pcs<F> as p3_commit::Pcs<Challenge<F>, Challenger<F>>>::ProverData;
 pub type Commitment<F> = <Pcs<F> as p3_commit::Pcs<Challenge<F>, Challenger<F>>>::Commitment;
 
+/// The parameters of FRI, which trade off proving time, proof size and security.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriParameters {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+impl FriParameters {
+    /// Fast proving for development and tests. Not secure.
+    pub const FAST_DEV: Self = Self {
+        log_blowup: 1,
+        num_queries: 16,
+        proof_of_work_bits: 0,
+    };
+
+    /// At least 100 bits of conjectured security.
+    pub const STANDARD: Self = Self {
+        log_blowup: 1,
+        num_queries: 100,
+        proof_of_work_bits: 16,
+    };
+
+    /// A larger blowup factor, at the cost of twice the proving time.
+    pub const CONSERVATIVE: Self = Self {
+        log_blowup: 2,
+        num_queries: 100,
+        proof_of_work_bits: 16,
+    };
+
+    /// The maximum degree of constraints that can be proven with these parameters.
+    pub fn degree_bound(&self) -> usize {
+        // Currently, Plonky3 can't compute evaluations other than those already computed for the
+        // FRI commitment. This introduces the following dependency between the blowup factor and
+        // the degree bound:
+        (1 << self.log_blowup) + 1
+    }
+}
+
+impl Default for FriParameters {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
 pub trait FieldElementMap: FieldElement
 where
     ProverData<Self>: Send,
@@ -31,7 +77,5 @@ where
 
     fn get_challenger() -> Challenger<Self>;
 
-    fn get_config() -> Self::Config;
-
-    fn degree_bound() -> usize;
+    fn get_config(fri_parameters: &FriParameters) -> Self::Config;
 }
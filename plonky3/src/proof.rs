@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 
 use p3_uni_stark::{StarkGenericConfig, Val};
 
+use crate::FriParameters;
+
 pub type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
     <SC as StarkGenericConfig>::Challenge,
     <SC as StarkGenericConfig>::Challenger,
@@ -26,6 +28,8 @@ pub struct Proof<SC: StarkGenericConfig> {
     pub(crate) commitments: Commitments<Com<SC>>,
     pub(crate) opened_values: OpenedValues<SC::Challenge>,
     pub(crate) opening_proof: PcsProof<SC>,
+    /// The FRI parameters the proof was generated with
+    pub(crate) fri_parameters: FriParameters,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,6 +59,8 @@ pub struct StageOpenedValues<Challenge> {
 pub struct StarkProvingKey<SC: StarkGenericConfig> {
     // for each table, the preprocessed data
     pub preprocessed: BTreeMap<String, TableProvingKeyCollection<SC>>,
+    // the FRI parameters the preprocessed data was committed with
+    pub fri_parameters: FriParameters,
 }
 
 /// For each possible size, the commitment and prover data
@@ -75,6 +81,8 @@ pub struct TableProvingKey<SC: StarkGenericConfig> {
 pub struct StarkVerifyingKey<SC: StarkGenericConfig> {
     // for each table, for each possible size, the commitment
     pub preprocessed: BTreeMap<String, TableVerifyingKeyCollection<SC>>,
+    // the FRI parameters the preprocessed data was committed with
+    pub fri_parameters: FriParameters,
 }
 
 pub struct ProcessedStage<SC: StarkGenericConfig> {
@@ -16,7 +16,7 @@ use p3_util::log2_strict_usize;
 use tracing::{info_span, instrument};
 
 use crate::circuit_builder::{generate_matrix, PowdrCircuit, PowdrTable};
-use crate::params::{Challenge, Challenger, Pcs};
+use crate::params::{Challenge, Challenger, FriParameters, Pcs};
 use crate::proof::{OpenedValues, StageOpenedValues};
 use crate::symbolic_builder::{
     get_log_quotient_degree, get_max_constraint_degree, SymbolicAirBuilder,
@@ -403,11 +403,20 @@ pub fn prove<T: FieldElementMap>(
     program: &PowdrCircuit<T>,
     witness_by_machine: &mut BTreeMap<String, Vec<(String, Vec<T>)>>,
     challenger: &mut Challenger<T>,
-) -> Proof<T::Config>
+    fri_parameters: &FriParameters,
+) -> Result<Proof<T::Config>, String>
 where
     ProverData<T>: Send,
     Commitment<T>: Send,
 {
+    if let Some(proving_key) = proving_key {
+        if proving_key.fri_parameters != *fri_parameters {
+            return Err(String::from(
+                "The proving key was generated with different FRI parameters",
+            ));
+        }
+    }
+
     let (tables, stage_0): (BTreeMap<_, _>, BTreeMap<_, _>) = witness_by_machine
         .iter()
         .map(|(name, columns)| {
@@ -421,7 +430,7 @@ where
 
             // Sanity-check that the degree bound is not exceeded
             // If we don't panic here, Plonky3 panics with a bad error message when computing the quotient polynomial
-            let degree_bound = T::degree_bound();
+            let degree_bound = fri_parameters.degree_bound();
             let max_degree = table.max_constraint_degree();
             if max_degree > degree_bound {
                 panic!(
@@ -462,7 +471,7 @@ where
 
     let multi_table = MultiTable { tables };
 
-    let config = T::get_config();
+    let config = T::get_config(fri_parameters);
 
     assert_eq!(stage_0.keys().collect_vec(), multi_table.table_names());
 
@@ -535,11 +544,12 @@ where
 
     let (opened_values, opening_proof) = multi_table.open(&mut state, proving_key, quotient_data);
 
-    Proof {
+    Ok(Proof {
         commitments,
         opened_values,
         opening_proof,
-    }
+        fri_parameters: *fri_parameters,
+    })
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -0,0 +1,69 @@
+//! Verification of plonky3 proofs inside powdr.
+//!
+//! This crate can be compiled for powdr's RISC-V target, where the `powdr-accel`
+//! feature delegates the Poseidon2 permutation over Goldilocks to the `Poseidon2GL`
+//! machine in `std/machines/hash`. Running [VerifierInput::verify] in such a
+//! program (see `riscv/tests/riscv_data/plonky3_verify`) therefore proves, inside
+//! another powdr proof, that a plonky3 proof is valid: the execution covers the
+//! Merkle path verification, the FRI query checks and the evaluation of the
+//! constraints at the out-of-domain point.
+//!
+//! Since all of the input comes from the prover, such a program also has to
+//! expose what it has verified: the PIL of the machines, the verifying key and
+//! the public values. Otherwise, any valid proof would be accepted.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use p3_uni_stark::PcsError;
+use powdr_ast::analyzed::Analyzed;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    verify, Commitment, ConstraintSystem, FieldElementMap, FriParameters, Proof, ProverData,
+    StarkVerifyingKey, VerificationError,
+};
+
+/// Everything needed to verify a plonky3 proof, e.g. passed as prover data to a
+/// verifier running in powdr.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierInput<T: FieldElementMap>
+where
+    ProverData<T>: Send,
+    Commitment<T>: Send,
+{
+    /// The PIL of each machine
+    pub machines: BTreeMap<String, Analyzed<T>>,
+    /// The verifying key, required so that the fixed columns are checked
+    pub verifying_key: StarkVerifyingKey<T::Config>,
+    pub proof: Proof<T::Config>,
+    /// For each machine, for each stage, the public values
+    pub public_values: BTreeMap<String, Vec<Vec<T>>>,
+    pub fri_parameters: FriParameters,
+}
+
+impl<T: FieldElementMap> VerifierInput<T>
+where
+    ProverData<T>: Send,
+    Commitment<T>: Send,
+{
+    pub fn verify(&self) -> Result<(), VerificationError<PcsError<T::Config>>> {
+        let constraint_systems = self
+            .machines
+            .iter()
+            .map(|(name, pil)| (name, ConstraintSystem::from(pil)))
+            .collect::<BTreeMap<_, _>>();
+
+        verify(
+            Some(&self.verifying_key),
+            &constraint_systems
+                .iter()
+                .map(|(name, constraint_system)| (*name, constraint_system))
+                .collect(),
+            &mut T::get_challenger(),
+            &self.proof,
+            self.public_values.clone(),
+            &self.fri_parameters,
+        )
+    }
+}
@@ -17,8 +17,8 @@ use crate::circuit_builder::PowdrTable;
 use crate::params::{Challenge, Challenger, Commitment, Pcs, ProverData};
 use crate::symbolic_builder::{get_log_quotient_degree, SymbolicAirBuilder};
 use crate::{
-    ConstraintSystem, FieldElementMap, MultiStageAir, Proof, StageOpenedValues, StarkVerifyingKey,
-    TableOpenedValues, TableVerifyingKeyCollection, VerifierConstraintFolder,
+    ConstraintSystem, FieldElementMap, FriParameters, MultiStageAir, Proof, StageOpenedValues,
+    StarkVerifyingKey, TableOpenedValues, TableVerifyingKeyCollection, VerifierConstraintFolder,
 };
 use p3_uni_stark::{Domain, PcsError, StarkGenericConfig, Val};
 
@@ -77,6 +77,7 @@ pub fn verify<T: FieldElementMap>(
     proof: &Proof<T::Config>,
     // Machine name -> (stage -> public values)
     public_inputs: BTreeMap<String, Vec<Vec<T>>>,
+    fri_parameters: &FriParameters,
 ) -> Result<(), VerificationError<PcsError<T::Config>>>
 where
     ProverData<T>: Send,
@@ -99,8 +100,25 @@ where
         commitments,
         opened_values,
         opening_proof,
+        fri_parameters: proof_fri_parameters,
     } = proof;
 
+    // reject proofs and verification keys generated with other FRI parameters
+    if let Some(verifying_key) = verifying_key {
+        if &verifying_key.fri_parameters != fri_parameters {
+            return Err(VerificationError::FriParametersMismatch {
+                expected: *fri_parameters,
+                actual: verifying_key.fri_parameters,
+            });
+        }
+    }
+    if proof_fri_parameters != fri_parameters {
+        return Err(VerificationError::FriParametersMismatch {
+            expected: *fri_parameters,
+            actual: *proof_fri_parameters,
+        });
+    }
+
     // Filters out machines that are not included in the proof.
     // With a sound bus argument, the prover can only do this if they don't interact
     // with the bus, i.e., are empty.
@@ -155,7 +173,7 @@ where
         )
         .collect();
 
-    let config = T::get_config();
+    let config = T::get_config(fri_parameters);
 
     let pcs = config.pcs();
 
@@ -462,4 +480,10 @@ pub enum VerificationError<PcsErr> {
     /// Out-of-domain evaluation mismatch, i.e. `constraints(zeta)` did not match
     /// `quotient(zeta) Z_H(zeta)`.
     OodEvaluationMismatch,
+    /// The proof or the verification key was generated with other FRI parameters
+    /// than the verifier expects.
+    FriParametersMismatch {
+        expected: FriParameters,
+        actual: FriParameters,
+    },
 }
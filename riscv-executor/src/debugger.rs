@@ -0,0 +1,636 @@
+//! Step-through debugging of the execution.
+//!
+//! The executor calls into a [DebugFrontend] whenever the execution stops, i.e. at the
+//! start, after a single step and at breakpoints. The frontend can then inspect the state
+//! of the machine through [DebugState] and decide how to continue.
+//!
+//! All locations refer to the executor PC, i.e. the index of an instruction batch of
+//! the powdr-asm program. Source lines are taken from the `.debug loc` directives, which the
+//! RISC-V translation emits from the debug information of the ELF file. The address of the
+//! original RISC-V instruction is taken from the `.debug insn <address>` directives.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use powdr_number::FieldElement;
+
+use crate::{builder::TraceBuilder, profiler::format_function_name};
+
+/// A condition under which the execution stops.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop when the executor PC reaches the given value.
+    Pc(u32),
+    /// Stop at the start of the original instruction at the given address.
+    Address(u32),
+    /// Stop at the start of the function (or any other label) with the given name.
+    /// Rust symbols can also be given in their demangled form.
+    Function(String),
+    /// Stop at the start of the given source line. `file` can be a suffix of the path.
+    Line { file: String, line: usize },
+    /// Stop when the program invokes the syscall with the given name, e.g. `input`.
+    Syscall(String),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "{pc}"),
+            Breakpoint::Address(address) => write!(f, "*0x{address:08x}"),
+            Breakpoint::Function(name) => write!(f, "{name}"),
+            Breakpoint::Line { file, line } => write!(f, "{file}:{line}"),
+            Breakpoint::Syscall(name) => write!(f, "syscall:{name}"),
+        }
+    }
+}
+
+/// Parses `<pc>`, `*<address>`, `<file>:<line>`, `syscall:<name>` or `<function>`.
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("Empty breakpoint".to_string());
+        }
+        if let Ok(pc) = s.parse() {
+            return Ok(Breakpoint::Pc(pc));
+        }
+        if let Some(address) = s.strip_prefix('*') {
+            return parse_number(address).map(Breakpoint::Address);
+        }
+        if let Some(name) = s.strip_prefix("syscall:") {
+            return Ok(Breakpoint::Syscall(name.to_string()));
+        }
+        match s.rsplit_once(':') {
+            Some((file, line)) if !file.ends_with(':') => match line.parse() {
+                Ok(line) => Ok(Breakpoint::Line {
+                    file: file.to_string(),
+                    line,
+                }),
+                Err(_) => Ok(Breakpoint::Function(s.to_string())),
+            },
+            _ => Ok(Breakpoint::Function(s.to_string())),
+        }
+    }
+}
+
+/// Why the execution stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The execution is about to execute the first instruction.
+    Start,
+    /// A step requested by [DebugCommand::Step] or [DebugCommand::StepLine] finished.
+    Step,
+    /// The breakpoint with the given id was hit.
+    Breakpoint(usize),
+    /// The program finished. The command returned by the frontend is ignored.
+    Exit,
+}
+
+/// How to continue after the execution stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Execute a single instruction batch.
+    Step,
+    /// Run until the source line changes.
+    StepLine,
+    /// Run until the address of the original instruction changes.
+    StepInstruction,
+    /// Abort the execution.
+    Quit,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Receives control whenever the execution stops.
+pub trait DebugFrontend<F: FieldElement> {
+    fn on_stop(&mut self, reason: StopReason, state: &mut DebugState<'_, F>) -> DebugCommand;
+}
+
+/// The symbol information of the program that is needed to resolve breakpoints.
+pub(crate) struct Symbols<'a> {
+    /// file number to (dir,name)
+    debug_files: &'a [(&'a str, &'a str)],
+    /// label to PC
+    labels: HashMap<&'a str, u32>,
+    /// function label to PC
+    function_starts: BTreeMap<usize, &'a str>,
+    /// PC to .debug loc
+    location_starts: BTreeMap<usize, (usize, usize)>,
+    /// PC to the address of the original instruction
+    instruction_addresses: BTreeMap<usize, Option<u32>>,
+}
+
+impl<'a> Symbols<'a> {
+    pub(crate) fn new(
+        debug_files: &'a [(&'a str, &'a str)],
+        labels: HashMap<&'a str, u32>,
+        function_starts: BTreeMap<usize, &'a str>,
+        location_starts: BTreeMap<usize, (usize, usize)>,
+        instruction_addresses: BTreeMap<usize, Option<u32>>,
+    ) -> Self {
+        Self {
+            debug_files,
+            labels,
+            function_starts,
+            location_starts,
+            instruction_addresses,
+        }
+    }
+
+    fn file_name(&self, file: usize) -> String {
+        match self.debug_files[file - 1] {
+            ("", name) => name.to_string(),
+            (dir, name) => format!("{dir}/{name}"),
+        }
+    }
+
+    fn location(&self, pc: u32) -> Option<SourceLocation> {
+        self.location_starts
+            .range(..=pc as usize)
+            .next_back()
+            .map(|(_, (file, line))| SourceLocation {
+                file: self.file_name(*file),
+                line: *line,
+            })
+    }
+
+    fn function(&self, pc: u32) -> Option<String> {
+        self.function_starts
+            .range(..=pc as usize)
+            .next_back()
+            .map(|(_, name)| format_function_name(name))
+    }
+
+    fn address(&self, pc: u32) -> Option<u32> {
+        self.instruction_addresses
+            .range(..=pc as usize)
+            .next_back()
+            .and_then(|(_, address)| *address)
+    }
+
+    /// Returns the PCs at which the execution stops for the given breakpoint.
+    fn resolve(&self, breakpoint: &Breakpoint) -> Result<Vec<u32>, String> {
+        let pcs = match breakpoint {
+            Breakpoint::Pc(pc) => vec![*pc],
+            Breakpoint::Address(address) => {
+                let pcs = self
+                    .instruction_addresses
+                    .iter()
+                    .filter(|(_, a)| **a == Some(*address))
+                    .map(|(pc, _)| *pc as u32)
+                    .collect::<Vec<_>>();
+                if pcs.is_empty() {
+                    return Err(format!("No instruction at address 0x{address:08x}"));
+                }
+                pcs
+            }
+            Breakpoint::Function(name) => self
+                .labels
+                .iter()
+                .filter(|(label, _)| {
+                    let formatted = format_function_name(label);
+                    **label == name.as_str()
+                        || formatted == *name
+                        // Allow to omit the hash of Rust symbols.
+                        || formatted.starts_with(&format!("{name}::h"))
+                })
+                .map(|(_, pc)| *pc)
+                .collect(),
+            Breakpoint::Line { file, line } => self
+                .location_starts
+                .iter()
+                .filter(|(_, (f, l))| {
+                    let file_name = self.file_name(*f);
+                    l == line && (file_name == *file || file_name.ends_with(&format!("/{file}")))
+                })
+                .map(|(pc, _)| *pc as u32)
+                .collect(),
+            Breakpoint::Syscall(name) => self
+                .labels
+                .get(format!("__ecall_handler_{name}").as_str())
+                .map(|pc| vec![*pc])
+                .ok_or_else(|| format!("Unknown syscall: {name}"))?,
+        };
+        if pcs.is_empty() {
+            Err(format!("No code found for breakpoint {breakpoint}"))
+        } else {
+            Ok(pcs.into_iter().sorted().dedup().collect())
+        }
+    }
+}
+
+#[derive(Default)]
+struct Breakpoints {
+    /// All breakpoints by id. Deleted breakpoints are None.
+    list: Vec<Option<Breakpoint>>,
+    /// PC to the id of the breakpoint at that PC.
+    pcs: HashMap<u32, usize>,
+}
+
+/// The state of a stopped execution.
+pub struct DebugState<'a, F: FieldElement> {
+    proc: &'a TraceBuilder<'a, F>,
+    symbols: &'a Symbols<'a>,
+    breakpoints: &'a mut Breakpoints,
+    step: u32,
+}
+
+impl<F: FieldElement> DebugState<'_, F> {
+    /// The executor PC of the instruction batch that is executed next.
+    pub fn pc(&self) -> u32 {
+        self.proc.get_pc().u()
+    }
+
+    /// The current step, as used for memory accesses.
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    pub fn location(&self) -> Option<SourceLocation> {
+        self.symbols.location(self.pc())
+    }
+
+    /// The address of the original instruction that is executed next, if the
+    /// current code was translated from one.
+    pub fn address(&self) -> Option<u32> {
+        self.symbols.address(self.pc())
+    }
+
+    /// The (demangled) name of the current function.
+    pub fn function(&self) -> Option<String> {
+        self.symbols.function(self.pc())
+    }
+
+    /// The value of a RISC-V register (`x0` to `x31`, followed by the
+    /// translation's temporary registers), truncated to 32 bits.
+    pub fn register(&self, idx: u32) -> u32 {
+        self.proc.get_reg_mem(idx).as_i64_from_lower_bytes() as u32
+    }
+
+    /// The memory word at the given address, which is rounded down to a multiple of 4.
+    pub fn memory_word(&self, addr: u32) -> u32 {
+        self.proc.peek_mem(addr & !3)
+    }
+
+    /// Reads `len` bytes of memory starting at `addr`.
+    pub fn read_memory(&self, addr: u32, len: u32) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                let addr = addr.wrapping_add(i);
+                self.memory_word(addr).to_le_bytes()[(addr % 4) as usize]
+            })
+            .collect()
+    }
+
+    /// Adds a breakpoint and returns its id.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> Result<usize, String> {
+        let pcs = self.symbols.resolve(&breakpoint)?;
+        let id = self.breakpoints.list.len();
+        for pc in pcs {
+            self.breakpoints.pcs.entry(pc).or_insert(id);
+        }
+        self.breakpoints.list.push(Some(breakpoint));
+        Ok(id)
+    }
+
+    /// Removes the breakpoint with the given id. Returns false if there is no such breakpoint.
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let Some(Some(breakpoint)) = self.breakpoints.list.get_mut(id).map(Option::take) else {
+            return false;
+        };
+        self.breakpoints.pcs.retain(|_, i| *i != id);
+        // Other breakpoints might share some of the PCs.
+        for (other_id, other) in self.breakpoints.list.iter().enumerate() {
+            if let Some(other) = other {
+                for pc in self.symbols.resolve(other).unwrap() {
+                    self.breakpoints.pcs.entry(pc).or_insert(other_id);
+                }
+            }
+        }
+        log::trace!("Removed breakpoint {id} ({breakpoint})");
+        true
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, &Breakpoint)> {
+        self.breakpoints
+            .list
+            .iter()
+            .enumerate()
+            .filter_map(|(id, breakpoint)| breakpoint.as_ref().map(|b| (id, b)))
+    }
+}
+
+/// Decides when to stop the execution and hands control to the frontend.
+pub(crate) struct Debugger<'a, F: FieldElement> {
+    frontend: &'a mut dyn DebugFrontend<F>,
+    symbols: Symbols<'a>,
+    breakpoints: Breakpoints,
+    /// The last command of the frontend, None before the start.
+    command: Option<DebugCommand>,
+    /// The source location at the last stop.
+    location: Option<SourceLocation>,
+    /// The address of the original instruction at the last stop.
+    address: Option<u32>,
+}
+
+impl<'a, F: FieldElement> Debugger<'a, F> {
+    pub(crate) fn new(frontend: &'a mut dyn DebugFrontend<F>, symbols: Symbols<'a>) -> Self {
+        Self {
+            frontend,
+            symbols,
+            breakpoints: Default::default(),
+            command: None,
+            location: None,
+            address: None,
+        }
+    }
+
+    /// Should be called before executing the first statement of each batch.
+    /// Returns false if the execution should be aborted.
+    pub(crate) fn on_batch(&mut self, proc: &TraceBuilder<'_, F>, step: u32) -> bool {
+        let pc = proc.get_pc().u();
+        let reason = match self.command {
+            None => Some(StopReason::Start),
+            Some(DebugCommand::Step) => Some(StopReason::Step),
+            Some(DebugCommand::StepLine) => {
+                let location = self.symbols.location(pc);
+                (location.is_some() && location != self.location).then_some(StopReason::Step)
+            }
+            Some(DebugCommand::StepInstruction) => {
+                (self.symbols.address(pc) != self.address).then_some(StopReason::Step)
+            }
+            Some(DebugCommand::Continue) | Some(DebugCommand::Quit) => None,
+        }
+        .or_else(|| {
+            self.breakpoints
+                .pcs
+                .get(&pc)
+                .map(|id| StopReason::Breakpoint(*id))
+        });
+        let Some(reason) = reason else {
+            return true;
+        };
+
+        let command = self.stop(reason, proc, step);
+        self.location = self.symbols.location(pc);
+        self.address = self.symbols.address(pc);
+        self.command = Some(command);
+        command != DebugCommand::Quit
+    }
+
+    /// Should be called after the program finished.
+    pub(crate) fn on_exit(&mut self, proc: &TraceBuilder<'_, F>, step: u32) {
+        self.stop(StopReason::Exit, proc, step);
+    }
+
+    fn stop(&mut self, reason: StopReason, proc: &TraceBuilder<'_, F>, step: u32) -> DebugCommand {
+        let mut state = DebugState {
+            proc,
+            symbols: &self.symbols,
+            breakpoints: &mut self.breakpoints,
+            step,
+        };
+        self.frontend.on_stop(reason, &mut state)
+    }
+}
+
+const HELP: &str = "\
+Commands:
+  break <location>  (b)  add a breakpoint at <pc>, *<address>, <function>, <file>:<line>
+                         or syscall:<name>
+  delete <id>       (d)  delete a breakpoint
+  breakpoints       (bl) list all breakpoints
+  continue          (c)  run until the next breakpoint
+  step              (s)  execute a single instruction batch
+  stepi             (si) execute a single RISC-V instruction
+  next              (n)  run until the source line changes
+  where             (w)  show the current location
+  regs              (r)  show all RISC-V registers
+  print <reg>       (p)  show a register, e.g. x10
+  mem <addr> [n]    (x)  show n memory words starting at addr
+  quit              (q)  abort the execution";
+
+/// A line-based interactive debugger, reading commands from `input` and
+/// writing to `output`.
+pub struct CommandLineFrontend<R, W> {
+    input: R,
+    output: W,
+    /// Breakpoints to add at the start of the execution.
+    initial_breakpoints: Vec<Breakpoint>,
+}
+
+impl<R: BufRead, W: Write> CommandLineFrontend<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            initial_breakpoints: vec![],
+        }
+    }
+
+    pub fn with_breakpoints(self, initial_breakpoints: Vec<Breakpoint>) -> Self {
+        Self {
+            initial_breakpoints,
+            ..self
+        }
+    }
+
+    /// Executes a single command. Returns Some if the execution should continue.
+    fn execute_command<F: FieldElement>(
+        &mut self,
+        line: &str,
+        state: &mut DebugState<'_, F>,
+    ) -> Result<Option<DebugCommand>, String> {
+        let mut parts = line.split_whitespace();
+        let Some(command) = parts.next() else {
+            return Ok(None);
+        };
+        let args = parts.collect::<Vec<_>>();
+        let out = &mut self.output;
+        let io_err = |e: std::io::Error| e.to_string();
+        match (command, &args[..]) {
+            ("help" | "h", []) => writeln!(out, "{HELP}").map_err(io_err)?,
+            ("break" | "b", [_, ..]) => {
+                let breakpoint = args.join(" ").parse()?;
+                let id = state.add_breakpoint(breakpoint)?;
+                writeln!(out, "Breakpoint {id} added").map_err(io_err)?;
+            }
+            ("delete" | "d", [id]) => {
+                let id = id.parse().map_err(|_| format!("Invalid id: {id}"))?;
+                if !state.remove_breakpoint(id) {
+                    return Err(format!("No breakpoint with id {id}"));
+                }
+            }
+            ("breakpoints" | "bl", []) => {
+                for (id, breakpoint) in state.breakpoints() {
+                    writeln!(out, "{id}: {breakpoint}").map_err(io_err)?;
+                }
+            }
+            ("continue" | "c", []) => return Ok(Some(DebugCommand::Continue)),
+            ("step" | "s", []) => return Ok(Some(DebugCommand::Step)),
+            ("stepi" | "si", []) => return Ok(Some(DebugCommand::StepInstruction)),
+            ("next" | "n", []) => return Ok(Some(DebugCommand::StepLine)),
+            ("quit" | "q", []) => return Ok(Some(DebugCommand::Quit)),
+            ("where" | "w", []) => write_location(out, state).map_err(io_err)?,
+            ("regs" | "r", []) => {
+                for row in &(0..32).chunks(4) {
+                    let row = row
+                        .map(|i| format!("x{i:<2} = 0x{:08x}", state.register(i)))
+                        .join("  ");
+                    writeln!(out, "{row}").map_err(io_err)?;
+                }
+            }
+            ("print" | "p", [register]) => {
+                let idx = register
+                    .strip_prefix('x')
+                    .and_then(|idx| idx.parse::<u32>().ok())
+                    .filter(|idx| *idx < 32)
+                    .ok_or_else(|| format!("Invalid register: {register}"))?;
+                let value = state.register(idx);
+                writeln!(out, "{register} = 0x{value:08x} ({value})").map_err(io_err)?;
+            }
+            ("mem" | "x", [addr, count @ ..]) if count.len() <= 1 => {
+                let addr = parse_number(addr)?;
+                let count = count.first().map(|c| parse_number(c)).transpose()?;
+                for i in 0..count.unwrap_or(1) {
+                    let addr = (addr & !3).wrapping_add(4 * i);
+                    let value = state.memory_word(addr);
+                    writeln!(out, "0x{addr:08x}: 0x{value:08x}").map_err(io_err)?;
+                }
+            }
+            _ => return Err(format!("Invalid command: {line} (try \"help\")")),
+        }
+        Ok(None)
+    }
+}
+
+impl<F: FieldElement, R: BufRead, W: Write> DebugFrontend<F> for CommandLineFrontend<R, W> {
+    fn on_stop(&mut self, reason: StopReason, state: &mut DebugState<'_, F>) -> DebugCommand {
+        let out = &mut self.output;
+        match reason {
+            StopReason::Start => {
+                for breakpoint in std::mem::take(&mut self.initial_breakpoints) {
+                    match state.add_breakpoint(breakpoint) {
+                        Ok(id) => writeln!(out, "Breakpoint {id} added").unwrap(),
+                        Err(e) => writeln!(out, "Error: {e}").unwrap(),
+                    }
+                }
+                writeln!(
+                    out,
+                    "Execution started. Type \"help\" for a list of commands."
+                )
+                .unwrap();
+            }
+            StopReason::Step => {}
+            StopReason::Breakpoint(id) => writeln!(out, "Breakpoint {id} hit").unwrap(),
+            StopReason::Exit => {
+                writeln!(out, "Execution finished after {} steps", state.step()).unwrap();
+                return DebugCommand::Quit;
+            }
+        }
+        write_location(out, state).unwrap();
+
+        loop {
+            write!(self.output, "(powdr) ").unwrap();
+            self.output.flush().unwrap();
+            let mut line = String::new();
+            if self.input.read_line(&mut line).unwrap() == 0 {
+                // End of input.
+                return DebugCommand::Quit;
+            }
+            match self.execute_command(line.trim(), state) {
+                Ok(Some(command)) => return command,
+                Ok(None) => {}
+                Err(e) => writeln!(self.output, "Error: {e}").unwrap(),
+            }
+        }
+    }
+}
+
+fn write_location<F: FieldElement>(
+    out: &mut impl Write,
+    state: &DebugState<'_, F>,
+) -> std::io::Result<()> {
+    write!(out, "pc {}", state.pc())?;
+    if let Some(address) = state.address() {
+        write!(out, " (0x{address:08x})")?;
+    }
+    if let Some(function) = state.function() {
+        write!(out, " in {function}")?;
+    }
+    if let Some(location) = state.location() {
+        write!(out, " at {location}")?;
+    }
+    writeln!(out)
+}
+
+/// Parses a decimal or a `0x`-prefixed hexadecimal number.
+fn parse_number(s: &str) -> Result<u32, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("Invalid number: {s}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_breakpoints() {
+        assert_eq!("12".parse(), Ok(Breakpoint::Pc(12)));
+        assert_eq!("*0x1007c".parse(), Ok(Breakpoint::Address(0x1007c)));
+        assert!("*main".parse::<Breakpoint>().is_err());
+        assert_eq!(
+            "src/main.rs:7".parse(),
+            Ok(Breakpoint::Line {
+                file: "src/main.rs".to_string(),
+                line: 7
+            })
+        );
+        assert_eq!(
+            "syscall:input".parse(),
+            Ok(Breakpoint::Syscall("input".to_string()))
+        );
+        assert_eq!(
+            "guest::main".parse(),
+            Ok(Breakpoint::Function("guest::main".to_string()))
+        );
+        assert_eq!(
+            "__runtime_start".parse(),
+            Ok(Breakpoint::Function("__runtime_start".to_string()))
+        );
+        for breakpoint in [
+            "12",
+            "*0x0001007c",
+            "src/main.rs:7",
+            "syscall:input",
+            "guest::main",
+        ] {
+            assert_eq!(
+                breakpoint.parse::<Breakpoint>().unwrap().to_string(),
+                breakpoint
+            );
+        }
+    }
+}
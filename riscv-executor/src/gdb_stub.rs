@@ -0,0 +1,429 @@
+//! A stub for the GDB remote serial protocol, so that GDB can be attached to a
+//! program running in the executor:
+//!
+//! ```text
+//! (gdb) set architecture riscv:rv32
+//! (gdb) target remote localhost:<port>
+//! ```
+//!
+//! The PC reported to GDB is the address of the original RISC-V instruction, and
+//! `break *<address>` stops at the start of the instruction at that address. Code that was
+//! not translated from a RISC-V instruction, e.g. a syscall handler, reports the executor PC
+//! (see [crate::debugger]) instead. A single step executes one RISC-V instruction.
+//! Breakpoints on functions, source lines and syscalls can be set using
+//! `monitor break <location>`, which resolves them using the debug information of the program.
+//! Memory and registers are read-only.
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use itertools::Itertools;
+use powdr_number::FieldElement;
+
+use crate::debugger::{Breakpoint, DebugCommand, DebugFrontend, DebugState, StopReason};
+
+/// The number of registers exposed to GDB: x0 to x31 and the PC.
+const REGISTER_COUNT: u32 = 33;
+
+const REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// A [DebugFrontend] that is controlled by GDB over the remote serial protocol.
+pub struct GdbStub<S> {
+    stream: S,
+    /// Whether packets need to be acknowledged, which GDB can turn off.
+    ack_mode: bool,
+    /// Whether GDB detached, in which case the execution runs to the end.
+    detached: bool,
+    /// The breakpoint ids of breakpoints set by GDB, by address.
+    breakpoints: Vec<(u32, usize)>,
+    /// Breakpoints to add at the start of the execution.
+    initial_breakpoints: Vec<Breakpoint>,
+}
+
+impl GdbStub<TcpStream> {
+    /// Waits for GDB to connect to the given listener.
+    pub fn accept(listener: &TcpListener) -> io::Result<Self> {
+        log::info!(
+            "Waiting for GDB to connect to {}...",
+            listener.local_addr()?
+        );
+        let (stream, addr) = listener.accept()?;
+        log::info!("GDB connected from {addr}");
+        Ok(Self::new(stream))
+    }
+}
+
+impl<S: Read + Write> GdbStub<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            ack_mode: true,
+            detached: false,
+            breakpoints: vec![],
+            initial_breakpoints: vec![],
+        }
+    }
+
+    pub fn with_breakpoints(self, initial_breakpoints: Vec<Breakpoint>) -> Self {
+        Self {
+            initial_breakpoints,
+            ..self
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Reads the next packet and returns its data, or None if the connection was closed.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // Skip everything up to the start of the packet, including acknowledgements.
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'$') => break,
+                    Some(_) => {}
+                }
+            }
+            let mut data = vec![];
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(b) => data.push(b),
+                }
+            }
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                == Some(checksum_of(&data));
+            if self.ack_mode {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${data}#{:02x}", checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())?;
+        self.stream.flush()
+    }
+
+    /// Handles packets until GDB resumes the execution.
+    fn serve<F: FieldElement>(
+        &mut self,
+        state: &mut DebugState<'_, F>,
+    ) -> io::Result<DebugCommand> {
+        loop {
+            let Some(packet) = self.read_packet()? else {
+                log::info!("GDB disconnected");
+                return Ok(DebugCommand::Quit);
+            };
+            log::trace!("GDB: {packet}");
+            let reply = match packet.as_bytes().first() {
+                Some(b'c') => return Ok(DebugCommand::Continue),
+                Some(b's') => return Ok(DebugCommand::StepInstruction),
+                Some(b'k') => return Ok(DebugCommand::Quit),
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    self.detached = true;
+                    return Ok(DebugCommand::Continue);
+                }
+                Some(b'?') => stop_reply(state),
+                Some(b'g') => (0..REGISTER_COUNT)
+                    .map(|i| hex_le(register(state, i)))
+                    .collect(),
+                Some(b'p') => u32::from_str_radix(&packet[1..], 16)
+                    .ok()
+                    .filter(|i| *i < REGISTER_COUNT)
+                    .map(|i| hex_le(register(state, i)))
+                    .unwrap_or_else(|| "E01".to_string()),
+                Some(b'm') => parse_address_and_length(&packet[1..])
+                    .map(|(addr, len)| encode_hex(state.read_memory(addr, len)))
+                    .unwrap_or_else(|| "E01".to_string()),
+                Some(b'Z' | b'z') => self.update_breakpoint(&packet, state),
+                Some(b'H') => "OK".to_string(),
+                Some(b'q' | b'Q') => self.query(&packet, state)?,
+                // Writing registers or memory and all other packets are not supported.
+                _ => String::new(),
+            };
+            self.write_packet(&reply)?;
+        }
+    }
+
+    /// Handles `Z0`/`Z1` (insert) and `z0`/`z1` (remove) breakpoint packets.
+    fn update_breakpoint<F: FieldElement>(
+        &mut self,
+        packet: &str,
+        state: &mut DebugState<'_, F>,
+    ) -> String {
+        let mut parts = packet[1..].split(',');
+        let (Some("0" | "1"), Some(addr)) = (parts.next(), parts.next()) else {
+            return String::new();
+        };
+        let Ok(address) = u32::from_str_radix(addr, 16) else {
+            return "E01".to_string();
+        };
+        if packet.starts_with('Z') {
+            // Fails if there is no instruction at the address.
+            match state.add_breakpoint(Breakpoint::Address(address)) {
+                Ok(id) => {
+                    self.breakpoints.push((address, id));
+                    "OK".to_string()
+                }
+                Err(e) => {
+                    log::error!("{e}");
+                    "E01".to_string()
+                }
+            }
+        } else if let Some(pos) = self.breakpoints.iter().position(|(a, _)| *a == address) {
+            let (_, id) = self.breakpoints.remove(pos);
+            state.remove_breakpoint(id);
+            "OK".to_string()
+        } else {
+            "E01".to_string()
+        }
+    }
+
+    fn query<F: FieldElement>(
+        &mut self,
+        packet: &str,
+        state: &mut DebugState<'_, F>,
+    ) -> io::Result<String> {
+        let (name, args) = packet.split_once([':', ',']).unwrap_or((packet, ""));
+        Ok(match name {
+            "qSupported" => "PacketSize=4000;QStartNoAckMode+;qXfer:features:read+".to_string(),
+            "QStartNoAckMode" => {
+                // GDB still acknowledges the reply to this packet, which `read_packet` skips.
+                self.ack_mode = false;
+                "OK".to_string()
+            }
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "qXfer" => match args.strip_prefix("features:read:target.xml:") {
+                Some(range) => match parse_address_and_length(range) {
+                    Some((offset, len)) => xfer_chunk(&target_description(), offset, len),
+                    None => "E01".to_string(),
+                },
+                None => String::new(),
+            },
+            "qRcmd" => {
+                let command = decode_hex(args).unwrap_or_default();
+                let output = monitor_command(&command, state);
+                self.write_packet(&format!("O{}", encode_hex(&output)))?;
+                "OK".to_string()
+            }
+            _ => String::new(),
+        })
+    }
+}
+
+impl<F: FieldElement, S: Read + Write> DebugFrontend<F> for GdbStub<S> {
+    fn on_stop(&mut self, reason: StopReason, state: &mut DebugState<'_, F>) -> DebugCommand {
+        if self.detached {
+            return DebugCommand::Continue;
+        }
+        let result = match reason {
+            StopReason::Start => {
+                for breakpoint in std::mem::take(&mut self.initial_breakpoints) {
+                    if let Err(e) = state.add_breakpoint(breakpoint) {
+                        log::error!("{e}");
+                    }
+                }
+                // GDB asks for the stop reason itself after connecting.
+                Ok(())
+            }
+            StopReason::Step | StopReason::Breakpoint(_) => self.write_packet(&stop_reply(state)),
+            StopReason::Exit => self.write_packet("W00"),
+        };
+        if reason == StopReason::Exit {
+            if let Err(e) = result {
+                log::error!("Connection to GDB failed: {e}");
+            }
+            return DebugCommand::Quit;
+        }
+        result.and_then(|_| self.serve(state)).unwrap_or_else(|e| {
+            log::error!("Connection to GDB failed: {e}");
+            DebugCommand::Quit
+        })
+    }
+}
+
+/// Executes a `monitor` command and returns its output.
+fn monitor_command<F: FieldElement>(command: &str, state: &mut DebugState<'_, F>) -> String {
+    let mut parts = command.split_whitespace();
+    match (parts.next(), parts.join(" ")) {
+        (Some("break" | "b"), location) if !location.is_empty() => {
+            match location.parse().and_then(|b| state.add_breakpoint(b)) {
+                Ok(id) => format!("Breakpoint {id} added\n"),
+                Err(e) => format!("Error: {e}\n"),
+            }
+        }
+        (Some("delete" | "d"), id) => match id.parse() {
+            Ok(id) if state.remove_breakpoint(id) => String::new(),
+            _ => format!("Error: No breakpoint with id {id}\n"),
+        },
+        (Some("breakpoints" | "bl"), _) => state
+            .breakpoints()
+            .map(|(id, breakpoint)| format!("{id}: {breakpoint}\n"))
+            .collect(),
+        (Some("where" | "w"), _) => {
+            let mut output = format!("pc {}", state.pc());
+            if let Some(address) = state.address() {
+                output.push_str(&format!(" (0x{address:08x})"));
+            }
+            if let Some(function) = state.function() {
+                output.push_str(&format!(" in {function}"));
+            }
+            if let Some(location) = state.location() {
+                output.push_str(&format!(" at {location}"));
+            }
+            output + "\n"
+        }
+        _ => "Commands: break <location>, delete <id>, breakpoints, where\n".to_string(),
+    }
+}
+
+/// The reply to `?` and after the execution stopped: a SIGTRAP, together with
+/// the value of the PC (register 0x20).
+fn stop_reply<F: FieldElement>(state: &DebugState<'_, F>) -> String {
+    format!("T0520:{};", hex_le(register(state, 32)))
+}
+
+fn register<F: FieldElement>(state: &DebugState<'_, F>, idx: u32) -> u32 {
+    if idx == 32 {
+        state.address().unwrap_or_else(|| state.pc())
+    } else {
+        state.register(idx)
+    }
+}
+
+fn target_description() -> String {
+    let registers = REGISTER_NAMES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let ty = match *name {
+                "sp" | "fp" => "data_ptr",
+                _ => "int",
+            };
+            format!(r#"<reg name="{name}" bitsize="32" type="{ty}" regnum="{i}"/>"#)
+        })
+        .chain(std::iter::once(
+            r#"<reg name="pc" bitsize="32" type="code_ptr" regnum="32"/>"#.to_string(),
+        ))
+        .join("");
+    format!(
+        r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd"><target version="1.0"><architecture>riscv:rv32</architecture><feature name="org.gnu.gdb.riscv.cpu">{registers}</feature></target>"#
+    )
+}
+
+/// Returns the reply to a `qXfer` read of the given range of `document`.
+fn xfer_chunk(document: &str, offset: u32, len: u32) -> String {
+    let offset = (offset as usize).min(document.len());
+    let end = (offset + len as usize).min(document.len());
+    let marker = if end == document.len() { 'l' } else { 'm' };
+    format!("{marker}{}", &document[offset..end])
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))
+}
+
+/// Parses `<addr>,<length>`, both in hex.
+fn parse_address_and_length(s: &str) -> Option<(u32, u32)> {
+    let (addr, len) = s.split_once(',')?;
+    Some((
+        u32::from_str_radix(addr, 16).ok()?,
+        u32::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+/// Encodes a value in target byte order, i.e. little endian.
+fn hex_le(value: u32) -> String {
+    encode_hex(value.to_le_bytes())
+}
+
+fn encode_hex(data: impl AsRef<[u8]>) -> String {
+    data.as_ref().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(s: &str) -> Option<String> {
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A stream that reads from a fixed input and records everything written to it.
+    struct TestStream {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for TestStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for TestStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn packets() {
+        // An acknowledgement, a valid packet, one with an invalid checksum and another valid one.
+        let input = b"+$qSupported:multiprocess+#c6$g#00$g#67".to_vec();
+        let mut stub = GdbStub::new(TestStream {
+            input: io::Cursor::new(input),
+            output: vec![],
+        });
+        assert_eq!(
+            stub.read_packet().unwrap().as_deref(),
+            Some("qSupported:multiprocess+")
+        );
+        assert_eq!(stub.read_packet().unwrap().as_deref(), Some("g"));
+        assert_eq!(stub.read_packet().unwrap(), None);
+        stub.write_packet("OK").unwrap();
+        assert_eq!(stub.stream.output, b"+-+$OK#9a");
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(hex_le(0x12345678), "78563412");
+        assert_eq!(decode_hex(&encode_hex("where")).unwrap(), "where");
+        assert_eq!(parse_address_and_length("1f,4"), Some((0x1f, 4)));
+        assert_eq!(xfer_chunk("abcdef", 0, 4), "mabcd");
+        assert_eq!(xfer_chunk("abcdef", 4, 4), "lef");
+    }
+}
@@ -32,20 +32,30 @@ use powdr_ast::{
 };
 use tiny_keccak::keccakf;
 
+pub use debugger::{
+    Breakpoint, CommandLineFrontend, DebugCommand, DebugFrontend, DebugState, SourceLocation,
+    StopReason,
+};
+pub use gdb_stub::GdbStub;
 use powdr_executor::constant_evaluator::VariablySizedColumn;
 use powdr_number::{write_polys_csv_file, FieldElement, LargeInt};
 pub use profiler::ProfilerOptions;
+pub use replay::{InputRecorder, InputRecording};
 
 pub mod arith;
+mod debugger;
+mod gdb_stub;
 mod poseidon2_gl;
 pub mod poseidon_gl;
 mod profiler;
+mod replay;
 mod submachines;
 use submachines::*;
 mod memory;
 use memory::*;
 mod pil;
 
+use crate::debugger::{Debugger, Symbols};
 use crate::profiler::Profiler;
 
 #[derive(Debug)]
@@ -1005,13 +1015,18 @@ mod builder {
             val
         }
 
+        /// get the value of a memory word without recording the access
+        pub(crate) fn peek_mem(&self, addr: u32) -> u32 {
+            *self.mem.get(&addr).unwrap_or(&0)
+        }
+
         pub(crate) fn set_reg_mem(&mut self, addr: u32, val: Elem<F>) {
             if addr != 0 {
                 self.reg_mem.last.insert(addr, val);
             }
         }
 
-        pub(crate) fn get_reg_mem(&mut self, addr: u32) -> Elem<F> {
+        pub(crate) fn get_reg_mem(&self, addr: u32) -> Elem<F> {
             let zero: Elem<F> = 0u32.into();
             if addr == 0 {
                 zero
@@ -1233,6 +1248,9 @@ struct PreprocessedMain<'a, F: FieldElement> {
     function_starts: BTreeMap<usize, &'a str>,
     /// .debug loc to batch number
     location_starts: BTreeMap<usize, (usize, usize)>,
+    /// batch number to the address of the original instruction from `.debug insn`,
+    /// None for code without an original instruction
+    instruction_addresses: BTreeMap<usize, Option<u32>>,
 }
 
 /// Returns the list of instructions, directly indexable by PC, the map from
@@ -1250,6 +1268,7 @@ fn preprocess_main_function<F: FieldElement>(machine: &Machine) -> PreprocessedM
     let mut debug_files = Vec::new();
     let mut function_starts = BTreeMap::new();
     let mut location_starts = BTreeMap::new();
+    let mut instruction_addresses = BTreeMap::new();
 
     for (batch_idx, batch) in orig_statements.iter_batches().enumerate() {
         batch_to_line_map.push(statements.len() as u32);
@@ -1278,6 +1297,10 @@ fn preprocess_main_function<F: FieldElement>(machine: &Machine) -> PreprocessedM
                             // keep debug locs for debugging purposes
                             statements.push(s);
                         }
+                        DebugDirective::InstructionAddress(address) => {
+                            instruction_addresses
+                                .insert(batch_idx + PC_INITIAL_VAL, Some(*address));
+                        }
                     }
                 }
                 FunctionStatement::Label(LabelStatement { source: _, name }) => {
@@ -1288,6 +1311,11 @@ fn preprocess_main_function<F: FieldElement>(machine: &Machine) -> PreprocessedM
                     if !name.contains("___dot_L") {
                         function_starts.insert(batch_idx + PC_INITIAL_VAL, name.as_str());
                     }
+                    // A label without an instruction address starts code that was
+                    // not translated from an original instruction, e.g. a syscall handler.
+                    instruction_addresses
+                        .entry(batch_idx + PC_INITIAL_VAL)
+                        .or_insert(None);
                 }
             }
         }
@@ -1304,6 +1332,7 @@ fn preprocess_main_function<F: FieldElement>(machine: &Machine) -> PreprocessedM
         debug_files,
         function_starts,
         location_starts,
+        instruction_addresses,
     }
 }
 
@@ -2818,7 +2847,9 @@ impl<F: FieldElement> Executor<'_, '_, F> {
             Expression::IfExpression(_, _) => panic!(),
             Expression::BlockExpression(_, _) => panic!(),
             Expression::IndexAccess(_, _) => todo!(),
-            Expression::StructExpression(_, _) => todo!(),
+            Expression::FieldAccess(_, _) | Expression::StructExpression(_, _) => {
+                unimplemented!("Expression not implemented: {expression}")
+            }
         }
     }
 
@@ -2884,6 +2915,33 @@ pub fn execute<F: FieldElement>(
         usize::MAX,
         ExecMode::Fast,
         profiling,
+        None,
+    )
+    .trace_len
+}
+
+/// Execute a Powdr/RISCV assembly program under the control of a debugger,
+/// without generating a witness.
+/// Returns the execution trace length.
+pub fn execute_with_debugger<F: FieldElement>(
+    asm: &AnalysisASMFile,
+    initial_memory: MemoryState,
+    prover_ctx: &Callback<F>,
+    bootloader_inputs: &[F],
+    frontend: &mut dyn DebugFrontend<F>,
+) -> usize {
+    log::info!("Executing (debugging)...");
+    execute_inner(
+        asm,
+        None,
+        None,
+        initial_memory,
+        prover_ctx,
+        bootloader_inputs,
+        usize::MAX,
+        ExecMode::Fast,
+        None,
+        Some(frontend),
     )
     .trace_len
 }
@@ -2912,6 +2970,7 @@ pub fn execute_with_trace<F: FieldElement>(
         max_steps_to_execute.unwrap_or(usize::MAX),
         ExecMode::Trace,
         profiling,
+        None,
     )
 }
 
@@ -2939,6 +2998,7 @@ pub fn execute_with_witness<F: FieldElement>(
         max_steps_to_execute.unwrap_or(usize::MAX),
         ExecMode::Witness,
         profiling,
+        None,
     )
 }
 
@@ -2953,6 +3013,7 @@ fn execute_inner<F: FieldElement>(
     max_steps_to_execute: usize,
     mode: ExecMode,
     profiling: Option<ProfilerOptions>,
+    debug_frontend: Option<&mut dyn DebugFrontend<F>>,
 ) -> Execution<F> {
     let start = Instant::now();
     let main_machine = get_main_machine(asm);
@@ -2964,6 +3025,7 @@ fn execute_inner<F: FieldElement>(
         debug_files,
         function_starts,
         location_starts,
+        instruction_addresses,
     } = preprocess_main_function(main_machine);
 
     let witness_cols: Vec<String> = opt_pil
@@ -3026,6 +3088,21 @@ fn execute_inner<F: FieldElement>(
 
     let pil_links = opt_pil.map(pil::links_from_pil).unwrap_or_default();
 
+    let mut debugger = debug_frontend.map(|frontend| {
+        let labels = label_map
+            .iter()
+            .map(|(label, pc)| (*label, pc.u()))
+            .collect();
+        let symbols = Symbols::new(
+            &debug_files[..],
+            labels,
+            function_starts.clone(),
+            location_starts.clone(),
+            instruction_addresses,
+        );
+        Debugger::new(frontend, symbols)
+    });
+
     // We clear the QueryCallback's virtual FS before the execution.
     (prover_ctx)("Clear").unwrap();
     let mut e = Executor {
@@ -3052,7 +3129,17 @@ fn execute_inner<F: FieldElement>(
     e.proc.push_row(PC_INITIAL_VAL as u32);
     let mut last = Instant::now();
     let mut count = 0;
+    let mut aborted = false;
     loop {
+        if let Some(d) = &mut debugger {
+            // Only stop before the first statement of each batch.
+            let pc = e.proc.get_pc().u();
+            if batch_to_line_map[pc as usize] == curr_pc && !d.on_batch(&e.proc, e.step) {
+                aborted = true;
+                break;
+            }
+        }
+
         let stm = statements[curr_pc as usize];
 
         log::trace!("l {curr_pc}: {stm}",);
@@ -3173,7 +3260,9 @@ fn execute_inner<F: FieldElement>(
                     DebugDirective::OriginalInstruction(insn) => {
                         log::trace!("  {insn}");
                     }
-                    DebugDirective::File(_, _, _) => unreachable!(),
+                    DebugDirective::File(_, _, _) | DebugDirective::InstructionAddress(_) => {
+                        unreachable!()
+                    }
                 };
             }
             FunctionStatement::Label(_) => {
@@ -3191,6 +3280,12 @@ fn execute_inner<F: FieldElement>(
         p.finish();
     }
 
+    if let Some(mut d) = debugger {
+        if !aborted {
+            d.on_exit(&e.proc, e.step);
+        }
+    }
+
     let mut program_columns = vec![];
 
     log::debug!("Program execution took {}s", start.elapsed().as_secs_f64());
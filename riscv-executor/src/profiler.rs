@@ -354,7 +354,7 @@ impl<'a> Profiler<'a> {
     }
 }
 
-fn format_function_name(name: &str) -> String {
+pub(crate) fn format_function_name(name: &str) -> String {
     if let Some(prefix) = name.find("___ZN") {
         format!("{}", demangle(&name[prefix + 2..]))
     } else {
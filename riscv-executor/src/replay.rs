@@ -0,0 +1,171 @@
+//! Recording and replaying the nondeterministic inputs of an execution.
+//!
+//! All inputs the host provides to an execution are its initial memory, the bootloader inputs
+//! and the responses to queries (which includes the prover data). An [InputRecorder] wraps
+//! the query callback passed to any of the `execute*` functions and records the responses,
+//! and [InputRecording::replay_callback] returns a query callback that provides the same
+//! responses again, so that the exact same execution can be reproduced without the
+//! original host context.
+//!
+//! Witness generation after the execution repeats some of its queries, in a different order.
+//! [InputRecording::lookup_callback] answers those from the recording as well.
+//! Executions with continuations are not supported, as they consist of several executions
+//! with different bootloader inputs.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    sync::Mutex,
+};
+
+use powdr_executor::witgen::QueryCallback;
+use powdr_number::FieldElement;
+use serde::{Deserialize, Serialize};
+
+use crate::{Callback, MemoryState};
+
+/// The response to a query, as returned by the query callback.
+type QueryResponse<F> = Result<Option<F>, String>;
+
+/// All nondeterministic inputs of an execution.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "F: FieldElement")]
+pub struct InputRecording<F> {
+    pub initial_memory: MemoryState,
+    pub bootloader_inputs: Vec<F>,
+    /// The queries, in the order they were made, and their responses.
+    pub queries: Vec<(String, QueryResponse<F>)>,
+}
+
+impl<F: FieldElement> InputRecording<F> {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("Error opening {}: {e}", path.display()))?;
+        serde_cbor::from_reader(BufReader::new(file))
+            .map_err(|e| format!("Error reading recording {}: {e}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let file =
+            File::create(path).map_err(|e| format!("Error creating {}: {e}", path.display()))?;
+        serde_cbor::to_writer(BufWriter::new(file), self)
+            .map_err(|e| format!("Error writing recording {}: {e}", path.display()))
+    }
+
+    /// Returns a query callback that provides the recorded responses in order.
+    /// Fails if the queries differ from the recorded ones, i.e. if the execution diverged.
+    pub fn replay_callback(&self) -> impl QueryCallback<F> + '_ {
+        let next = Mutex::new(0);
+        move |query: &str| {
+            let mut next = next.lock().unwrap();
+            let (recorded_query, response) = self
+                .queries
+                .get(*next)
+                .ok_or_else(|| format!("Replay diverged: query \"{query}\" was not recorded"))?;
+            if recorded_query != query {
+                return Err(format!(
+                    "Replay diverged: expected query \"{recorded_query}\", but got \"{query}\""
+                ));
+            }
+            *next += 1;
+            response.clone()
+        }
+    }
+
+    /// Returns a query callback that answers each query with the first response recorded
+    /// for the same query, independent of the order of the queries.
+    pub fn lookup_callback(&self) -> impl QueryCallback<F> {
+        let mut responses = HashMap::new();
+        for (query, response) in &self.queries {
+            responses
+                .entry(query.clone())
+                .or_insert_with(|| response.clone());
+        }
+        move |query: &str| {
+            responses
+                .get(query)
+                .cloned()
+                .unwrap_or_else(|| Err(format!("Query \"{query}\" was not recorded")))
+        }
+    }
+}
+
+/// Wraps a query callback and records all queries and their responses.
+pub struct InputRecorder<'a, F> {
+    inner: &'a Callback<'a, F>,
+    queries: Mutex<Vec<(String, QueryResponse<F>)>>,
+}
+
+impl<'a, F: FieldElement> InputRecorder<'a, F> {
+    pub fn new(inner: &'a Callback<'a, F>) -> Self {
+        Self {
+            inner,
+            queries: Default::default(),
+        }
+    }
+
+    /// Returns a query callback that forwards all queries to the wrapped callback
+    /// and records them.
+    pub fn callback(&self) -> impl QueryCallback<F> + '_ {
+        |query: &str| {
+            let response = (self.inner)(query);
+            self.queries
+                .lock()
+                .unwrap()
+                .push((query.to_string(), response.clone()));
+            response
+        }
+    }
+
+    /// Returns the recording, given the other inputs that were passed to the execution.
+    pub fn finish(self, initial_memory: MemoryState, bootloader_inputs: &[F]) -> InputRecording<F> {
+        InputRecording {
+            initial_memory,
+            bootloader_inputs: bootloader_inputs.to_vec(),
+            queries: self.queries.into_inner().unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use powdr_number::GoldilocksField;
+
+    use super::*;
+
+    #[test]
+    fn record_and_replay() {
+        let callback = |query: &str| match query {
+            "Input(0)" => Ok(Some(GoldilocksField::from(7))),
+            "Clear" => Ok(None),
+            _ => Err(format!("Unknown query: {query}")),
+        };
+        let recorder = InputRecorder::new(&callback);
+        {
+            let recording_callback = recorder.callback();
+            for query in ["Clear", "Input(0)", "Input(1)"] {
+                let _ = recording_callback(query);
+            }
+        }
+        let recording = recorder.finish(MemoryState::from([(4, 5)]), &[1.into()]);
+
+        let file = mktemp::Temp::new_file().unwrap();
+        recording.write(&file).unwrap();
+        let recording = InputRecording::<GoldilocksField>::read(&file).unwrap();
+        assert_eq!(recording.initial_memory, MemoryState::from([(4, 5)]));
+        assert_eq!(recording.bootloader_inputs, vec![1.into()]);
+
+        let replay = recording.replay_callback();
+        assert_eq!(replay("Clear"), Ok(None));
+        assert_eq!(replay("Input(0)"), Ok(Some(7.into())));
+        assert_eq!(replay("Input(1)"), callback("Input(1)"));
+        assert!(replay("Input(2)").unwrap_err().contains("diverged"));
+
+        let lookup = recording.lookup_callback();
+        assert_eq!(lookup("Input(0)"), Ok(Some(7.into())));
+        assert_eq!(lookup("Clear"), Ok(None));
+        assert!(lookup("Input(2)").unwrap_err().contains("not recorded"));
+    }
+}
@@ -72,7 +72,7 @@ impl From<&str> for Register {
 pub enum Statement<'a, L: AsRef<str>, A: InstructionArgs> {
     DebugLoc { file: u64, line: u64, col: u64 },
     Label(L),
-    Instruction { op: &'a str, args: A },
+    Instruction { address: u32, op: &'a str, args: A },
 }
 
 pub struct MemEntry {
@@ -373,6 +373,7 @@ impl RiscVProgram for ElfProgram {
                     }
                     Either::Right((_, Either::Right(insn))) => {
                         Box::new(std::iter::once(Statement::Instruction {
+                            address: insn.loc.address,
                             op: insn.op,
                             args: WrappedArgs {
                                 args: &insn.args,
@@ -164,7 +164,8 @@ fn translate_program_impl(
                 statements.push(format!(".debug loc {file} {line} {col};"))
             }
             Statement::Label(l) => statements.push(format!("{}:", escape_label(l.as_ref()))),
-            Statement::Instruction { op, args } => {
+            Statement::Instruction { address, op, args } => {
+                statements.push(format!(".debug insn 0x{address:08x};"));
                 let processed_instr = match process_instruction(op, args, runtime) {
                     Ok(s) => s,
                     Err(e) => panic!("Failed to process instruction '{op}'. {e}"),
@@ -7,8 +7,15 @@ use powdr_pipeline::{
     test_util::{run_pilcom_with_backend_variant, BackendVariant},
     Pipeline,
 };
-use powdr_riscv_executor::ProfilerOptions;
-use std::path::{Path, PathBuf};
+use powdr_riscv_executor::{
+    CommandLineFrontend, GdbStub, InputRecorder, InputRecording, ProfilerOptions,
+};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use test_log::test;
 
 use powdr_riscv::{
@@ -231,10 +238,8 @@ fn std_hello_world() {
 }
 
 #[test]
-fn plonky3_verify() {
+fn plonky3_verify_compiles() {
     let case = "plonky3_verify";
-    // Just compile for now
-    // TODO: make it execute like the other tests, and mark it as ignore = "Too slow".
     let temp_dir = Temp::new_dir().unwrap();
     powdr_riscv::compile_rust_crate_to_riscv(
         &format!("tests/riscv_data/{case}/Cargo.toml"),
@@ -243,6 +248,121 @@ fn plonky3_verify() {
     );
 }
 
+/// Proves the given PIL file with plonky3 and returns the input of the verifier guest.
+#[cfg(feature = "plonky3")]
+fn plonky3_verifier_input(
+    file: &str,
+    backend_options: &str,
+) -> powdr_backend::Plonky3VerifierInput<GoldilocksField> {
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .with_tmp_output()
+        .from_file(powdr_pipeline::test_util::resolve_test_file(file))
+        .with_backend(
+            powdr_backend::BackendType::Plonky3,
+            Some(backend_options.to_string()),
+        );
+    let proof = pipeline.compute_proof().cloned().unwrap();
+    let publics = pipeline
+        .publics()
+        .unwrap()
+        .into_iter()
+        .map(|(_, value)| value.unwrap())
+        .collect::<Vec<_>>();
+    let mut verification_key = vec![];
+    pipeline
+        .export_verification_key(&mut verification_key)
+        .unwrap();
+
+    powdr_backend::plonky3_verifier_input(
+        pipeline.optimized_pil().unwrap(),
+        &proof,
+        &mut verification_key.as_slice(),
+        &publics,
+        backend_options.to_string(),
+    )
+    .unwrap()
+}
+
+#[cfg(feature = "plonky3")]
+#[test]
+fn plonky3_verify_execute() {
+    let case = "plonky3_verify";
+    let temp_dir = Temp::new_dir().unwrap();
+    let executable = powdr_riscv::compile_rust_crate_to_riscv(
+        &format!("tests/riscv_data/{case}/Cargo.toml"),
+        &temp_dir,
+        None,
+    );
+    let options = CompilerOptions::new_gl().with_poseidon2();
+    let asm = powdr_riscv::elf::translate(&executable, options);
+
+    // Runs the verifier in the fast executor only, with the parameters for tests.
+    let execute = |inputs: &Vec<powdr_backend::Plonky3VerifierInput<GoldilocksField>>| {
+        let mut pipeline = Pipeline::<GoldilocksField>::default()
+            .from_asm_string(asm.clone(), Some(PathBuf::from(format!("{case}.asm"))))
+            .add_data(1, inputs);
+        let analyzed = pipeline.compute_analyzed_asm().unwrap().clone();
+        powdr_riscv_executor::execute(
+            &analyzed,
+            Default::default(),
+            pipeline.data_callback().unwrap(),
+            &[],
+            None,
+        )
+    };
+
+    let single = vec![plonky3_verifier_input(
+        "pil/fibonacci_with_public.pil",
+        "fast-dev",
+    )];
+    let single_trace_len = execute(&single);
+
+    // Aggregate proofs of two different PILs.
+    let mut inputs = single;
+    inputs.push(plonky3_verifier_input("pil/fibonacci.pil", "fast-dev"));
+    assert!(inputs.iter().all(|input| input.verify().is_ok()));
+    assert!(execute(&inputs) > single_trace_len);
+
+    // A proof of other public values is rejected, without the program failing.
+    let public_value = inputs[1]
+        .public_values
+        .values_mut()
+        .flatten()
+        .flatten()
+        .next()
+        .unwrap();
+    *public_value += GoldilocksField::from(1);
+    assert!(inputs[1].verify().is_err());
+    assert!(execute(&inputs) > 0);
+}
+
+#[cfg(feature = "plonky3")]
+#[test]
+#[ignore = "Too slow"]
+fn plonky3_verify() {
+    let case = "plonky3_verify";
+    let inputs = vec![plonky3_verifier_input("pil/fibonacci_with_public.pil", "")];
+
+    // Verify the proof in powdr, with the Poseidon2 permutation running in the Poseidon2GL machine.
+    let options = CompilerOptions::new_gl().with_poseidon2();
+    verify_riscv_crate_impl(case, options, vec![], Some(vec![(1, inputs)]), false);
+}
+
+#[cfg(feature = "plonky3")]
+#[test]
+#[ignore = "Too slow"]
+fn plonky3_aggregate() {
+    let case = "plonky3_verify";
+    let inputs = vec![
+        plonky3_verifier_input("pil/fibonacci_with_public.pil", ""),
+        plonky3_verifier_input("pil/fibonacci.pil", ""),
+    ];
+
+    // A single powdr proof of the verification of both proofs.
+    let options = CompilerOptions::new_gl().with_poseidon2();
+    verify_riscv_crate_impl(case, options, vec![], Some(vec![(1, inputs)]), false);
+}
+
 #[test]
 #[ignore = "Too slow"]
 fn function_pointer() {
@@ -762,6 +882,205 @@ fn profiler_sanity_check() {
     assert!(!callgrind.unwrap().is_empty());
 }
 
+#[test]
+fn debugger_sanity_check() {
+    let case = "trivial";
+
+    let temp_dir = Temp::new_dir().unwrap();
+    let executable = powdr_riscv::compile_rust_crate_to_riscv(
+        &format!("tests/riscv_data/{case}/Cargo.toml"),
+        &temp_dir,
+        None,
+    );
+
+    let options = CompilerOptions::new(KnownField::GoldilocksField, RuntimeLibs::new(), false);
+    let asm = powdr_riscv::elf::translate(&executable, options);
+
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_asm_string(asm, Some(PathBuf::from(format!("{case}.asm"))));
+    let analyzed = pipeline.compute_analyzed_asm().unwrap().clone();
+
+    let commands = "break syscall:halt\ncontinue\nprint x10\nstep\ncontinue\n";
+    let mut output = vec![];
+    let mut frontend = CommandLineFrontend::new(commands.as_bytes(), &mut output);
+    let trace_len = powdr_riscv_executor::execute_with_debugger(
+        &analyzed,
+        Default::default(),
+        pipeline.data_callback().unwrap(),
+        &[],
+        &mut frontend,
+    );
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Breakpoint 0 added"));
+    assert!(output.contains("Breakpoint 0 hit"));
+    assert!(output.contains("x10 = 0x"));
+    assert!(output.contains("Execution finished"));
+
+    // The debugger does not change the execution.
+    let expected_trace_len = powdr_riscv_executor::execute(
+        &analyzed,
+        Default::default(),
+        pipeline.data_callback().unwrap(),
+        &[],
+        None,
+    );
+    assert_eq!(trace_len, expected_trace_len);
+}
+
+#[test]
+fn gdb_stub_sanity_check() {
+    let case = "trivial";
+
+    let temp_dir = Temp::new_dir().unwrap();
+    let executable = powdr_riscv::compile_rust_crate_to_riscv(
+        &format!("tests/riscv_data/{case}/Cargo.toml"),
+        &temp_dir,
+        None,
+    );
+
+    let options = CompilerOptions::new(KnownField::GoldilocksField, RuntimeLibs::new(), false);
+    let asm = powdr_riscv::elf::translate(&executable, options);
+
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_asm_string(asm, Some(PathBuf::from(format!("{case}.asm"))));
+    let analyzed = pipeline.compute_analyzed_asm().unwrap().clone();
+
+    /// Sends a packet and returns the data of the reply, skipping console output.
+    fn request(stream: &mut TcpStream, data: &str) -> String {
+        let checksum = data.bytes().fold(0u8, |acc, b| acc.wrapping_add(b));
+        write!(stream, "${data}#{checksum:02x}").unwrap();
+        loop {
+            let mut reply = vec![];
+            let mut byte = [0];
+            // Skip the acknowledgement.
+            while byte[0] != b'$' {
+                stream.read_exact(&mut byte).unwrap();
+            }
+            loop {
+                stream.read_exact(&mut byte).unwrap();
+                if byte[0] == b'#' {
+                    break;
+                }
+                reply.push(byte[0]);
+            }
+            stream.read_exact(&mut [0; 2]).unwrap();
+            let reply = String::from_utf8(reply).unwrap();
+            if !reply.starts_with('O') || reply == "OK" {
+                return reply;
+            }
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert!(request(&mut stream, "?").starts_with("T05"));
+        // Step to the first RISC-V instruction and use its address as a breakpoint.
+        let stop = request(&mut stream, "s");
+        let pc = request(&mut stream, "p20");
+        assert_eq!(stop, format!("T0520:{pc};"));
+        let address = u32::from_le_bytes(u32::from_str_radix(&pc, 16).unwrap().to_be_bytes());
+        assert!(address >= 0x10000);
+        assert_eq!(request(&mut stream, &format!("Z0,{address:x},4")), "OK");
+        assert_eq!(request(&mut stream, &format!("z0,{address:x},4")), "OK");
+        // There is no instruction at an odd address.
+        assert_eq!(
+            request(&mut stream, &format!("Z0,{:x},4", address + 1)),
+            "E01"
+        );
+        let command: String = "break syscall:halt"
+            .bytes()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert_eq!(request(&mut stream, &format!("qRcmd,{command}")), "OK");
+        assert!(request(&mut stream, "c").starts_with("T05"));
+        // 33 registers, 4 bytes each.
+        assert_eq!(request(&mut stream, "g").len(), 33 * 8);
+        assert_eq!(request(&mut stream, "m0,4").len(), 8);
+        assert_eq!(request(&mut stream, "c"), "W00");
+    });
+
+    let mut stub = GdbStub::accept(&listener).unwrap();
+    let trace_len = powdr_riscv_executor::execute_with_debugger(
+        &analyzed,
+        Default::default(),
+        pipeline.data_callback().unwrap(),
+        &[],
+        &mut stub,
+    );
+    client.join().unwrap();
+
+    let expected_trace_len = powdr_riscv_executor::execute(
+        &analyzed,
+        Default::default(),
+        pipeline.data_callback().unwrap(),
+        &[],
+        None,
+    );
+    assert_eq!(trace_len, expected_trace_len);
+}
+
+#[test]
+fn record_and_replay_inputs() {
+    let case = "sum_serde";
+
+    let temp_dir = Temp::new_dir().unwrap();
+    let executable = powdr_riscv::compile_rust_crate_to_riscv(
+        &format!("tests/riscv_data/{case}/Cargo.toml"),
+        &temp_dir,
+        None,
+    );
+
+    let options = CompilerOptions::new(KnownField::GoldilocksField, RuntimeLibs::new(), false);
+    let asm = powdr_riscv::elf::translate(&executable, options);
+
+    let data: Vec<u32> = vec![1, 2, 8, 5];
+    let answer = data.iter().sum::<u32>();
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_asm_string(asm.clone(), Some(PathBuf::from(format!("{case}.asm"))))
+        .with_prover_inputs(vec![answer.into()])
+        .add_data(42, &data);
+    let analyzed = pipeline.compute_analyzed_asm().unwrap().clone();
+
+    let recorder = InputRecorder::new(pipeline.data_callback().unwrap());
+    let trace_len = powdr_riscv_executor::execute(
+        &analyzed,
+        Default::default(),
+        &recorder.callback(),
+        &[],
+        None,
+    );
+    let recording_file = temp_dir.join("inputs.cbor");
+    recorder
+        .finish(Default::default(), &[])
+        .write(&recording_file)
+        .unwrap();
+
+    // Replay without any inputs and generate the witness.
+    let recording = InputRecording::<GoldilocksField>::read(&recording_file).unwrap();
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_asm_string(asm, Some(PathBuf::from(format!("{case}.asm"))))
+        .add_query_callback(Arc::new(recording.lookup_callback()));
+    let analyzed = pipeline.compute_analyzed_asm().unwrap().clone();
+    let fixed = pipeline.compute_fixed_cols().unwrap().clone();
+    let pil = pipeline.compute_optimized_pil().unwrap();
+    let execution = powdr_riscv_executor::execute_with_witness(
+        &analyzed,
+        &pil,
+        fixed,
+        recording.initial_memory.clone(),
+        &recording.replay_callback(),
+        &recording.bootloader_inputs,
+        None,
+        None,
+    );
+    assert_eq!(trace_len, execution.trace_len);
+    let mut pipeline = pipeline.add_external_witness_values(execution.trace.into_iter().collect());
+    pipeline.compute_witness().unwrap();
+}
+
 #[cfg(feature = "plonky3")]
 #[test]
 #[ignore = "Too slow"]
@@ -1,7 +1,61 @@
-extern crate powdr_plonky3;
+use powdr_number::{FieldElement, GoldilocksField, LargeInt};
+use powdr_plonky3::VerifierInput;
+use powdr_riscv_runtime::commit::commit;
+use powdr_riscv_runtime::hash::native_hash;
+use powdr_riscv_runtime::io::read_fd;
 
+/// Verifies any number of proofs, e.g. the proofs of the chunks of a computation,
+/// so that a single powdr proof attests to all of them.
 fn main() {
-    // This is just a stub to ensure that the `powdr_plonky3` crate is built and linked correctly.
-    // TODO: perform an actual test.
-    assert_eq!(true, !false);
+    let inputs: Vec<VerifierInput<GoldilocksField>> = read_fd(1);
+    // An invalid proof does not make the program fail: whether all proofs are
+    // valid is committed first, so that the verifier of the powdr proof can check it.
+    commit(inputs.iter().all(|input| input.verify().is_ok()) as u32);
+
+    commit(inputs.len() as u32);
+    for input in &inputs {
+        commit_statement(input);
+    }
+}
+
+/// The input is untrusted, so we commit to the statement that was verified:
+/// the constraints, the verifying key and the public values.
+fn commit_statement(input: &VerifierInput<GoldilocksField>) {
+    let machines = input
+        .machines
+        .iter()
+        .map(|(name, pil)| format!("{name}\n{pil}\n"))
+        .collect::<String>();
+    let verifying_key = serde_cbor::to_vec(&input.verifying_key).unwrap();
+    for word in digest(machines.as_bytes())
+        .into_iter()
+        .chain(digest(&verifying_key))
+    {
+        commit_u64(word);
+    }
+    for value in input.public_values.values().flatten().flatten() {
+        commit_u64(value.to_integer().try_into_u64().unwrap());
+    }
+}
+
+fn commit_u64(value: u64) {
+    commit(value as u32);
+    commit((value >> 32) as u32);
+}
+
+/// Hashes `data` with the same Poseidon sponge that is used for the committed values.
+fn digest(data: &[u8]) -> [u64; 4] {
+    let mut words = vec![data.len() as u64];
+    words.extend(data.chunks(4).map(|chunk| {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        u32::from_le_bytes(bytes) as u64
+    }));
+    let mut state = [0; 12];
+    for chunk in words.chunks(4) {
+        state[4..8].fill(0);
+        state[4..4 + chunk.len()].copy_from_slice(chunk);
+        native_hash(&mut state);
+    }
+    state[..4].try_into().unwrap()
 }